@@ -0,0 +1,131 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// One file or directory stored in an archive
+pub struct ArchiveEntry {
+    pub name: String,  // Path inside the archive, `/`-separated
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+fn format_of(path: &Path) -> Option<Format> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    if name.ends_with(".zip") {
+        Some(Format::Zip)
+    } else if name.ends_with(".tar") {
+        Some(Format::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(Format::TarGz)
+    } else {
+        None
+    }
+}
+
+/// Archives --archives looks inside
+pub fn is_archive(path: &Path) -> bool {
+    format_of(path).is_some()
+}
+
+/// Name of the file a single-file gzip holds, `app.log` for `app.log.gz`. None for anything
+/// else, including `.tar.gz`, which is an archive.
+pub fn gunzipped_name(path: &Path) -> Option<&Path> {
+    let name = path.file_name()?.to_str()?;
+    let lowercase = name.to_lowercase();
+    if !lowercase.ends_with(".gz") || lowercase.ends_with(".tar.gz") {
+        return None;
+    }
+    Some(Path::new(&name[..name.len() - 3]))
+}
+
+/// Visit every entry of an archive in stored order. `wants_content` decides per entry whether
+/// its first `max_bytes` are read and passed along, which is the only chance for tar archives.
+pub fn for_each_entry(
+    path: &Path,
+    max_bytes: u64,
+    wants_content: impl Fn(&ArchiveEntry) -> bool,
+    visit: impl FnMut(ArchiveEntry, Option<Vec<u8>>),
+) -> io::Result<()> {
+    match format_of(path) {
+        Some(Format::Zip) => zip_entries(path, max_bytes, wants_content, visit),
+        Some(Format::Tar) => tar_entries(BufReader::new(File::open(path)?), max_bytes, wants_content, visit),
+        Some(Format::TarGz) => {
+            let decoder = flate2::read::GzDecoder::new(BufReader::new(File::open(path)?));
+            tar_entries(decoder, max_bytes, wants_content, visit)
+        }
+        None => Err(io::Error::new(io::ErrorKind::InvalidInput, "not an archive ff can read")),
+    }
+}
+
+fn read_prefix(reader: impl Read, max_bytes: u64) -> io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    reader.take(max_bytes).read_to_end(&mut content)?;
+    Ok(content)
+}
+
+fn zip_entries(
+    path: &Path,
+    max_bytes: u64,
+    wants_content: impl Fn(&ArchiveEntry) -> bool,
+    mut visit: impl FnMut(ArchiveEntry, Option<Vec<u8>>),
+) -> io::Result<()> {
+    let mut archive = zip::ZipArchive::new(BufReader::new(File::open(path)?)).map_err(io::Error::other)?;
+
+    for index in 0..archive.len() {
+        // Raw access reads the header without decrypting or decompressing anything
+        let entry = {
+            let file = archive.by_index_raw(index).map_err(io::Error::other)?;
+            ArchiveEntry {
+                name: file.name().trim_end_matches('/').to_string(),
+                is_dir: file.is_dir(),
+                size: file.size(),
+                modified: file.last_modified().and_then(|time| {
+                    let date = crate::filters::parse_date(&format!("{:04}-{:02}-{:02}", time.year(), time.month(), time.day())).ok()?;
+                    date.checked_add(Duration::from_secs(time.hour() as u64 * 3600 + time.minute() as u64 * 60 + time.second() as u64))
+                }),
+            }
+        };
+
+        // Encrypted or unsupported entries are still listed, just without content
+        let content = if wants_content(&entry) {
+            archive.by_index(index).ok().and_then(|file| read_prefix(file, max_bytes).ok())
+        } else {
+            None
+        };
+        visit(entry, content);
+    }
+    Ok(())
+}
+
+fn tar_entries(
+    reader: impl Read,
+    max_bytes: u64,
+    wants_content: impl Fn(&ArchiveEntry) -> bool,
+    mut visit: impl FnMut(ArchiveEntry, Option<Vec<u8>>),
+) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+
+    for entry in archive.entries()? {
+        let mut file = entry?;
+        let header = file.header();
+        let entry = ArchiveEntry {
+            name: file.path()?.to_string_lossy().trim_end_matches('/').to_string(),
+            is_dir: header.entry_type().is_dir(),
+            size: file.size(),
+            modified: header.mtime().ok().and_then(|secs| SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs))),
+        };
+
+        let content = if wants_content(&entry) { read_prefix(&mut file, max_bytes).ok() } else { None };
+        visit(entry, content);
+    }
+    Ok(())
+}
@@ -1,9 +1,14 @@
 use clap::{Parser, Subcommand, ValueEnum};
+use crate::file_types::Category;
+use crate::syntax::Scope;
+use crate::filters::{AgeFilter, SizeFilter};
+use crate::walker::Backend;
+use crate::export_env::{self, EnvShell};
 
 #[derive(Parser)]
 #[command(
     name = "ff",
-    version = "0.0.1-beta.1",
+    version,
     about = "Fast File - Locate your filesystem (BLAZINGLY FAST🔥)",
     long_about = None,
     help_template = "\
@@ -23,6 +28,10 @@ EXAMPLES:
     ff main --path /codes       Search for 'main' in /codes directory
     ff package --copy           Copy the selected file path to clipboard
     ff \"*.rs\" --files-only     Find only Rust files (use quotes for wildcards)
+    ff stats --path /codes      Show file counts and sizes by extension
+    ff timeline .rs             When were the Rust files here last changed
+    ff -q 'name:config ext:toml content:\"port\" size:<1M mtime:<7d'
+                                Whole search as one query string
 "
 )]
 pub struct Cli {
@@ -36,10 +45,54 @@ pub struct Cli {
     #[arg(short, long, value_name = "PATH")]
     pub path: Option<String>,
 
-    /// [Search] Include hidden files and directories (.git, .env, etc.)
+    /// [Navigation] Search from the enclosing project or workspace root (Cargo.toml, package.json, .git, ...)
+    #[arg(long, conflicts_with = "path")]
+    pub project: bool,
+
+    /// [Search] Whole search as one query: name: content: ext: size: mtime: mode: type: hidden:
+    #[arg(short = 'q', long, value_name = "QUERY")]
+    pub query: Option<String>,
+
+    /// [Search] Only files with these extensions, comma-separated (e.g. -e rs,toml,md); also skips scanning the rest
+    #[arg(short = 'e', long, value_name = "EXTS")]
+    pub ext: Vec<String>,
+
+    /// [Search] Only keep content matches in comments, string literals or the code around them (common languages only)
+    #[arg(long = "in", value_name = "PART", value_enum)]
+    pub scope: Option<Scope>,
+
+    /// [Search] Only files of these kinds, comma-separated (e.g. --type video or --type image,doc)
+    #[arg(long = "type", value_name = "KINDS", value_enum, value_delimiter = ',')]
+    pub file_type: Vec<Category>,
+
+    /// [Search] Only files larger (+) or smaller (-) than SIZE, e.g. --size +10M --size -1G (repeatable)
+    #[arg(long, value_name = "SIZE", allow_hyphen_values = true, value_parser = SizeFilter::parse)]
+    pub size: Vec<SizeFilter>,
+
+    /// [Search] Only entries modified within an age or after a date, e.g. --newer-than 2d or 2024-01-01
+    #[arg(long, value_name = "WHEN", value_parser = AgeFilter::newer_than)]
+    pub newer_than: Option<AgeFilter>,
+
+    /// [Search] Only entries modified longer ago than an age or before a date, e.g. --older-than 1y
+    #[arg(long, value_name = "WHEN", value_parser = AgeFilter::older_than)]
+    pub older_than: Option<AgeFilter>,
+
+    /// [Search] find-style modification filter: -2d (newer), +30d (older), or <DATE / >DATE (repeatable)
+    #[arg(long, value_name = "WHEN", allow_hyphen_values = true, value_parser = AgeFilter::parse)]
+    pub mtime: Vec<AgeFilter>,
+
+    /// [Search] Include hidden files and directories (.git, .env, Windows hidden/system attributes)
     #[arg(short = 'h', long)]
     pub hidden: bool,
 
+    /// [Search] Follow symbolic links into the directories they point to (overrides config follow_symlinks)
+    #[arg(long, global = true, overrides_with = "no_follow")]
+    pub follow: bool,
+
+    /// [Search] Don't follow symbolic links, even when config follow_symlinks is on
+    #[arg(long, global = true, overrides_with = "follow")]
+    pub no_follow: bool,
+
     /// [Search] Only match files (exclude directories)
     #[arg(short = 'f', long)]
     pub files_only: bool,
@@ -48,21 +101,166 @@ pub struct Cli {
     #[arg(short = 'd', long)]
     pub dirs_only: bool,
 
-    /// [Search] Matching mode: fuzzy or exact
-    #[arg(short = 'm', long, value_enum, default_value = "fuzzy")]
-    pub match_mode: MatchMode,
+    /// [Search] Ignore the config's ignore_directories rules and build-output heuristics for this search
+    #[arg(long, global = true)]
+    pub no_ignore_dirs: bool,
+
+    /// [Search] Ignore the config's ignore_file_patterns rules for this search
+    #[arg(long, global = true)]
+    pub no_ignore_files: bool,
+
+    /// [Search] Search entries listed in .gitignore, .ignore, .ffignore or git's global excludes too
+    #[arg(long, global = true)]
+    pub no_ignore: bool,
+
+    /// [Search] Rerun the search as admin (sudo, or a UAC prompt on Windows); only in directories listed in the per-user elevate_roots.json
+    #[arg(long)]
+    pub elevate: bool,
+
+    /// Set by --elevate on the search it relaunches: no hooks run, and on Windows the window waits before closing
+    #[arg(long, hide = true)]
+    pub elevated: bool,
+
+    /// [Search] Only search files git tracks (git ls-files), leaving out build output and other untracked files
+    #[arg(long, conflicts_with_all = ["git_dirty", "git_diff"])]
+    pub git_tracked: bool,
+
+    /// [Search] Only search files with uncommitted changes, modified or staged (git diff HEAD)
+    #[arg(long, conflicts_with = "git_diff")]
+    pub git_dirty: bool,
+
+    /// [Search] Only search files that differ from a git ref in the working tree (e.g. main, HEAD~3)
+    #[arg(long, value_name = "REF")]
+    pub git_diff: Option<String>,
+
+    /// [Search] Search inside a directory the config or the build-output heuristics ignore (repeatable, e.g. --include-dir target)
+    #[arg(long, value_name = "NAME", global = true)]
+    pub include_dir: Vec<String>,
+
+    /// [Search] Leave out entries whose name matches a glob or contains the text (repeatable, e.g. --exclude "*.lock")
+    #[arg(short = 'E', long, value_name = "PATTERN", global = true)]
+    pub exclude: Vec<String>,
+
+    /// [Search] Search directories marked as caches with a CACHEDIR.TAG file (skipped by default)
+    #[arg(long, global = true)]
+    pub include_caches: bool,
+
+    /// [Search] Only descend into these top-level directories of the root (e.g. --only-dirs src,tests)
+    #[arg(long, value_name = "NAMES", value_delimiter = ',', global = true)]
+    pub only_dirs: Vec<String>,
+
+    /// [Search] Only descend NUM levels below the root (1 = entries directly in the root)
+    #[arg(long, value_name = "NUM", global = true)]
+    pub max_depth: Option<usize>,
 
-    /// [Search] Search inside file contents (slower but more thorough)
+    /// [Search] Only report entries at least NUM levels below the root (2 skips the root's own entries)
+    #[arg(long, value_name = "NUM", global = true)]
+    pub min_depth: Option<usize>,
+
+    /// [Search] Leave out symlinks, and on Windows junctions, cloud placeholders and other reparse points
+    #[arg(long, global = true)]
+    pub skip_reparse: bool,
+
+    /// [Search] Matching mode: fuzzy, exact or regex (default: config default_search_options.match_mode)
+    #[arg(short = 'm', long, value_enum)]
+    pub match_mode: Option<MatchMode>,
+
+    /// [Search] Drop fuzzy name matches scoring under NUM per pattern character (about 16-30 is a clean match, 0 keeps all)
+    #[arg(long, value_name = "NUM")]
+    pub min_score: Option<i64>,
+
+    /// [Search] Match the pattern against the whole path relative to the root, not just the file name
+    #[arg(long, global = true)]
+    pub full_path: bool,
+
+    /// [Search] Match names by their ASCII form, so `muller` finds Müller.pdf and `sao-paulo` finds São Paulo.txt
+    #[arg(long, global = true)]
+    pub transliterate: bool,
+
+    /// [Search] Match names and content case-sensitively (default: config default_search_options)
+    #[arg(long, conflicts_with_all = ["ignore_case", "smart_case"], global = true)]
+    pub case_sensitive: bool,
+
+    /// [Search] Match case-insensitively, even for patterns with uppercase letters
+    #[arg(long, conflicts_with = "smart_case", global = true)]
+    pub ignore_case: bool,
+
+    /// [Search] Match case-sensitively only when the pattern has an uppercase letter (the default)
+    #[arg(long, global = true)]
+    pub smart_case: bool,
+
+    /// [Search] Search inside file contents (slower but more thorough). Repeat it to find any of several literal patterns in one pass, each match tagged with its pattern
     #[arg(long, value_name = "TEXT")]
-    pub content: Option<String>,
+    pub content: Vec<String>,
+
+    /// [Search] Also content-search cloud-only placeholder files (downloads them)
+    #[arg(long)]
+    pub hydrate: bool,
+
+    /// [Search] Also match the files inside zip, tar and tar.gz archives, shown as archive.zip!/path/inside
+    #[arg(long)]
+    pub archives: bool,
+
+    /// [Search] Let --content regexes span lines (e.g. 'fn main\s*\(\)\s*\{'), reading each file whole; matches are shown at their first line
+    #[arg(long)]
+    pub multiline: bool,
+
+    /// [Search] List the files --content does NOT match, like grep -L (e.g. configs missing a setting)
+    #[arg(short = 'L', long, requires = "content", conflicts_with_all = ["hybrid", "count", "group_by_match"])]
+    pub files_without_match: bool,
 
     /// [Search] Search both filename and content simultaneously
     #[arg(long)]
     pub hybrid: bool,
 
-    /// [Output] Maximum number of results to show
-    #[arg(short = 'l', long, default_value = "10", value_name = "NUM")]
-    pub limit: usize,
+    /// [Output] Print matching paths separated by NUL bytes, raw and undecorated (for xargs -0)
+    #[arg(long, conflicts_with_all = ["group_by_match", "copy", "terminal", "link_to"])]
+    pub print0: bool,
+
+    /// [Output] Print the top results as shell variables NAME_1, NAME_2, ... and NAME_COUNT, for eval "$(ff ... --export-env NAME)"
+    #[arg(long, value_name = "NAME", value_parser = export_env::parse_name,
+          conflicts_with_all = ["print0", "group_by_match", "copy", "terminal", "link_to"])]
+    pub export_env: Option<String>,
+
+    /// [Output] Assignment syntax for --export-env: sh or powershell (default: powershell on Windows, sh elsewhere)
+    #[arg(long, value_enum, value_name = "SHELL", requires = "export_env")]
+    pub env_shell: Option<EnvShell>,
+
+    /// [Output] Also write the search settings, totals and results with their matching lines to FILE as an md or html report
+    #[arg(long, num_args = 2, value_names = ["FORMAT", "FILE"])]
+    pub report: Option<Vec<String>>,
+
+    /// [Output] Under each matching directory (e.g. with --dirs-only), list its first NUM entries, skipping ignored ones
+    #[arg(long, value_name = "NUM")]
+    pub peek: Option<usize>,
+
+    /// [Output] Group content matches by the distinct matched text, with counts and examples
+    #[arg(long, requires = "content")]
+    pub group_by_match: bool,
+
+    /// [Output] Only count content matches, printing the number per file, most first (faster, no lines are kept)
+    #[arg(long, conflicts_with = "group_by_match")]
+    pub count: bool,
+
+    /// [Output] Print the effective settings and where each one came from (flag, config or default)
+    #[arg(long)]
+    pub show_effective: bool,
+
+    /// [Output] Print the resolved search plan (roots, rules, filters, threads) without scanning
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// [Output] Maximum number of results to show (default: config default_search_options.limit)
+    #[arg(short = 'l', long, value_name = "NUM")]
+    pub limit: Option<usize>,
+
+    /// [Output] Show the first NUM files with content matches, a content-only search stops scanning once it has them
+    #[arg(long, value_name = "NUM", requires = "content", conflicts_with_all = ["limit", "limit_matches"])]
+    pub limit_files: Option<usize>,
+
+    /// [Output] Show the first NUM content matches across files, a content-only search stops scanning once it has them
+    #[arg(long, value_name = "NUM", requires = "content", conflicts_with_all = ["limit", "files_without_match", "group_by_match"])]
+    pub limit_matches: Option<usize>,
 
     /// [Output] Copy selected path to clipboard instead of navigating
     #[arg(short = 'c', long)]
@@ -72,14 +270,46 @@ pub struct Cli {
     #[arg(long)]
     pub details: bool,
 
+    /// [Output] Print a one-line header instead of the SEARCH SUMMARY block
+    #[arg(long)]
+    pub no_summary: bool,
+
+    /// [Output] Leave the FIGlet banner off the welcome screen (config output_options.banner)
+    #[arg(long)]
+    pub no_banner: bool,
+
+    /// [Output] Print all results at once instead of paging long output
+    #[arg(long)]
+    pub no_pager: bool,
+
+    /// [Output] After the results, show how many matched per file type (e.g. "12 .rs, 4 .toml, 3 dirs")
+    #[arg(long)]
+    pub type_breakdown: bool,
+
+    /// [Output] Keep only results of the most common file type
+    #[arg(long)]
+    pub only_top_type: bool,
+
+    /// [Output] Don't run the on_select and post_search hooks from hooks.json
+    #[arg(long, global = true)]
+    pub no_hooks: bool,
+
     /// [Output] Open new terminal window
     #[arg(short = 't', long)]
     pub terminal: bool,
 
-    /// [Performance] Use parallel processing with Rayon
+    /// [Output] Symlink the selected result into DIR (a shortcut on Windows without symlink rights)
+    #[arg(long, value_name = "DIR")]
+    pub link_to: Option<std::path::PathBuf>,
+
+    /// [Performance] Use parallel processing with Rayon (default: config default_search_options.parallel)
     #[arg(long = "pl")]
     pub parallel: bool,
 
+    /// [Performance] Directory traversal backend (default: config walker_backend)
+    #[arg(long, value_enum, global = true)]
+    pub backend: Option<Backend>,
+
     /// [Performance] Number of threads to use (default: auto-detect)
     #[arg(long = "th", value_name = "THREADS")]
     pub threads: Option<usize>,
@@ -87,17 +317,137 @@ pub struct Cli {
     /// [Performance] Use maximum CPU cores (CPU_COUNT * 2)
     #[arg(long = "mx")]
     pub max_cpu: bool,
+
+    /// [Performance] Content-scan at most NUM files at once in parallel mode (default: one per thread)
+    #[arg(long, value_name = "NUM", value_parser = clap::value_parser!(u64).range(1..))]
+    pub content_threads: Option<u64>,
+
+    /// [Performance] Stop after visiting NUM entries (0 = no cap, default: config max_files_per_search)
+    #[arg(long, value_name = "NUM")]
+    pub max_files: Option<usize>,
+
+    /// [Performance] Read every entry of huge directories instead of stopping at config max_entries_per_dir
+    #[arg(long, global = true)]
+    pub no_dir_cap: bool,
+
+    /// [Performance] Skip directories that take longer than MS to open, with --backend ignore (0 disables, default: config)
+    #[arg(long, value_name = "MS", global = true)]
+    pub stall_timeout: Option<u64>,
+
+    /// [Output] List the affected paths in the end-of-search issue summary, not just the counts
+    #[arg(short = 'v', long)]
+    pub verbose: bool,
+
+    /// [Performance] Rescan every file instead of reusing cached content-search results
+    #[arg(long)]
+    pub no_cache: bool,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
-    // Reserve space for future subcommands
+    /// Report file counts and sizes by extension, largest directories and file ages
+    Stats {
+        /// Directory to analyze (default: current directory)
+        #[arg(short, long, value_name = "PATH")]
+        path: Option<String>,
+
+        /// Include hidden files and directories
+        #[arg(long)]
+        hidden: bool,
+
+        /// Number of rows to show per section
+        #[arg(long, default_value = "10", value_name = "NUM")]
+        top: usize,
+    },
+
+    /// Find it anywhere: search the whole home folder (or a drive) in parallel within a time budget,
+    /// including hidden directories but skipping OS ones (config deep_search)
+    Deep {
+        /// File name pattern
+        pattern: String,
+
+        /// Where to start (default: your home directory)
+        #[arg(short, long, value_name = "PATH")]
+        path: Option<String>,
+    },
+
+    /// Histogram of when files were last modified, plus the most recently changed ones
+    Timeline {
+        /// Only count files whose name matches this pattern (default: all files)
+        pattern: Option<String>,
+
+        /// Directory to analyze (default: current directory)
+        #[arg(short, long, value_name = "PATH")]
+        path: Option<String>,
+
+        /// Include hidden files and directories
+        #[arg(long)]
+        hidden: bool,
+
+        /// Number of most recently modified files to list
+        #[arg(long, default_value = "10", value_name = "NUM")]
+        recent: usize,
+    },
+
+    /// Check source files for license headers (SPDX identifiers or standard notices) and list
+    /// the files missing one or declaring a different license than the rest
+    Licenses {
+        /// Directory to scan (default: current directory)
+        #[arg(short, long, value_name = "PATH")]
+        path: Option<String>,
+
+        /// Include hidden files and directories
+        #[arg(long)]
+        hidden: bool,
+
+        /// SPDX identifier every file should declare, e.g. MIT or Apache-2.0 (default: the most common one)
+        #[arg(long, value_name = "SPDX")]
+        expect: Option<String>,
+
+        /// Lines read from the top of each file looking for the header
+        #[arg(long, default_value = "30", value_name = "NUM")]
+        header_lines: usize,
+    },
+
+    /// Manage ff's settings
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Carry settings between machines
+    Sync {
+        #[command(subcommand)]
+        action: SyncCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SyncCommand {
+    /// Write the current settings to a portable file
+    Export {
+        /// Where to write them (default: stdout)
+        file: Option<std::path::PathBuf>,
+    },
+
+    /// Merge settings exported on another machine into this config: lists gain the missing
+    /// entries, other settings take the imported value
+    Import {
+        /// A file written by `ff config sync export`
+        file: std::path::PathBuf,
+    },
 }
 
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(ValueEnum, Clone, Debug, Default)]
 pub enum MatchMode {
     /// Fuzzy matching (default) - finds partial matches
+    #[default]
     Fuzzy,
     /// Exact matching - only exact substring matches
     Exact,
+    /// Regular expressions for the file name and --content, e.g. "^test_.*\.rs$"
+    Regex,
 }
\ No newline at end of file
@@ -1,33 +1,154 @@
 use serde::{Serialize, Deserialize};
+use std::fs::File;
 use std::path::PathBuf;
+use clap::ValueEnum;
+use crate::cli::MatchMode;
+use crate::ignore_rules::IgnoreRules;
+use crate::hooks::Hooks;
 
+/// Config file name, looked up in the current working directory
+pub const CONFIG_FILE: &str = "ff-config.json";
+
+/// Defaults for search flags that weren't given on the command line
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct DefaultSearchOptions {
     pub match_mode: String,       // "fuzzy" or "exact"
-    pub case_sensitive: bool,
+    pub case_sensitive: bool,     // Always match case, overrides smart_case
+    pub smart_case: bool,         // Match case only for patterns with an uppercase letter
+    pub full_path: bool,          // Match name patterns against the path relative to the root
+    pub transliterate: bool,      // Match names by their ASCII form (accents dropped, - and _ as spaces)
+    pub min_score: i64,           // Fuzzy score needed per pattern character, 0 keeps weak matches
+    pub limit: usize,
+    pub parallel: bool,
+}
+
+impl Default for DefaultSearchOptions {
+    fn default() -> Self {
+        Self {
+            match_mode: "fuzzy".to_string(),
+            case_sensitive: false,
+            smart_case: true,
+            full_path: false,
+            transliterate: false,
+            min_score: 0,
+            limit: 10,
+            parallel: false,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct OutputOptions {
     pub show_details: bool,
+    pub show_summary: bool,  // Multi-line SEARCH SUMMARY before results, false for a one-line header
+    pub banner: bool,  // FIGlet banner on the welcome screen (skipped anyway on narrow or dumb terminals)
     pub color_theme: String,
     pub max_content_matches: usize,
     pub max_line_length: usize,
+    pub clipboard_backend: String,  // "auto", "system" or "osc52" (terminal escape sequence, works over SSH)
+    pub redact_patterns: Vec<String>,  // Regexes masked as [REDACTED] in content previews and reports (tokens, emails)
+}
+
+/// Settings for `ff deep`, the whole-home / whole-drive preset
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DeepSearchOptions {
+    pub time_limit_secs: u64,          // Hard stop for the walk, 0 = none
+    pub skip_directories: Vec<String>, // OS and system locations, added to ignore_directories
+}
+
+impl Default for DeepSearchOptions {
+    fn default() -> Self {
+        Self {
+            time_limit_secs: 120,
+            skip_directories: [
+                // Unix virtual and system trees (anchored, they only matter at a drive root)
+                "/proc", "/sys", "/dev", "/run", "/snap", "/var/lib", "/private/var", "/System",
+                // Windows
+                "Windows", "$Recycle.Bin", "System Volume Information", "Program Files",
+                "Program Files (x86)", "ProgramData", "AppData/Local/Temp",
+                // Caches and trash in user folders
+                "Library/Caches", ".Trash", ".local/share/Trash",
+            ]
+            .iter()
+            .map(|dir| dir.to_string())
+            .collect(),
+        }
+    }
 }
 
+/// How --project finds the root to search from
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ProjectRoots {
+    pub markers: Vec<String>,            // Files or directories marking a project root, nearest wins
+    pub workspace_markers: Vec<String>,  // "file" or "file:text" marking an enclosing workspace root
+}
+
+impl Default for ProjectRoots {
+    fn default() -> Self {
+        Self {
+            markers: [".git", "Cargo.toml", "package.json", "pyproject.toml", "go.mod"]
+                .iter()
+                .map(|marker| marker.to_string())
+                .collect(),
+            workspace_markers: ["Cargo.toml:[workspace]", "package.json:\"workspaces\"", "pnpm-workspace.yaml"]
+                .iter()
+                .map(|marker| marker.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            show_details: true,
+            show_summary: true,
+            banner: true,
+            color_theme: "default".to_string(),
+            max_content_matches: 3,
+            max_line_length: 100,
+            clipboard_backend: "auto".to_string(),
+            redact_patterns: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct Config {
     pub ignore_directories: Vec<String>,
     pub ignore_file_patterns: Vec<String>,
-    pub max_memory_mb: usize,
+    pub heuristic_ignores: bool,  // Also skip directories with build-output markers (CACHEDIR.TAG, pyvenv.cfg, ...)
+    pub respect_ignore_files: bool,  // Skip what .gitignore, .ignore, .ffignore and git's excludes list
+    pub max_memory_mb: usize,  // Budget for the path list a parallel search collects before matching, 0 = none
     pub max_files_per_search: usize,
+    pub search_time_limit_secs: u64,  // Ask (or warn, when piped) after this long, 0 disables
     pub max_parallel_threads: Option<usize>,
     pub max_file_size_mb: u64,
+    pub max_content_bytes_per_file: u64,  // Content scans stop after this many bytes, 0 = no limit
+    pub max_total_content_bytes: u64,     // Content bytes read per search before skipping the rest, 0 = no limit
     pub include_hidden: bool,
     pub follow_symlinks: bool,
-    pub content_search_extensions: Vec<String>,
+    pub dir_stall_timeout_ms: u64,  // 0 disables stall detection, only the ignore backend checks
+    pub max_entries_per_dir: usize,  // Entries read from one directory before the rest is skipped, 0 = no cap
+    pub walker_backend: String,     // "walkdir" or "ignore"
+    pub content_cache_entries: usize,  // Files remembered by the content cache, 0 disables it
+    pub content_search_extensions: Vec<String>,  // Always content-searched
+    pub content_skip_extensions: Vec<String>,    // Never content-searched
+    pub probe_unknown_files: bool,               // Search other files too, unless their first block looks binary
+    pub search_compressed: bool,                 // Content-search .gz files (not .tar.gz) decompressed, judged by the name inside
     pub default_search_options: DefaultSearchOptions,
     pub output_options: OutputOptions,
+    #[serde(skip_serializing)]
+    pub hooks: Hooks,  // The user's hooks.json; a hooks section in ff-config.json is only read to warn about it
+    pub deep_search: DeepSearchOptions,
+    pub project_roots: ProjectRoots,
+    #[serde(skip_serializing)]
+    pub elevate_roots: Vec<String>,  // The user's elevate_roots.json, where --elevate may rerun a search as admin; only read here to warn
 }
 
 impl Default for Config {
@@ -53,12 +174,21 @@ impl Default for Config {
                 "thumbs.db".to_string(),
                 ".DS_Store".to_string(),
             ],
+            heuristic_ignores: true,
+            respect_ignore_files: true,
             max_memory_mb: 1024,
             max_files_per_search: 50000,
+            search_time_limit_secs: 30,
             max_parallel_threads: None,  // Auto-detect
             max_file_size_mb: 10,
+            max_content_bytes_per_file: 16 * 1024 * 1024,
+            max_total_content_bytes: 0,
             include_hidden: false,
             follow_symlinks: false,
+            dir_stall_timeout_ms: 5000,
+            max_entries_per_dir: 100_000,
+            walker_backend: "walkdir".to_string(),
+            content_cache_entries: 20000,
             content_search_extensions: vec![
                 ".rs".to_string(),
                 ".py".to_string(),
@@ -76,16 +206,19 @@ impl Default for Config {
                 ".toml".to_string(),
                 ".cfg".to_string(),
             ],
-            default_search_options: DefaultSearchOptions {
-                match_mode: "fuzzy".to_string(),
-                case_sensitive: false,
-            },
-            output_options: OutputOptions {
-                show_details: true,
-                color_theme: "default".to_string(),
-                max_content_matches: 3,
-                max_line_length: 100,
-            },
+            content_skip_extensions: [
+                ".png", ".jpg", ".jpeg", ".gif", ".ico", ".pdf", ".zip", ".gz", ".7z", ".tar",
+                ".exe", ".dll", ".so", ".dylib", ".o", ".class", ".jar", ".mp3", ".mp4", ".mov",
+                ".iso", ".bin",
+            ].iter().map(|ext| ext.to_string()).collect(),
+            probe_unknown_files: true,
+            search_compressed: true,
+            default_search_options: DefaultSearchOptions::default(),
+            output_options: OutputOptions::default(),
+            hooks: Hooks::default(),
+            deep_search: DeepSearchOptions::default(),
+            project_roots: ProjectRoots::default(),
+            elevate_roots: Vec::new(),
         }
     }
 }
@@ -93,28 +226,50 @@ impl Default for Config {
 impl Config {
     /// Main entry point - handles all config logic with safeguards
     pub fn load_with_safeguard() -> Self {
-        let config_path = PathBuf::from("ff-config.json");
+        let mut config = Self::load_or_create();
+        if !config.hooks.is_empty() {
+            let user_file = Hooks::user_file().map(|path| path.display().to_string()).unwrap_or_default();
+            eprintln!("⚠️  Ignoring hooks in {}, a config in the working directory can come with any checkout. Hooks are read from {}",
+                      CONFIG_FILE, user_file);
+        }
+        config.hooks = Hooks::load_user();
+        if !config.elevate_roots.is_empty() {
+            eprintln!("⚠️  Ignoring elevate_roots in {}, a config in the working directory can come with any checkout. They are read from {}",
+                      CONFIG_FILE, crate::elevate::roots_file_name());
+        }
+        config.elevate_roots = crate::elevate::load_user_roots();
+        config
+    }
+
+    fn load_or_create() -> Self {
+        let config_path = PathBuf::from(CONFIG_FILE);
 
         if config_path.exists() {
             match Self::load_from_file(&config_path) {
                 Ok(config) => {
-                    println!("📁 Loaded config from: {}", config_path.display());
+                    eprintln!("📁 Loaded config from: {}", config_path.display());
                     config
                 },
                 Err(_) => {
-                    println!("⚠️  Invalid config file detected, regenerating default config");
+                    // Keep the broken file around instead of silently losing the user's settings
+                    let backup_path = PathBuf::from(format!("{}.bak", CONFIG_FILE));
+                    match std::fs::rename(&config_path, &backup_path) {
+                        Ok(()) => eprintln!("⚠️  Invalid config file detected, moved it to {} and regenerating default config",
+                                           backup_path.display()),
+                        Err(_) => eprintln!("⚠️  Invalid config file detected, regenerating default config"),
+                    }
                     let default_config = Self::default();
                     if let Err(e) = default_config.save_to_file(&config_path) {
-                        println!("⚠️  Warning: Could not save config: {}", e);
+                        eprintln!("⚠️  Warning: Could not save config: {}", e);
                     }
                     default_config
                 }
             }
         } else {
-            println!("📁 Config file not found, creating default config");
+            eprintln!("📁 Config file not found, creating default config");
             let default_config = Self::default();
             if let Err(e) = default_config.save_to_file(&config_path) {
-                println!("⚠️  Warning: Could not save config: {}", e);
+                eprintln!("⚠️  Warning: Could not save config: {}", e);
             }
             default_config
         }
@@ -127,46 +282,64 @@ impl Config {
         Ok(config)
     }
 
-    /// Save config to specific file path
-    pub fn save_to_file(&self, path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    /// Save config to specific file path, atomically and under a lock shared by all ff processes
+    pub fn save_to_file(&self, path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
         let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(path, content)?;
-        println!("💾 Config saved to: {}", path.display());
+
+        // Held until the end of the function, the lock is released when the file is dropped
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let lock = File::create(lock_path)?;
+        lock.lock()?;
+
+        crate::util::write_atomically(path, &content)?;
+        eprintln!("💾 Config saved to: {}", path.display());
         Ok(())
     }
 
     // Helper methods for using the config
-    pub fn should_ignore_directory(&self, dir_name: &str) -> bool {
-        self.ignore_directories.iter().any(|pattern| dir_name.contains(pattern))
-    }
-
-    pub fn should_ignore_file(&self, file_name: &str) -> bool {
-        self.ignore_file_patterns.iter().any(|pattern| {
-            if pattern.starts_with("*.") {
-                let ext = &pattern[2..];
-                file_name.ends_with(ext)
-            } else {
-                file_name.contains(pattern)
-            }
-        })
+    pub fn ignore_rules(&self) -> IgnoreRules {
+        IgnoreRules::from_config(self)
     }
 
+    /// Listed extensions are always searched and skipped ones never are. Anything else is
+    /// searched when probing is on, binary files then get dropped after reading their first block.
     pub fn is_content_searchable(&self, file_path: &std::path::Path) -> bool {
+        let listed = |list: &[String], ext: &str| list.iter().any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(ext));
+
+        // Rotated logs and other gzipped files count as what they hold, app.log.gz like app.log
+        if let Some(inner) = crate::archive::gunzipped_name(file_path) {
+            return self.search_compressed && self.is_content_searchable(inner);
+        }
+
         if let Some(ext) = file_path.extension().and_then(|s| s.to_str()) {
-            let ext_with_dot = format!(".{}", ext);
-            self.content_search_extensions.contains(&ext_with_dot)
-        } else {
+            if listed(&self.content_search_extensions, ext) {
+                return true;
+            }
+            if listed(&self.content_skip_extensions, ext) {
+                return false;
+            }
+            // Formats ff extracts text from are searchable even with probing off
+            if crate::extract::extractor_for(file_path).is_some() {
+                return true;
+            }
+        } else if let Some(name) = file_path.file_name().and_then(|n| n.to_str()) {
             // Files without extension - check common names
-            if let Some(name) = file_path.file_name().and_then(|n| n.to_str()) {
-                matches!(name, "README" | "Makefile" | "Dockerfile" | "LICENSE")
-            } else {
-                false
+            if matches!(name, "README" | "Makefile" | "Dockerfile" | "LICENSE") {
+                return true;
             }
         }
+
+        self.probe_unknown_files
+    }
+
+    /// Configured default match mode, unknown names fall back to fuzzy
+    pub fn default_match_mode(&self) -> MatchMode {
+        MatchMode::from_str(&self.default_search_options.match_mode, true).unwrap_or_default()
     }
 
     pub fn get_effective_thread_count(&self, cli_threads: Option<usize>, max_cpu_flag: bool) -> usize {
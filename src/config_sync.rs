@@ -0,0 +1,155 @@
+use std::path::Path;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::config::{Config, CONFIG_FILE};
+
+/// Layout version of the export file, bumped when an older ff couldn't read it
+const BUNDLE_FORMAT: u64 = 1;
+
+/// Settings that run commands or allow admin rights. An import never sets them, a bundle
+/// from another machine (or from someone else) has to be checked and copied by hand.
+const PROTECTED_KEYS: &[&str] = &["hooks", "elevate_roots"];
+
+/// A portable copy of the ff setup, written by `ff config sync export`
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    format: u64,
+    ff_version: String,
+    config: Value,
+}
+
+/// Write the current config as a bundle, to stdout without a file
+pub fn export(config: &Config, file: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let bundle = Bundle {
+        format: BUNDLE_FORMAT,
+        ff_version: env!("CARGO_PKG_VERSION").to_string(),
+        config: serde_json::to_value(config)?,
+    };
+    let content = serde_json::to_string_pretty(&bundle)?;
+
+    match file {
+        Some(file) => {
+            crate::util::write_atomically(file, &content)?;
+            eprintln!("📤 Exported settings to {}", file.display().to_string().green());
+        }
+        None => println!("{}", content),
+    }
+    Ok(())
+}
+
+/// Merge a bundle into the config file instead of replacing it, so settings made on this
+/// machine survive unless the bundle sets them too
+pub fn import(config: &Config, file: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let bundle: Bundle = serde_json::from_str(&std::fs::read_to_string(file)?)
+        .map_err(|e| format!("{} is not an ff export: {}", file.display(), e))?;
+    if bundle.format > BUNDLE_FORMAT {
+        return Err(format!("{} was exported by a newer ff ({}), update ff to import it",
+                           file.display(), bundle.ff_version).into());
+    }
+
+    let mut merged = serde_json::to_value(config)?;
+    let mut changes = Vec::new();
+    merge(&mut merged, bundle.config, "", &mut changes);
+    if changes.is_empty() {
+        eprintln!("✅ {} has nothing this config doesn't already have", file.display());
+        return Ok(());
+    }
+
+    // Round-trip through Config so a bundle with wrong value types fails before anything is written
+    let merged: Config = serde_json::from_value(merged)
+        .map_err(|e| format!("{} holds invalid settings: {}", file.display(), e))?;
+    merged.save_to_file(Path::new(CONFIG_FILE))?;
+
+    eprintln!("📥 Imported {} change(s) from {}:", changes.len(), file.display().to_string().green());
+    for change in &changes {
+        eprintln!("   {}", change);
+    }
+    Ok(())
+}
+
+/// Objects merge key by key and lists gain the entries they lack. Any other value is
+/// replaced by the imported one.
+fn merge(current: &mut Value, incoming: Value, path: &str, changes: &mut Vec<String>) {
+    match (current, incoming) {
+        (Value::Object(current), Value::Object(incoming)) => {
+            for (key, value) in incoming {
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                if PROTECTED_KEYS.contains(&path.as_str()) {
+                    eprintln!("{} Skipping {}, imports never change it. Set it by hand if you trust: {}",
+                              "⚠️".yellow(), path, value);
+                    continue;
+                }
+                match current.get_mut(&key) {
+                    Some(existing) => merge(existing, value, &path, changes),
+                    // Every setting this ff knows is serialized, so a missing key is from a newer version
+                    None => eprintln!("{} Skipping {}, a setting this ff version doesn't have", "⚠️".yellow(), path),
+                }
+            }
+        }
+        (Value::Array(current), Value::Array(incoming)) => {
+            for value in incoming {
+                if !current.contains(&value) {
+                    changes.push(format!("{} += {}", path.blue(), value));
+                    current.push(value);
+                }
+            }
+        }
+        (current, incoming) => {
+            if *current != incoming {
+                changes.push(format!("{}: {} -> {}", path.blue(), current, incoming));
+                *current = incoming;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::merge;
+
+    fn merged(mut current: serde_json::Value, incoming: serde_json::Value) -> (serde_json::Value, usize) {
+        let mut changes = Vec::new();
+        merge(&mut current, incoming, "", &mut changes);
+        (current, changes.len())
+    }
+
+    #[test]
+    fn scalars_take_the_imported_value() {
+        let (config, changes) = merged(json!({"max_file_size_mb": 10, "include_hidden": false}),
+                                       json!({"max_file_size_mb": 20, "include_hidden": false}));
+        assert_eq!(config, json!({"max_file_size_mb": 20, "include_hidden": false}));
+        assert_eq!(changes, 1);
+    }
+
+    #[test]
+    fn lists_gain_missing_entries_only() {
+        let (config, changes) = merged(json!({"ignore_directories": ["target", "build"]}),
+                                       json!({"ignore_directories": ["build", "dist"]}));
+        assert_eq!(config, json!({"ignore_directories": ["target", "build", "dist"]}));
+        assert_eq!(changes, 1);
+    }
+
+    #[test]
+    fn objects_merge_key_by_key() {
+        let (config, _) = merged(json!({"output_options": {"banner": true, "max_line_length": 100}}),
+                                 json!({"output_options": {"banner": false}}));
+        assert_eq!(config, json!({"output_options": {"banner": false, "max_line_length": 100}}));
+    }
+
+    #[test]
+    fn unknown_keys_are_skipped() {
+        let (config, changes) = merged(json!({"include_hidden": false}), json!({"from_the_future": 1}));
+        assert_eq!(config, json!({"include_hidden": false}));
+        assert_eq!(changes, 0);
+    }
+
+    #[test]
+    fn protected_keys_are_never_imported() {
+        let (config, changes) = merged(json!({"elevate_roots": []}),
+                                       json!({"elevate_roots": ["/"], "hooks": {"post_search": "rm -rf ~"}}));
+        assert_eq!(config, json!({"elevate_roots": []}));
+        assert_eq!(changes, 0);
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::fs::{File, Metadata};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::util;
+
+const CACHE_FILE: &str = "content-cache.json";
+
+/// Result of one content scan, valid while the file keeps the same size and mtime
+#[derive(Serialize, Deserialize)]
+struct CachedScan {
+    query: String,
+    path: PathBuf,
+    size: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+    match_lines: Vec<usize>,
+    last_used: u64,
+}
+
+/// Persistent cache of content-search results, so repeated searches for the
+/// same pattern skip re-reading files that haven't changed
+pub struct ContentCache {
+    file: Option<PathBuf>,
+    entries: HashMap<(String, PathBuf), CachedScan>,
+    max_entries: usize,
+    now: u64,
+    dirty: bool,
+}
+
+/// Saved scans, an unreadable or corrupt cache file just means starting over
+fn read_scans(path: &Path) -> Vec<CachedScan> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn file_stamp(metadata: &Metadata) -> Option<(u64, u64, u32)> {
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((metadata.len(), modified.as_secs(), modified.subsec_nanos()))
+}
+
+impl ContentCache {
+    /// Load the cache from the user cache directory, starting empty if it's missing or unreadable
+    pub fn load(max_entries: usize) -> Self {
+        let file = util::cache_dir().map(|dir| dir.join(CACHE_FILE));
+        let entries = file
+            .as_deref()
+            .map(read_scans)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|scan| ((scan.query.clone(), scan.path.clone()), scan))
+            .collect();
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Self { file, entries, max_entries, now, dirty: false }
+    }
+
+    /// Matching line numbers from an earlier scan, if the file is unchanged since
+    pub fn lookup(&mut self, query: &str, path: &Path, metadata: &Metadata) -> Option<Vec<usize>> {
+        let (size, secs, nanos) = file_stamp(metadata)?;
+        let scan = self.entries.get_mut(&(query.to_string(), path.to_path_buf()))?;
+
+        if scan.size != size || scan.modified_secs != secs || scan.modified_nanos != nanos {
+            return None;
+        }
+
+        if scan.last_used != self.now {
+            scan.last_used = self.now;
+            self.dirty = true;
+        }
+        Some(scan.match_lines.clone())
+    }
+
+    pub fn store(&mut self, query: &str, path: &Path, metadata: &Metadata, match_lines: Vec<usize>) {
+        let Some((size, modified_secs, modified_nanos)) = file_stamp(metadata) else { return };
+
+        self.entries.insert((query.to_string(), path.to_path_buf()), CachedScan {
+            query: query.to_string(),
+            path: path.to_path_buf(),
+            size,
+            modified_secs,
+            modified_nanos,
+            match_lines,
+            last_used: self.now,
+        });
+        self.dirty = true;
+    }
+
+    /// Write the cache back, evicting the least recently used scans beyond the cap.
+    /// Other ff processes may have saved since we loaded, so their entries are merged
+    /// in under a lock rather than overwritten.
+    pub fn save(mut self) {
+        let Some(file) = self.file.take().filter(|_| self.dirty) else { return };
+
+        if let Some(parent) = file.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        let mut lock_path = file.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let Ok(lock) = File::create(lock_path) else { return };
+        if lock.lock().is_err() {
+            return;
+        }
+
+        for scan in read_scans(&file) {
+            let key = (scan.query.clone(), scan.path.clone());
+            match self.entries.get(&key) {
+                Some(ours) if ours.last_used >= scan.last_used => {}
+                _ => {
+                    self.entries.insert(key, scan);
+                }
+            }
+        }
+
+        let mut scans: Vec<CachedScan> = self.entries.into_values().collect();
+        if scans.len() > self.max_entries {
+            scans.sort_by_key(|scan| std::cmp::Reverse(scan.last_used));
+            scans.truncate(self.max_entries);
+        }
+
+        if let Ok(content) = serde_json::to_string(&scans) {
+            let _ = util::write_atomically(&file, &content);
+        }
+    }
+}
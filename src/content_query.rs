@@ -0,0 +1,290 @@
+use crate::query::QueryError;
+
+/// A --content pattern, optionally combining terms with AND / OR / NOT, e.g.
+/// `foo AND bar NOT baz`. Without those keywords the whole pattern is one term,
+/// so plain phrases search exactly as before.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentQuery {
+    Term(String),
+    Not(Box<ContentQuery>),
+    And(Vec<ContentQuery>),
+    Or(Vec<ContentQuery>),
+    AnyOf(Vec<String>),  // Repeated --content flags, literal patterns searched for in one pass
+}
+
+impl ContentQuery {
+    /// Every distinct term, in the order written
+    pub fn terms(&self) -> Vec<&str> {
+        let mut terms = Vec::new();
+        self.collect_terms(false, &mut |term, _| {
+            if !terms.contains(&term) {
+                terms.push(term);
+            }
+        });
+        terms
+    }
+
+    /// Terms whose matches are shown for a matching file, i.e. the ones not under a NOT
+    pub fn shown_terms(&self) -> Vec<&str> {
+        let mut terms = Vec::new();
+        self.collect_terms(false, &mut |term, negated| {
+            if !negated && !terms.contains(&term) {
+                terms.push(term);
+            }
+        });
+        terms
+    }
+
+    fn collect_terms<'a>(&'a self, negated: bool, visit: &mut impl FnMut(&'a str, bool)) {
+        match self {
+            ContentQuery::Term(term) => visit(term, negated),
+            ContentQuery::AnyOf(patterns) => patterns.iter().for_each(|pattern| visit(pattern, negated)),
+            ContentQuery::Not(inner) => inner.collect_terms(!negated, visit),
+            ContentQuery::And(parts) | ContentQuery::Or(parts) => {
+                parts.iter().for_each(|part| part.collect_terms(negated, visit))
+            }
+        }
+    }
+
+    /// Whether a file satisfies the query. `term_matches` is only asked about the terms the
+    /// answer depends on, so `a AND b` never scans for `b` in a file without `a`.
+    pub fn evaluate<E>(&self, term_matches: &mut impl FnMut(&str) -> Result<bool, E>) -> Result<bool, E> {
+        match self {
+            ContentQuery::Term(term) => term_matches(term),
+            ContentQuery::AnyOf(patterns) => {
+                for pattern in patterns {
+                    if term_matches(pattern)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            ContentQuery::Not(inner) => Ok(!inner.evaluate(term_matches)?),
+            ContentQuery::And(parts) => {
+                for part in parts {
+                    if !part.evaluate(term_matches)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            ContentQuery::Or(parts) => {
+                for part in parts {
+                    if part.evaluate(term_matches)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    And,
+    Or,
+    Not,
+}
+
+/// Parse a --content pattern. NOT binds tightest, then AND, then OR, and a NOT right
+/// after a term means AND NOT. Words between keywords form one phrase, and `"..."`
+/// quotes keywords or exact spacing.
+pub fn parse(input: &str) -> Result<ContentQuery, QueryError> {
+    let tokens = tokenize(input)?;
+    if !tokens.iter().any(|(token, _)| !matches!(token, Token::Word(_))) {
+        return Ok(ContentQuery::Term(input.to_string()));
+    }
+
+    let mut parser = Parser { tokens: phrases(tokens), next: 0, end: input.len() };
+    let query = parser.or()?;
+    match parser.tokens.get(parser.next) {
+        None => Ok(query),
+        Some((_, position)) => Err(QueryError::new(*position, "unexpected keyword")),
+    }
+}
+
+/// Whitespace-separated words and keywords with their byte offsets
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if c == '"' {
+            chars.next();
+            let mut word = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, c)) => word.push(c),
+                    None => return Err(QueryError::new(start, "unclosed quote")),
+                }
+            }
+            tokens.push((Token::Word(word), start));
+            continue;
+        }
+
+        let mut word = String::new();
+        while let Some(&(_, c)) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            word.push(c);
+            chars.next();
+        }
+        let token = match word.as_str() {
+            "AND" => Token::And,
+            "OR" => Token::Or,
+            "NOT" => Token::Not,
+            _ => Token::Word(word),
+        };
+        tokens.push((token, start));
+    }
+
+    Ok(tokens)
+}
+
+/// Join runs of adjacent words into single-space phrases
+fn phrases(tokens: Vec<(Token, usize)>) -> Vec<(Token, usize)> {
+    let mut joined: Vec<(Token, usize)> = Vec::new();
+    for (token, position) in tokens {
+        match (joined.last_mut(), token) {
+            (Some((Token::Word(phrase), _)), Token::Word(word)) => {
+                phrase.push(' ');
+                phrase.push_str(&word);
+            }
+            (_, token) => joined.push((token, position)),
+        }
+    }
+    joined
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+    end: usize,  // Input length, where "expected a term" points once the tokens run out
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(token, _)| token)
+    }
+
+    fn or(&mut self) -> Result<ContentQuery, QueryError> {
+        let mut parts = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next += 1;
+            parts.push(self.and()?);
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { ContentQuery::Or(parts) })
+    }
+
+    fn and(&mut self) -> Result<ContentQuery, QueryError> {
+        let mut parts = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.next += 1,
+                Some(Token::Not) => {}
+                _ => break,
+            }
+            parts.push(self.unary()?);
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { ContentQuery::And(parts) })
+    }
+
+    fn unary(&mut self) -> Result<ContentQuery, QueryError> {
+        let Some((token, position)) = self.tokens.get(self.next) else {
+            return Err(QueryError::new(self.end, "expected a term here"));
+        };
+        self.next += 1;
+        match token {
+            Token::Not => Ok(ContentQuery::Not(Box::new(self.unary()?))),
+            Token::Word(term) => Ok(ContentQuery::Term(term.clone())),
+            Token::And | Token::Or => Err(QueryError::new(
+                *position,
+                "expected a term before this keyword (quote it to search for the word itself)",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(text: &str) -> ContentQuery {
+        ContentQuery::Term(text.to_string())
+    }
+
+    fn not(query: ContentQuery) -> ContentQuery {
+        ContentQuery::Not(Box::new(query))
+    }
+
+    #[test]
+    fn without_keywords_the_whole_pattern_is_one_term() {
+        assert_eq!(parse("fn  main()").unwrap(), term("fn  main()"));
+        assert_eq!(parse("and or not").unwrap(), term("and or not"));
+    }
+
+    #[test]
+    fn words_between_keywords_form_phrases() {
+        assert_eq!(parse("use serde AND derive  Debug").unwrap(),
+                   ContentQuery::And(vec![term("use serde"), term("derive Debug")]));
+    }
+
+    #[test]
+    fn not_binds_tightest_then_and_then_or() {
+        assert_eq!(parse("a OR b AND NOT c").unwrap(),
+                   ContentQuery::Or(vec![term("a"), ContentQuery::And(vec![term("b"), not(term("c"))])]));
+        assert_eq!(parse("NOT NOT a").unwrap(), not(not(term("a"))));
+    }
+
+    #[test]
+    fn not_after_a_term_means_and_not() {
+        assert_eq!(parse("serde NOT async").unwrap(), ContentQuery::And(vec![term("serde"), not(term("async"))]));
+    }
+
+    #[test]
+    fn quotes_protect_keywords_and_spacing() {
+        assert_eq!(parse(r#""AND" OR "a  b""#).unwrap(), ContentQuery::Or(vec![term("AND"), term("a  b")]));
+    }
+
+    #[test]
+    fn malformed_queries_point_at_the_problem() {
+        let error = parse("a AND").unwrap_err();
+        assert_eq!((error.position, error.message.as_str()), (5, "expected a term here"));
+        assert_eq!(parse("AND a").unwrap_err().position, 0);
+        assert_eq!(parse("a OR OR b").unwrap_err().position, 5);
+        assert_eq!(parse(r#"a AND "b"#).unwrap_err().message, "unclosed quote");
+    }
+
+    #[test]
+    fn terms_are_listed_once_and_negated_ones_are_not_shown() {
+        let query = parse("a AND b NOT c OR a").unwrap();
+        assert_eq!(query.terms(), ["a", "b", "c"]);
+        assert_eq!(query.shown_terms(), ["a", "b"]);
+        assert_eq!(parse("NOT NOT a").unwrap().shown_terms(), ["a"]);
+    }
+
+    #[test]
+    fn evaluation_only_asks_about_terms_it_needs() {
+        let query = parse("a AND b OR c").unwrap();
+        let mut asked = Vec::new();
+        let hit = query.evaluate(&mut |term| {
+            asked.push(term.to_string());
+            Ok::<_, ()>(term == "c")
+        });
+        assert_eq!(hit, Ok(true));
+        assert_eq!(asked, ["a", "c"]);
+
+        let any_of = ContentQuery::AnyOf(vec!["x".to_string(), "y".to_string()]);
+        assert_eq!(any_of.evaluate(&mut |term| Ok::<_, ()>(term == "y")), Ok(true));
+        assert_eq!(not(term("x")).evaluate(&mut |_| Ok::<_, ()>(true)), Ok(false));
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use colored::Colorize;
+use crate::config::Config;
+use crate::util::{self, display_path};
+
+/// Clusters named in the hint, the biggest first
+const MAX_CLUSTERS: usize = 3;
+
+/// File in the per-user config directory listing where --elevate may run, a JSON list of paths
+pub const ROOTS_FILE: &str = "elevate_roots.json";
+
+/// Where the elevation roots are read from. Never the working directory: a checkout shipping its
+/// own ff-config.json could otherwise allow itself (or `/`) to be searched as admin.
+pub fn roots_file() -> Option<PathBuf> {
+    util::config_dir().map(|dir| dir.join(ROOTS_FILE))
+}
+
+/// The user's elevation roots, none without the file. A broken file is reported and ignored.
+pub fn load_user_roots() -> Vec<String> {
+    let Some(path) = roots_file() else { return Vec::new() };
+    let Ok(content) = std::fs::read_to_string(&path) else { return Vec::new() };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        eprintln!("{} Ignoring {}: {}", "⚠️".yellow(), path.display(), e);
+        Vec::new()
+    })
+}
+
+/// The roots file as shown in messages
+pub fn roots_file_name() -> String {
+    roots_file().map(|path| path.display().to_string()).unwrap_or_else(|| ROOTS_FILE.to_string())
+}
+
+/// Whether `root` lies inside one of the user's elevation roots. Elevation is opt-in per
+/// location, so a stray --elevate never runs a search as admin somewhere unexpected.
+pub fn is_allowed(root: &Path, config: &Config) -> bool {
+    let Ok(root) = root.canonicalize() else { return false };
+    config
+        .elevate_roots
+        .iter()
+        .filter_map(|allowed| Path::new(allowed).canonicalize().ok())
+        .any(|allowed| root.starts_with(allowed))
+}
+
+/// Run this same search again with admin rights and wait for it: through sudo on Unix, a UAC
+/// prompt on Windows (in a new console window, which stays open to read the results). The
+/// elevated search gets --elevated, so it runs no hooks and its exit code comes back here.
+pub fn relaunch() -> io::Result<ExitStatus> {
+    let exe = std::env::current_exe()?;
+    let args: Vec<OsString> = std::env::args_os()
+        .skip(1)
+        .filter(|arg| arg != "--elevate")
+        .chain(std::iter::once(OsString::from("--elevated")))
+        .collect();
+
+    if cfg!(windows) {
+        // ff is started directly, no cmd.exe to re-parse the arguments. The path and command
+        // line reach PowerShell through the environment, so nothing of them is parsed as script.
+        let line = args.iter().map(|arg| windows_arg(&arg.to_string_lossy())).collect::<Vec<_>>().join(" ");
+        let script = "$ff = Start-Process -FilePath $env:FF_ELEVATE_EXE -ArgumentList $env:FF_ELEVATE_ARGS \
+                      -Verb RunAs -Wait -PassThru; exit $ff.ExitCode";
+        Command::new("powershell")
+            .args(["-NoProfile", "-Command", script])
+            .env("FF_ELEVATE_EXE", exe)
+            .env("FF_ELEVATE_ARGS", line)
+            .status()
+    } else {
+        Command::new("sudo").arg("--").arg(exe).args(args).status()
+    }
+}
+
+/// Quote an argument the way Windows programs split their command line (CommandLineToArgvW):
+/// backslashes are literal unless they precede a quote
+fn windows_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '\x0b', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // Doubled, so the closing quote isn't escaped
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Keeps the elevated search's console window open until Enter, Windows closes it with the process
+pub struct HoldWindow;
+
+impl Drop for HoldWindow {
+    fn drop(&mut self) {
+        eprint!("\n{} Press Enter to close this window", "🔒".yellow());
+        let _ = io::stdin().read_line(&mut String::new());
+    }
+}
+
+/// End-of-search note about the paths the search wasn't allowed into: how many, where they
+/// cluster, and how to search them elevated
+pub fn print_hint(denied: &[PathBuf], root: &Path, config: &Config) {
+    if denied.is_empty() {
+        return;
+    }
+    let mut clusters: HashMap<&Path, usize> = HashMap::new();
+    for path in denied {
+        *clusters.entry(path.parent().unwrap_or(path)).or_default() += 1;
+    }
+    let mut clusters: Vec<_> = clusters.into_iter().collect();
+    clusters.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let named = clusters
+        .iter()
+        .take(MAX_CLUSTERS)
+        .map(|(dir, count)| format!("{} ({})", display_path(dir), count))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    eprintln!();
+    eprintln!("{} {} path(s) could not be searched without admin rights, mostly in {}",
+              "🔒".yellow(), denied.len().to_string().yellow().bold(), named.cyan());
+    let admin = if cfg!(windows) { "an administrator (UAC prompt)" } else { "root (sudo)" };
+    if is_allowed(root, config) {
+        eprintln!("{} Run it again with {} to search them as {}", "💡".yellow(), "--elevate".blue(), admin);
+    } else {
+        eprintln!("{} To allow {} here, add {} to the list in {}",
+                  "💡".yellow(), "--elevate".blue(), display_path(root).cyan(), roots_file_name().cyan());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::windows_arg;
+
+    #[test]
+    fn plain_arguments_stay_bare() {
+        assert_eq!(windows_arg("--content"), "--content");
+        assert_eq!(windows_arg(r"C:\tools\ff.exe"), r"C:\tools\ff.exe");
+    }
+
+    #[test]
+    fn spaces_and_empty_arguments_are_quoted() {
+        assert_eq!(windows_arg("two words"), "\"two words\"");
+        assert_eq!(windows_arg(""), "\"\"");
+    }
+
+    #[test]
+    fn quotes_and_trailing_backslashes_are_escaped() {
+        assert_eq!(windows_arg(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(windows_arg(r"dir with space\"), r#""dir with space\\""#);
+        assert_eq!(windows_arg(r#"a\"b"#), r#""a\\\"b""#);
+    }
+
+    #[test]
+    fn shell_metacharacters_pass_through_literally() {
+        // No cmd.exe parses the line, so & and % need no escaping
+        assert_eq!(windows_arg("a&calc"), "a&calc");
+        assert_eq!(windows_arg("%PATH%"), "%PATH%");
+    }
+}
@@ -0,0 +1,65 @@
+use std::borrow::Cow;
+use std::io::Read;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use encoding_rs_io::{DecodeReaderBytes, DecodeReaderBytesBuilder};
+
+/// How a file's text is stored, judged from its first block
+pub enum TextEncoding {
+    Utf8,
+    Decoded(&'static Encoding),  // Transcoded to UTF-8 while reading
+    Binary,
+}
+
+/// Byte order marks decide first. Without one, ASCII-heavy UTF-16 shows as every other byte
+/// being NUL, any other NUL means binary, and text that isn't UTF-8 is read as Windows-1252
+/// (which covers Latin-1).
+pub fn sniff(block: &[u8]) -> TextEncoding {
+    if let Some((encoding, _)) = Encoding::for_bom(block) {
+        // Decoding UTF-8 with a BOM too, so the BOM doesn't end up in the first line
+        return TextEncoding::Decoded(encoding);
+    }
+    if let Some(encoding) = bomless_utf16(block) {
+        return TextEncoding::Decoded(encoding);
+    }
+    if block.contains(&0) {
+        return TextEncoding::Binary;
+    }
+    match std::str::from_utf8(block) {
+        Ok(_) => TextEncoding::Utf8,
+        // The block can end in the middle of a character, that's still UTF-8
+        Err(e) if e.error_len().is_none() => TextEncoding::Utf8,
+        Err(_) => TextEncoding::Decoded(WINDOWS_1252),
+    }
+}
+
+fn bomless_utf16(block: &[u8]) -> Option<&'static Encoding> {
+    let pairs = block.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let zeros_at = |offset: usize| block.chunks_exact(2).filter(|pair| pair[offset] == 0).count();
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    // Mostly ASCII text, NULs consistently on one side of each code unit
+    if even == 0 && odd * 2 > pairs {
+        Some(UTF_16LE)
+    } else if odd == 0 && even * 2 > pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Reader yielding `source` as UTF-8, with a leading BOM dropped
+pub fn decoder<R: Read>(source: R, encoding: &'static Encoding) -> DecodeReaderBytes<R, Vec<u8>> {
+    DecodeReaderBytesBuilder::new().encoding(Some(encoding)).build(source)
+}
+
+/// Text of bytes already in memory, None for binary content
+pub fn decode(bytes: &[u8]) -> Option<Cow<'_, str>> {
+    let block = &bytes[..bytes.len().min(crate::search::BINARY_PROBE_BYTES)];
+    match sniff(block) {
+        TextEncoding::Utf8 => Some(String::from_utf8_lossy(bytes)),
+        TextEncoding::Decoded(encoding) => Some(encoding.decode(bytes).0),
+        TextEncoding::Binary => None,
+    }
+}
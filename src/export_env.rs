@@ -0,0 +1,53 @@
+use std::io::{self, Write};
+use clap::ValueEnum;
+use crate::SearchResult;
+
+/// Assignment syntax for --export-env
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnvShell {
+    /// export NAME_1='path' (bash, zsh, sh)
+    Sh,
+    /// $env:NAME_1 = 'path'
+    Powershell,
+}
+
+impl EnvShell {
+    /// PowerShell on Windows, sh everywhere else
+    pub fn native() -> Self {
+        if cfg!(windows) { EnvShell::Powershell } else { EnvShell::Sh }
+    }
+}
+
+/// --export-env names become variable prefixes, so they have to be valid identifiers
+pub fn parse_name(name: &str) -> Result<String, String> {
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(format!("`{}` is not a variable name (letters, digits and _, not starting with a digit)", name))
+    }
+}
+
+/// Print NAME_1, NAME_2, ... for the results in order, and NAME_COUNT, ready for `eval`
+pub fn print_assignments(results: &[SearchResult], name: &str, shell: EnvShell) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    for (index, result) in results.iter().enumerate() {
+        let mut path = result.path.to_string_lossy().into_owned();
+        if let Some(ref entry) = result.archive_entry {
+            path.push_str("!/");
+            path.push_str(entry);
+        }
+        writeln!(stdout, "{}", assignment(shell, &format!("{}_{}", name, index + 1), &path))?;
+    }
+    writeln!(stdout, "{}", assignment(shell, &format!("{}_COUNT", name), &results.len().to_string()))?;
+    stdout.flush()
+}
+
+fn assignment(shell: EnvShell, variable: &str, value: &str) -> String {
+    match shell {
+        EnvShell::Sh => format!("export {}={}", variable, crate::hooks::posix_quote(value)),
+        // Single-quoted PowerShell strings are literal, only ' itself is escaped (by doubling)
+        EnvShell::Powershell => format!("$env:{} = '{}'", variable, value.replace('\'', "''")),
+    }
+}
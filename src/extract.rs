@@ -0,0 +1,308 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// A labelled piece of text pulled out of a structured file, e.g. one notebook cell
+pub struct Section {
+    pub label: String,
+    pub text: String,
+    pub line_name: &'static str,  // What a line of `text` is called in locations, "row" for spreadsheets
+}
+
+/// Reads a file's searchable text as sections
+pub type Extractor = fn(&Path) -> io::Result<Vec<Section>>;
+
+/// Formats whose raw bytes are mostly markup, searched through the text they hold instead
+pub fn extractor_for(path: &Path) -> Option<Extractor> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "ipynb" => Some(notebook_cells),
+        "docx" => Some(word_paragraphs),
+        "odt" => Some(open_document_paragraphs),
+        "xlsx" => Some(spreadsheet_rows),
+        _ => None,
+    }
+}
+
+/// Code and markdown cell sources of a Jupyter notebook, leaving out outputs and metadata
+fn notebook_cells(path: &Path) -> io::Result<Vec<Section>> {
+    let notebook: serde_json::Value = serde_json::from_slice(&std::fs::read(path)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("not a notebook: {}", e)))?;
+    let cells = notebook["cells"].as_array().map(Vec::as_slice).unwrap_or_default();
+
+    let sections = cells
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| matches!(cell["cell_type"].as_str(), Some("code" | "markdown")))
+        .map(|(index, cell)| {
+            // nbformat stores a source as one string or as a list of lines that keep their newlines
+            let text = match &cell["source"] {
+                serde_json::Value::String(source) => source.clone(),
+                serde_json::Value::Array(lines) => lines.iter().filter_map(|line| line.as_str()).collect(),
+                _ => String::new(),
+            };
+            Section { label: format!("cell {}", index + 1), text, line_name: "line" }
+        })
+        .collect();
+    Ok(sections)
+}
+
+fn invalid(what: &str, error: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("not {}: {}", what, error))
+}
+
+/// Office documents are zip files of XML parts
+type Package = zip::ZipArchive<BufReader<File>>;
+
+fn open_package(path: &Path, what: &str) -> io::Result<Package> {
+    zip::ZipArchive::new(BufReader::new(File::open(path)?)).map_err(|e| invalid(what, e))
+}
+
+fn read_part(package: &mut Package, name: &str, what: &str) -> io::Result<String> {
+    let mut part = package.by_name(name).map_err(|e| invalid(what, format!("{}: {}", name, e)))?;
+    let mut xml = String::new();
+    part.read_to_string(&mut xml)?;
+    Ok(xml)
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    let value = element.try_get_attribute(name).ok().flatten()?;
+    value.unescape_value().ok().map(Cow::into_owned)
+}
+
+/// Where a word processor format keeps its text
+struct Markup {
+    paragraphs: &'static [&'static [u8]],          // Elements holding one paragraph each
+    text: Option<&'static [u8]>,                   // Element text has to be in, None for any text in a paragraph
+    characters: &'static [(&'static [u8], char)],  // Empty elements standing for a character
+}
+
+const WORD: Markup = Markup {
+    paragraphs: &[b"w:p"],
+    text: Some(b"w:t"),
+    characters: &[(b"w:tab", '\t'), (b"w:br", '\n'), (b"w:cr", '\n')],
+};
+
+const OPEN_DOCUMENT: Markup = Markup {
+    paragraphs: &[b"text:p", b"text:h"],
+    text: None,
+    characters: &[(b"text:tab", '\t'), (b"text:s", ' '), (b"text:line-break", '\n')],
+};
+
+/// Text of each paragraph, in document order. Paragraphs nested in another one (footnotes,
+/// frames) are read as part of it.
+fn paragraphs(xml: &str, markup: &Markup, what: &str) -> io::Result<Vec<String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event().map_err(|e| invalid(what, e))? {
+            Event::Start(element) => {
+                let name = element.name();
+                if markup.paragraphs.contains(&name.as_ref()) {
+                    // A nested paragraph goes on a line of its own
+                    if depth > 0 {
+                        current.push('\n');
+                    }
+                    depth += 1;
+                } else if markup.text == Some(name.as_ref()) {
+                    in_text = true;
+                }
+            }
+            Event::End(element) => {
+                let name = element.name();
+                if markup.paragraphs.contains(&name.as_ref()) {
+                    depth -= 1;
+                    if depth == 0 {
+                        paragraphs.push(std::mem::take(&mut current));
+                    }
+                } else if markup.text == Some(name.as_ref()) {
+                    in_text = false;
+                }
+            }
+            Event::Empty(element) => {
+                let name = element.name();
+                if markup.paragraphs.contains(&name.as_ref()) && depth == 0 {
+                    paragraphs.push(String::new());
+                } else if depth > 0
+                    && let Some(&(_, character)) = markup.characters.iter().find(|(tag, _)| *tag == name.as_ref())
+                {
+                    current.push(character);
+                }
+            }
+            Event::Text(text) if depth > 0 && (in_text || markup.text.is_none()) => {
+                current.push_str(&text.unescape().map_err(|e| invalid(what, e))?);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(paragraphs)
+}
+
+fn paragraph_sections(paragraphs: Vec<String>) -> Vec<Section> {
+    paragraphs
+        .into_iter()
+        .enumerate()
+        .map(|(index, text)| Section { label: format!("paragraph {}", index + 1), text, line_name: "line" })
+        .collect()
+}
+
+/// Paragraphs of a Word document's main body, leaving out headers, footers and comments
+fn word_paragraphs(path: &Path) -> io::Result<Vec<Section>> {
+    let xml = read_part(&mut open_package(path, "a Word document")?, "word/document.xml", "a Word document")?;
+    Ok(paragraph_sections(paragraphs(&xml, &WORD, "a Word document")?))
+}
+
+/// Paragraphs and headings of an OpenDocument text
+fn open_document_paragraphs(path: &Path) -> io::Result<Vec<Section>> {
+    let xml = read_part(&mut open_package(path, "an OpenDocument text")?, "content.xml", "an OpenDocument text")?;
+    Ok(paragraph_sections(paragraphs(&xml, &OPEN_DOCUMENT, "an OpenDocument text")?))
+}
+
+/// Cell values of every worksheet, one line per row with cells separated by tabs. Empty rows
+/// are kept as empty lines, so matches are reported as `sheet Budget, row 12`.
+fn spreadsheet_rows(path: &Path) -> io::Result<Vec<Section>> {
+    const WHAT: &str = "an Excel workbook";
+    let mut package = open_package(path, WHAT)?;
+    // Text cells point into the shared string table, which only exists when there are any
+    let shared = match read_part(&mut package, "xl/sharedStrings.xml", WHAT) {
+        Ok(xml) => shared_strings(&xml)?,
+        Err(_) => Vec::new(),
+    };
+
+    let mut sections = Vec::new();
+    for (name, part) in worksheets(&mut package)? {
+        let xml = read_part(&mut package, &part, WHAT)?;
+        sections.push(Section { label: format!("sheet {}", name), text: sheet_rows(&xml, &shared)?, line_name: "row" });
+    }
+    Ok(sections)
+}
+
+/// Sheet names in tab order with the part each is stored in, resolved through the workbook's relationships
+fn worksheets(package: &mut Package) -> io::Result<Vec<(String, String)>> {
+    const WHAT: &str = "an Excel workbook";
+    let mut targets = HashMap::new();
+    let relationships = read_part(package, "xl/_rels/workbook.xml.rels", WHAT)?;
+    let mut reader = Reader::from_str(&relationships);
+    loop {
+        match reader.read_event().map_err(|e| invalid(WHAT, e))? {
+            Event::Start(element) | Event::Empty(element) if element.name().as_ref() == b"Relationship" => {
+                if let (Some(id), Some(target)) = (attribute(&element, b"Id"), attribute(&element, b"Target")) {
+                    targets.insert(id, target);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let mut sheets = Vec::new();
+    let workbook = read_part(package, "xl/workbook.xml", WHAT)?;
+    let mut reader = Reader::from_str(&workbook);
+    loop {
+        match reader.read_event().map_err(|e| invalid(WHAT, e))? {
+            Event::Start(element) | Event::Empty(element) if element.name().as_ref() == b"sheet" => {
+                let Some(name) = attribute(&element, b"name") else { continue };
+                let Some(target) = attribute(&element, b"r:id").and_then(|id| targets.get(&id)) else { continue };
+                // Targets are relative to xl/, or absolute within the package
+                let part = match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None => format!("xl/{}", target),
+                };
+                sheets.push((name, part));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(sheets)
+}
+
+/// Strings of the shared string table, skipping the phonetic guides of East Asian text
+fn shared_strings(xml: &str) -> io::Result<Vec<String>> {
+    const WHAT: &str = "an Excel workbook";
+    let mut reader = Reader::from_str(xml);
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let (mut in_text, mut in_phonetic) = (false, false);
+
+    loop {
+        match reader.read_event().map_err(|e| invalid(WHAT, e))? {
+            Event::Start(element) => match element.name().as_ref() {
+                b"si" => current.clear(),
+                b"t" => in_text = true,
+                b"rPh" => in_phonetic = true,
+                _ => {}
+            },
+            Event::End(element) => match element.name().as_ref() {
+                b"si" => strings.push(std::mem::take(&mut current)),
+                b"t" => in_text = false,
+                b"rPh" => in_phonetic = false,
+                _ => {}
+            },
+            Event::Empty(element) if element.name().as_ref() == b"si" => strings.push(String::new()),
+            Event::Text(text) if in_text && !in_phonetic => current.push_str(&text.unescape().map_err(|e| invalid(WHAT, e))?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(strings)
+}
+
+/// Rows of one worksheet, formulas left out in favour of their cached values
+fn sheet_rows(xml: &str, shared: &[String]) -> io::Result<String> {
+    const WHAT: &str = "an Excel workbook";
+    let mut reader = Reader::from_str(xml);
+    let mut rows: Vec<String> = Vec::new();
+    let mut cells = Vec::new();
+    let mut row: usize = 0;
+    let mut cell_type = None;
+    let mut value = String::new();
+    let mut in_value = false;
+
+    loop {
+        match reader.read_event().map_err(|e| invalid(WHAT, e))? {
+            Event::Start(element) => match element.name().as_ref() {
+                b"row" => {
+                    row = attribute(&element, b"r").and_then(|r| r.parse().ok()).unwrap_or(row + 1);
+                    cells.clear();
+                }
+                b"c" => {
+                    cell_type = attribute(&element, b"t");
+                    value.clear();
+                }
+                b"v" | b"t" => in_value = true,
+                _ => {}
+            },
+            Event::End(element) => match element.name().as_ref() {
+                b"row" => {
+                    // Line numbers are row numbers, so rows the sheet skips become empty lines
+                    let previous = row.saturating_sub(1).max(rows.len());
+                    rows.resize(previous, String::new());
+                    rows.push(cells.join("\t"));
+                }
+                b"c" => {
+                    let text = match cell_type.as_deref() {
+                        Some("s") => value.trim().parse::<usize>().ok().and_then(|index| shared.get(index)).cloned().unwrap_or_default(),
+                        _ => std::mem::take(&mut value),
+                    };
+                    cells.push(text.replace('\n', " "));
+                }
+                b"v" | b"t" => in_value = false,
+                _ => {}
+            },
+            Event::Text(text) if in_value => value.push_str(&text.unescape().map_err(|e| invalid(WHAT, e))?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(rows.join("\n"))
+}
@@ -0,0 +1,97 @@
+use clap::ValueEnum;
+
+/// Broad file kinds for --type and the type: query field
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    Code,
+    Data,
+    Doc,
+    Image,
+    Video,
+    Audio,
+    Archive,
+}
+
+impl Category {
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Code => "code",
+            Category::Data => "data",
+            Category::Doc => "doc",
+            Category::Image => "image",
+            Category::Video => "video",
+            Category::Audio => "audio",
+            Category::Archive => "archive",
+        }
+    }
+}
+
+/// Extensions, their icon, and the category they count as (None for no category)
+type FileType = (&'static [&'static str], &'static str, Option<Category>);
+
+/// Known extensions, the one table behind result icons and --type
+const FILE_TYPES: &[FileType] = &[
+    // Programming / Scripting
+    (&["rs"], "🦀", Some(Category::Code)),
+    (&["js", "ts", "jsx", "tsx", "mjs", "cjs"], "📜", Some(Category::Code)),
+    (&["py"], "🐍", Some(Category::Code)),
+    (&["java"], "☕", Some(Category::Code)),
+    (&["cpp", "cxx", "cc"], "💠", Some(Category::Code)),
+    (&["c"], "🔵", Some(Category::Code)),
+    (&["h", "hpp"], "📘", Some(Category::Code)),
+    (&["go"], "🐹", Some(Category::Code)),
+    (&["rb"], "💎", Some(Category::Code)),
+    (&["php"], "🐘", Some(Category::Code)),
+    (&["sh", "bash", "zsh", "fish", "ps1"], "🐚", Some(Category::Code)),
+    (&["swift"], "🍎", Some(Category::Code)),
+    (&["kt", "kts"], "🤖", Some(Category::Code)),
+    (&["cs"], "🎯", Some(Category::Code)),
+    (&["lua", "scala", "zig", "dart", "ex", "exs", "hs", "sql"], "📄", Some(Category::Code)),
+
+    // Data / Config
+    (&["json"], "📋", Some(Category::Data)),
+    (&["yaml", "yml"], "⚙️", Some(Category::Data)),
+    (&["toml"], "🛠️", Some(Category::Data)),
+    (&["ini"], "📑", Some(Category::Data)),
+    (&["csv", "tsv", "xlsx", "ods"], "📊", Some(Category::Data)),
+    (&["xml"], "🗂️", Some(Category::Data)),
+
+    // Markup / Docs
+    (&["md", "rst"], "📝", Some(Category::Doc)),
+    (&["txt"], "📄", Some(Category::Doc)),
+    (&["html", "htm"], "🌐", Some(Category::Doc)),
+    (&["css", "scss"], "🎨", Some(Category::Code)),
+    (&["pdf"], "📕", Some(Category::Doc)),
+    (&["doc", "docx", "odt", "rtf", "epub", "ipynb"], "📄", Some(Category::Doc)),
+
+    // Images
+    (&["png", "jpg", "jpeg", "gif", "bmp", "svg", "webp", "heic", "tif", "tiff"], "🖼️", Some(Category::Image)),
+    (&["ico"], "🔖", Some(Category::Image)),
+
+    // Video
+    (&["mp4", "mkv", "avi", "mov", "webm", "m4v", "wmv"], "🎬", Some(Category::Video)),
+
+    // Audio
+    (&["mp3", "wav", "flac", "ogg", "m4a", "aac", "opus"], "🎵", Some(Category::Audio)),
+
+    // Archives
+    (&["zip", "tar", "gz", "bz2", "xz", "7z", "rar", "tgz", "zst"], "📦", Some(Category::Archive)),
+
+    // Misc
+    (&["exe", "bin", "dll"], "⚙️", None),
+    (&["lock"], "🔒", None),
+    (&["log"], "📜", None),
+];
+
+fn lookup(extension: &str) -> Option<&'static FileType> {
+    let extension = extension.to_lowercase();
+    FILE_TYPES.iter().find(|(extensions, _, _)| extensions.contains(&extension.as_str()))
+}
+
+pub fn icon(extension: Option<&str>) -> &'static str {
+    extension.and_then(lookup).map_or("📄", |(_, icon, _)| icon)
+}
+
+pub fn category(extension: Option<&str>) -> Option<Category> {
+    extension.and_then(lookup).and_then(|(_, _, category)| *category)
+}
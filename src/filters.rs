@@ -0,0 +1,367 @@
+use std::fs::Metadata;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use crate::file_types::{self, Category};
+
+/// Which side of a threshold an entry has to be on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Below,
+    Above,
+}
+
+impl Bound {
+    /// Leading `<`/`-` means below, `>`/`+` means above
+    fn split(input: &str) -> Option<(Bound, &str)> {
+        let mut chars = input.chars();
+        let bound = match chars.next()? {
+            '<' | '-' => Bound::Below,
+            '>' | '+' => Bound::Above,
+            _ => return None,
+        };
+        Some((bound, chars.as_str()))
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Bound::Below => '<',
+            Bound::Above => '>',
+        }
+    }
+}
+
+/// File size threshold, e.g. `<1M` or `>10k`
+#[derive(Clone, Debug)]
+pub struct SizeFilter {
+    pub bound: Bound,
+    pub bytes: u64,
+}
+
+impl SizeFilter {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let (bound, amount) = Bound::split(input.trim())
+            .ok_or_else(|| format!("size needs a comparison, e.g. <{0} or >{0}", input.trim()))?;
+
+        let digits_end = amount.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(amount.len());
+        let (number, unit) = amount.split_at(digits_end);
+        let number: f64 = number.parse().map_err(|_| format!("expected a number in size `{}`", input))?;
+
+        let multiplier: u64 = match unit.to_lowercase().as_str() {
+            "" | "b" => 1,
+            "k" | "kb" | "kib" => 1024,
+            "m" | "mb" | "mib" => 1024 * 1024,
+            "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+            "t" | "tb" | "tib" => 1024 * 1024 * 1024 * 1024,
+            _ => return Err(format!("unknown size unit `{}` (use b, k, m, g or t)", unit)),
+        };
+
+        Ok(Self { bound, bytes: (number * multiplier as f64) as u64 })
+    }
+
+    pub fn matches(&self, size: u64) -> bool {
+        match self.bound {
+            Bound::Below => size < self.bytes,
+            Bound::Above => size > self.bytes,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!("size {}{}", self.bound.symbol(), crate::util::format_size(self.bytes))
+    }
+}
+
+/// Modification time threshold, either an age like `<7d` (changed in the last week) or `>1y`,
+/// or a date like `>2024-01-01` (changed after New Year) or `<2024-01-01`
+#[derive(Clone, Debug)]
+pub struct AgeFilter {
+    pub bound: Bound,
+    pub threshold: TimeThreshold,
+}
+
+#[derive(Clone, Debug)]
+pub enum TimeThreshold {
+    Age(Duration),
+    Date(SystemTime, String),  // Midnight UTC, with the date as written for describe()
+}
+
+impl TimeThreshold {
+    fn parse(input: &str) -> Result<Self, String> {
+        if input.len() == 10 && input.as_bytes()[4] == b'-' {
+            Ok(TimeThreshold::Date(parse_date(input)?, input.to_string()))
+        } else {
+            Ok(TimeThreshold::Age(parse_duration(input)?))
+        }
+    }
+}
+
+impl AgeFilter {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let (bound, amount) = Bound::split(input.trim())
+            .ok_or_else(|| format!("age needs a comparison, e.g. <{0} or >{0}", input.trim()))?;
+        Ok(Self { bound, threshold: TimeThreshold::parse(amount)? })
+    }
+
+    /// --newer-than: younger than an age, or modified after a date
+    pub fn newer_than(input: &str) -> Result<Self, String> {
+        let threshold = TimeThreshold::parse(input.trim())?;
+        let bound = match threshold {
+            TimeThreshold::Age(_) => Bound::Below,
+            TimeThreshold::Date(..) => Bound::Above,
+        };
+        Ok(Self { bound, threshold })
+    }
+
+    /// --older-than: older than an age, or modified before a date
+    pub fn older_than(input: &str) -> Result<Self, String> {
+        let threshold = TimeThreshold::parse(input.trim())?;
+        let bound = match threshold {
+            TimeThreshold::Age(_) => Bound::Above,
+            TimeThreshold::Date(..) => Bound::Below,
+        };
+        Ok(Self { bound, threshold })
+    }
+
+    pub fn matches(&self, modified: SystemTime) -> bool {
+        match &self.threshold {
+            TimeThreshold::Age(limit) => {
+                // Timestamps in the future count as brand new
+                let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+                match self.bound {
+                    Bound::Below => age < *limit,
+                    Bound::Above => age > *limit,
+                }
+            }
+            TimeThreshold::Date(date, _) => match self.bound {
+                Bound::Below => modified < *date,
+                Bound::Above => modified > *date,
+            },
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.threshold {
+            TimeThreshold::Age(age) => {
+                format!("modified {}{}", self.bound.symbol(), crate::util::format_time_ago(*age).trim_end_matches(" ago"))
+            }
+            TimeThreshold::Date(_, date) => match self.bound {
+                Bound::Below => format!("modified before {}", date),
+                Bound::Above => format!("modified after {}", date),
+            },
+        }
+    }
+}
+
+/// Dates like `2024-01-01`, taken as midnight UTC
+pub fn parse_date(input: &str) -> Result<SystemTime, String> {
+    let invalid = || format!("expected a date like 2024-01-31, got `{}`", input);
+    let fields: Vec<i64> = input.split('-').map(|part| part.parse()).collect::<Result<_, _>>().map_err(|_| invalid())?;
+    let [year, month, day] = fields[..] else { return Err(invalid()) };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+
+    // Days since 1970-01-01 in the proleptic Gregorian calendar (Howard Hinnant's days_from_civil)
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let offset = Duration::from_secs(days.unsigned_abs() * 86400);
+    let time = if days >= 0 { SystemTime::UNIX_EPOCH.checked_add(offset) } else { SystemTime::UNIX_EPOCH.checked_sub(offset) };
+    time.ok_or_else(invalid)
+}
+
+/// Durations like `90s`, `30m`, `12h`, `7d`, `2w` or `1y`
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let digits_end = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+    let (number, unit) = input.split_at(digits_end);
+    let number: u64 = number.parse().map_err(|_| format!("expected a number in duration `{}`", input))?;
+
+    let seconds = match unit {
+        "s" => 1,
+        "m" | "min" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        "y" => 365 * 86400,
+        _ => return Err(format!("unknown duration unit `{}` (use s, m, h, d, w or y)", unit)),
+    };
+
+    Ok(Duration::from_secs(number * seconds))
+}
+
+/// Extension, category, size and age restrictions on results, shared by every way of specifying them
+#[derive(Clone, Debug, Default)]
+pub struct ResultFilters {
+    pub extensions: Vec<String>,  // Lowercase, without the leading dot
+    pub categories: Vec<Category>,
+    pub sizes: Vec<SizeFilter>,
+    pub ages: Vec<AgeFilter>,
+}
+
+impl ResultFilters {
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty() && self.categories.is_empty() && self.sizes.is_empty() && self.ages.is_empty()
+    }
+
+    /// Add extensions from a comma-separated list such as `rs,.toml,MD`
+    pub fn add_extensions(&mut self, list: &str) {
+        self.extensions.extend(
+            list.split(',')
+                .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                .filter(|ext| !ext.is_empty()),
+        );
+    }
+
+    /// Whether an entry passes every filter. Metadata is only fetched when a size or age filter needs it.
+    /// Directories have no extension, category or size, so they never pass those filters.
+    pub fn matches(&self, path: &Path, is_dir: bool, metadata: impl FnOnce() -> Option<Metadata>) -> bool {
+        self.matches_stats(path, is_dir, || metadata().map(|metadata| (metadata.len(), metadata.modified().ok())))
+    }
+
+    /// `matches` for entries without file system metadata, such as files inside an archive
+    pub fn matches_stats(&self, path: &Path, is_dir: bool, stats: impl FnOnce() -> Option<(u64, Option<SystemTime>)>) -> bool {
+        if !self.extensions.is_empty() {
+            let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+            if is_dir || !extension.is_some_and(|ext| self.extensions.contains(&ext)) {
+                return false;
+            }
+        }
+        if !self.categories.is_empty() {
+            let category = file_types::category(path.extension().and_then(|ext| ext.to_str()));
+            if is_dir || !category.is_some_and(|category| self.categories.contains(&category)) {
+                return false;
+            }
+        }
+
+        if self.sizes.is_empty() && self.ages.is_empty() {
+            return true;
+        }
+        if is_dir && !self.sizes.is_empty() {
+            return false;
+        }
+
+        let Some((size, modified)) = stats() else { return false };
+        self.sizes.iter().all(|filter| filter.matches(size))
+            && self.ages.iter().all(|filter| modified.is_some_and(|modified| filter.matches(modified)))
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.extensions.is_empty() {
+            parts.push(format!("ext {}", self.extensions.join(",")));
+        }
+        if !self.categories.is_empty() {
+            let names: Vec<&str> = self.categories.iter().map(|category| category.name()).collect();
+            parts.push(format!("type {}", names.join(",")));
+        }
+        parts.extend(self.sizes.iter().map(SizeFilter::describe));
+        parts.extend(self.ages.iter().map(AgeFilter::describe));
+        parts.join(" | ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_take_a_bound_and_a_unit() {
+        let below = SizeFilter::parse("<1M").unwrap();
+        assert_eq!((below.bound, below.bytes), (Bound::Below, 1024 * 1024));
+        let above = SizeFilter::parse(" +10kb ").unwrap();
+        assert_eq!((above.bound, above.bytes), (Bound::Above, 10 * 1024));
+        assert_eq!(SizeFilter::parse(">1.5G").unwrap().bytes, 3 * 512 * 1024 * 1024);
+        assert_eq!(SizeFilter::parse("-200").unwrap().bytes, 200);
+        assert_eq!(SizeFilter::parse(">2TiB").unwrap().bytes, 2 * 1024 * 1024 * 1024 * 1024);
+    }
+
+    #[test]
+    fn size_bounds_are_strict() {
+        let below = SizeFilter::parse("<100").unwrap();
+        assert!(below.matches(99));
+        assert!(!below.matches(100));
+        let above = SizeFilter::parse(">100").unwrap();
+        assert!(above.matches(101));
+        assert!(!above.matches(100));
+    }
+
+    #[test]
+    fn malformed_sizes_are_rejected() {
+        assert!(SizeFilter::parse("1M").unwrap_err().contains("comparison"));
+        assert!(SizeFilter::parse("<M").unwrap_err().contains("number"));
+        assert!(SizeFilter::parse("<5q").unwrap_err().contains("unit"));
+        assert!(SizeFilter::parse("").is_err());
+    }
+
+    #[test]
+    fn extension_lists_are_normalized() {
+        let mut filters = ResultFilters::default();
+        filters.add_extensions("rs, .TOML,,md");
+        assert_eq!(filters.extensions, ["rs", "toml", "md"]);
+        assert!(filters.matches_stats(Path::new("src/Main.RS"), false, || None));
+        assert!(!filters.matches_stats(Path::new("src"), true, || None));
+        assert!(!filters.matches_stats(Path::new("notes.txt"), false, || None));
+    }
+
+    #[test]
+    fn directories_never_pass_a_size_filter() {
+        let filters = ResultFilters { sizes: vec![SizeFilter::parse(">0").unwrap()], ..Default::default() };
+        assert!(filters.matches_stats(Path::new("a.bin"), false, || Some((1, None))));
+        assert!(!filters.matches_stats(Path::new("dir"), true, || Some((1, None))));
+        // Entries without stats can't be judged
+        assert!(!filters.matches_stats(Path::new("a.bin"), false, || None));
+    }
+
+    #[test]
+    fn durations_take_a_unit() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("5min"), Ok(Duration::from_secs(5 * 60)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::from_secs(14 * 86400)));
+        assert_eq!(parse_duration("1y"), Ok(Duration::from_secs(365 * 86400)));
+        assert!(parse_duration("7").unwrap_err().contains("unit"));
+        assert!(parse_duration("d").unwrap_err().contains("number"));
+    }
+
+    #[test]
+    fn dates_are_midnight_utc() {
+        let days = |date: &str| parse_date(date).unwrap().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() / 86400;
+        assert_eq!(days("1970-01-01"), 0);
+        assert_eq!(days("2000-03-01"), 11017);
+        assert_eq!(days("2024-02-29"), 19782);
+        assert!(parse_date("1969-12-31").unwrap() < SystemTime::UNIX_EPOCH);
+        assert!(parse_date("2024-13-01").is_err());
+        assert!(parse_date("2024-01-32").is_err());
+        assert!(parse_date("2024-1x-01").is_err());
+    }
+
+    #[test]
+    fn ages_compare_against_now() {
+        let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        assert!(AgeFilter::parse("<1d").unwrap().matches(hour_ago));
+        assert!(!AgeFilter::parse(">1d").unwrap().matches(hour_ago));
+        assert!(AgeFilter::parse("-2h").unwrap().matches(hour_ago));
+        // Timestamps in the future count as brand new
+        assert!(AgeFilter::parse("<1m").unwrap().matches(SystemTime::now() + Duration::from_secs(600)));
+        assert!(AgeFilter::parse("7d").unwrap_err().contains("comparison"));
+    }
+
+    #[test]
+    fn dates_compare_against_the_timestamp() {
+        let after = AgeFilter::parse(">2024-01-01").unwrap();
+        assert!(after.matches(parse_date("2024-06-01").unwrap()));
+        assert!(!after.matches(parse_date("2023-06-01").unwrap()));
+        assert_eq!(after.describe(), "modified after 2024-01-01");
+    }
+
+    #[test]
+    fn newer_and_older_than_pick_the_bound_for_ages_and_dates() {
+        assert_eq!(AgeFilter::newer_than("2d").unwrap().bound, Bound::Below);
+        assert_eq!(AgeFilter::newer_than("2024-01-01").unwrap().bound, Bound::Above);
+        assert_eq!(AgeFilter::older_than("2d").unwrap().bound, Bound::Above);
+        assert_eq!(AgeFilter::older_than("2024-01-01").unwrap().bound, Bound::Below);
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Files git lists under a directory, the only ones a --git-tracked, --git-dirty or
+/// --git-diff search looks at
+pub struct GitFiles {
+    root: PathBuf,  // Absolute directory the listed paths are relative to
+    files: HashSet<PathBuf>,
+    dirs: HashSet<PathBuf>,  // Every directory holding a listed file, the walk prunes the others
+    pub label: String,  // What was listed, for the search plan
+}
+
+impl GitFiles {
+    /// Files in git's index (`git ls-files`)
+    pub fn tracked(root: &Path) -> Result<Self, String> {
+        Self::list(root, &["ls-files", "-z"], "tracked".to_string())
+    }
+
+    /// Files that differ from `base` in the working tree or the index, HEAD by default:
+    /// modified and staged files, not untracked ones
+    pub fn changed(root: &Path, base: Option<&str>) -> Result<Self, String> {
+        let base = base.unwrap_or("HEAD");
+        // --relative keeps the paths relative to (and inside) the search root
+        Self::list(root, &["diff", "--name-only", "--relative", "-z", base, "--"], format!("changed since {}", base))
+    }
+
+    fn list(root: &Path, args: &[&str], label: String) -> Result<Self, String> {
+        let root = std::path::absolute(root).map_err(|e| e.to_string())?;
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&root)
+            .args(args)
+            .output()
+            .map_err(|e| format!("couldn't run git: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+
+        let mut files = HashSet::new();
+        let mut dirs = HashSet::new();
+        // -z lists paths unquoted, as git stores them (UTF-8)
+        for name in output.stdout.split(|&byte| byte == 0).filter(|name| !name.is_empty()) {
+            let path = PathBuf::from(String::from_utf8_lossy(name).into_owned());
+            for dir in path.ancestors().skip(1) {
+                // Seen before means its parents were too
+                if !dirs.insert(dir.to_path_buf()) {
+                    break;
+                }
+            }
+            files.insert(path);
+        }
+        Ok(Self { root, files, dirs, label })
+    }
+
+    /// Whether an entry is listed, or for a directory holds listed files. Anything outside
+    /// the listed directory (a narrowed search is always inside it) is left to the other rules.
+    pub fn contains(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(absolute) = std::path::absolute(path) else { return true };
+        let Ok(relative) = absolute.strip_prefix(&self.root) else { return true };
+        if relative.as_os_str().is_empty() {
+            return true;
+        }
+        if is_dir { self.dirs.contains(relative) } else { self.files.contains(relative) }
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+}
@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use crate::SearchResult;
+use crate::util;
+
+/// Hooks file in the per-user config directory
+pub const HOOKS_FILE: &str = "hooks.json";
+
+/// User commands run at points of the search flow, e.g. `"on_select": "code --goto {path}:{line}"`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Hooks {
+    pub on_select: Option<String>,    // Placeholders: {path} {dir} {name} {line}
+    pub post_search: Option<String>,  // Placeholders: {root} {pattern} {content} {count}
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.on_select.is_none() && self.post_search.is_none()
+    }
+
+    /// Where hooks are read from. Never the working directory: a checkout shipping its own
+    /// ff-config.json would otherwise get its commands run by the next search inside it.
+    pub fn user_file() -> Option<PathBuf> {
+        util::config_dir().map(|dir| dir.join(HOOKS_FILE))
+    }
+
+    /// The user's hooks, none without a hooks file. A broken file is reported and ignored.
+    pub fn load_user() -> Self {
+        let Some(path) = Self::user_file() else { return Self::default() };
+        let Ok(content) = std::fs::read_to_string(&path) else { return Self::default() };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("{} Ignoring hooks in {}: {}", "⚠️".yellow(), path.display(), e);
+            Self::default()
+        })
+    }
+}
+
+/// Values substituted for `{name}` placeholders, each shell-quoted on the way in (on Windows,
+/// read from an environment variable, see `substitute`)
+pub struct Placeholders<'a>(Vec<(&'a str, String)>);
+
+impl<'a> Placeholders<'a> {
+    pub fn for_result(result: &SearchResult) -> Self {
+        let dir = if result.is_dir { Some(result.path.as_path()) } else { result.path.parent() };
+        let line = result.content_matches.first().map(|m| m.line_number).unwrap_or(1);
+        Self(vec![
+            ("path", result.path.display().to_string()),
+            ("dir", dir.map(|dir| dir.display().to_string()).unwrap_or_default()),
+            ("name", result.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()),
+            ("line", line.to_string()),
+        ])
+    }
+
+    pub fn for_search(root: &Path, pattern: Option<&str>, content: Option<&str>, count: usize) -> Self {
+        Self(vec![
+            ("root", root.display().to_string()),
+            ("pattern", pattern.unwrap_or_default().to_string()),
+            ("content", content.unwrap_or_default().to_string()),
+            ("count", count.to_string()),
+        ])
+    }
+
+    /// Replace every known `{name}` in the template. Unknown ones are left as typed.
+    pub fn expand(&self, template: &str) -> String {
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let value = after
+                .find('}')
+                .and_then(|end| self.0.iter().find(|(name, _)| *name == &after[..end]).map(|(name, value)| (end, (*name, value))));
+            match value {
+                Some((end, (name, value))) => {
+                    out.push_str(&substitute(name, value));
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    out.push('{');
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// The variables `substitute` refers to. cmd.exe can't take a `"` inside a quoted value,
+    /// so those are dropped (Windows paths never have one).
+    #[cfg(windows)]
+    fn environment(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.0.iter().map(|(name, value)| (env_name(name), value.replace('"', "")))
+    }
+}
+
+/// A placeholder's value as it goes into the command
+#[cfg(not(windows))]
+fn substitute(_name: &str, value: &str) -> String {
+    shell_quote(value)
+}
+
+/// cmd.exe has no quoting that keeps `%VAR%` in a value from expanding, so the command reads the
+/// value from a variable instead: cmd expands `%FF_PATH%` once and never the text it expands to.
+/// `run` sets the variables and turns off delayed expansion, which would still see `!VAR!`.
+#[cfg(windows)]
+fn substitute(name: &str, _value: &str) -> String {
+    format!("\"%{}%\"", env_name(name))
+}
+
+/// `FF_PATH` for `{path}`
+#[cfg(windows)]
+fn env_name(placeholder: &str) -> String {
+    format!("FF_{}", placeholder.to_ascii_uppercase())
+}
+
+/// Quote a value so the shell passes it through as one literal argument
+#[cfg(not(windows))]
+pub fn shell_quote(value: &str) -> String {
+    posix_quote(value)
+}
+
+/// Single-quote a value for sh-style shells, leaving plain paths readable
+pub fn posix_quote(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || "/._-+:,@%=".contains(c));
+    if safe {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// Expand a hook and run it through the shell, reporting (not failing on) errors. What the
+/// hook prints goes to stderr, stdout carries only results (--print0, --export-env).
+pub fn run(hook_name: &str, template: &str, placeholders: &Placeholders) {
+    let command = placeholders.expand(template);
+    eprintln!("{} Running {} hook: {}", "🪝".cyan(), hook_name, command.dimmed());
+
+    let status = shell_command(&command, placeholders).stdout(Stdio::from(std::io::stderr())).status();
+
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("{} {} hook exited with {}", "⚠️".yellow(), hook_name, status),
+        Err(e) => eprintln!("{} Couldn't run {} hook: {}", "❌".red(), hook_name, e),
+    }
+}
+
+/// The shell running an expanded hook
+#[cfg(not(windows))]
+fn shell_command(command: &str, _placeholders: &Placeholders) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
+
+/// The shell running an expanded hook. The command goes in as typed, since the usual argument
+/// quoting escapes `"` in a way cmd.exe doesn't understand. /S strips just the outer quotes.
+#[cfg(windows)]
+fn shell_command(command: &str, placeholders: &Placeholders) -> Command {
+    use std::os::windows::process::CommandExt;
+    let mut shell = Command::new("cmd");
+    shell.args(["/V:OFF", "/S", "/C"]).raw_arg(format!("\"{}\"", command)).envs(placeholders.environment());
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_values_stay_readable() {
+        assert_eq!(posix_quote("/home/me/src/main.rs"), "/home/me/src/main.rs");
+        assert_eq!(posix_quote("a=b,c@d:1"), "a=b,c@d:1");
+    }
+
+    #[test]
+    fn anything_else_is_single_quoted() {
+        assert_eq!(posix_quote(""), "''");
+        assert_eq!(posix_quote("my file.txt"), "'my file.txt'");
+        assert_eq!(posix_quote("$(rm -rf ~)"), "'$(rm -rf ~)'");
+        assert_eq!(posix_quote("it's"), r"'it'\''s'");
+        assert_eq!(posix_quote("a;b|c&d`e`"), "'a;b|c&d`e`'");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn known_placeholders_are_replaced_and_quoted() {
+        let placeholders = Placeholders::for_search(Path::new("/srv/my code"), Some("main"), None, 3);
+        assert_eq!(placeholders.expand("echo {count} {pattern} in {root}"), "echo 3 main in '/srv/my code'");
+        assert_eq!(placeholders.expand("grep -c {content}"), "grep -c ''");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn unknown_and_unclosed_braces_are_left_as_typed() {
+        let placeholders = Placeholders::for_search(Path::new("/"), Some("x"), None, 0);
+        assert_eq!(placeholders.expand("awk '{print $1}' {pattern}"), "awk '{print $1}' x");
+        assert_eq!(placeholders.expand("{{pattern}}"), "{x}");
+        assert_eq!(placeholders.expand("echo {pattern"), "echo {pattern");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn substituted_values_are_not_expanded_again() {
+        let placeholders = Placeholders::for_search(Path::new("/"), Some("{root}"), None, 0);
+        assert_eq!(placeholders.expand("echo {pattern}"), "echo '{root}'");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn cmd_placeholders_read_variables() {
+        let placeholders = Placeholders::for_search(Path::new(r"C:\my code"), Some("x"), None, 3);
+        assert_eq!(placeholders.expand("echo {count} in {root}"), r#"echo "%FF_COUNT%" in "%FF_ROOT%""#);
+        assert!(placeholders.environment().any(|(name, value)| name == "FF_ROOT" && value == r"C:\my code"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn cmd_leaves_variables_and_carets_in_values_alone() {
+        let pattern = r#"%PATH% !PATH! a^b & "quoted""#;
+        let placeholders = Placeholders::for_search(Path::new("C:\\"), Some(pattern), None, 0);
+        let output = shell_command(&placeholders.expand("echo {pattern}"), &placeholders).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim_end(), r#""%PATH% !PATH! a^b & quoted""#);
+    }
+}
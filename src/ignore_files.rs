@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+/// Ignore files read anywhere, in increasing precedence. `.gitignore` comes before them, but only
+/// inside a git repository.
+const IGNORE_FILES: &[&str] = &[".ignore", ".ffignore"];
+
+/// Ignore files kept next to the code: `.gitignore`, `.ignore` and ff's own `.ffignore`, plus
+/// git's global excludes and `.git/info/exclude`. Read lazily, once per directory.
+pub struct IgnoreFiles {
+    root: PathBuf,           // The search root as the walker spells it
+    absolute_root: PathBuf,
+    repo_root: Option<PathBuf>,
+    repo_wide: Vec<Gitignore>,  // core.excludesFile and .git/info/exclude
+    per_dir: Mutex<HashMap<PathBuf, Arc<Gitignore>>>,
+}
+
+impl IgnoreFiles {
+    pub fn new(root: &Path) -> Self {
+        let absolute_root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+        let repo_root = absolute_root.ancestors().find(|dir| dir.join(".git").exists()).map(Path::to_path_buf);
+
+        let mut repo_wide = Vec::new();
+        if let Some(ref repo) = repo_root {
+            repo_wide.push(GitignoreBuilder::new(repo).build_global().0);
+            let mut exclude = GitignoreBuilder::new(repo);
+            exclude.add(repo.join(".git").join("info").join("exclude"));
+            repo_wide.extend(exclude.build().ok());
+        }
+
+        Self { root: root.to_path_buf(), absolute_root, repo_root, repo_wide, per_dir: Mutex::new(HashMap::new()) }
+    }
+
+    /// Whether the nearest ignore file with an opinion ignores the entry. Directories above the
+    /// search root count too, up to the repository root.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        if relative.as_os_str().is_empty() {
+            return false;
+        }
+        let path = self.absolute_root.join(relative);
+        let boundary = self.repo_root.as_deref().unwrap_or(&self.absolute_root);
+
+        for dir in path.ancestors().skip(1) {
+            match self.matcher(dir).matched(&path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+            if dir == boundary {
+                break;
+            }
+        }
+        self.repo_wide.iter().any(|excludes| excludes.matched(&path, is_dir).is_ignore())
+    }
+
+    fn matcher(&self, dir: &Path) -> Arc<Gitignore> {
+        if let Some(matcher) = self.per_dir.lock().unwrap().get(dir) {
+            return matcher.clone();
+        }
+
+        // Later files win, so .ffignore can re-include what .gitignore leaves out
+        let mut builder = GitignoreBuilder::new(dir);
+        if self.repo_root.is_some() {
+            builder.add(dir.join(".gitignore"));
+        }
+        for name in IGNORE_FILES {
+            builder.add(dir.join(name));
+        }
+        let matcher = Arc::new(builder.build().unwrap_or_else(|_| Gitignore::empty()));
+        self.per_dir.lock().unwrap().insert(dir.to_path_buf(), matcher.clone());
+        matcher
+    }
+}
@@ -0,0 +1,502 @@
+use std::path::Path;
+use crate::config::Config;
+
+/// A single gitignore-style pattern compiled from the config ignore lists
+#[derive(Debug, Clone)]
+pub struct IgnorePattern {
+    source: String,
+    segments: Vec<Vec<char>>,
+    negated: bool,
+    anchored: bool,
+    dir_only: bool,
+}
+
+impl IgnorePattern {
+    /// Parse a pattern line, returns None for blank lines and comments
+    pub fn parse(raw: &str) -> Option<Self> {
+        let mut pattern = raw.trim();
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return None;
+        }
+
+        let negated = pattern.starts_with('!');
+        if negated {
+            pattern = &pattern[1..];
+        } else if pattern.starts_with("\\!") || pattern.starts_with("\\#") {
+            pattern = &pattern[1..]; // Escaped literal '!' or '#'
+        }
+
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+
+        // A leading or middle slash anchors the pattern to the search root,
+        // otherwise it matches the entry name at any depth (like gitignore)
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+
+        let segments = pattern
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.chars().collect())
+            .collect();
+
+        Some(Self { source: raw.trim().to_string(), segments, negated, anchored, dir_only })
+    }
+
+    /// The pattern as written, directory-only rules always shown with a trailing slash
+    pub fn describe(&self) -> String {
+        let mut text = self.source.clone();
+        if self.negated && !text.starts_with('!') {
+            text.insert(0, '!');
+        }
+        if self.dir_only && !text.ends_with('/') {
+            text.push('/');
+        }
+        text
+    }
+
+    /// Check the pattern against a path relative to the search root
+    pub fn matches(&self, components: &[String], is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+
+        if self.anchored {
+            match_segments(&self.segments, components)
+        } else {
+            match components.last() {
+                Some(name) => glob_match(&self.segments[0], &name.chars().collect::<Vec<_>>()),
+                None => false,
+            }
+        }
+    }
+}
+
+/// A --exclude pattern: a glob when it has wildcards (`*.lock`), otherwise a substring (`test`).
+/// Matched case-insensitively against entry names, or with a `/` against the path relative to the root.
+#[derive(Debug, Clone)]
+pub struct ExcludePattern {
+    source: String,
+    pattern: Vec<char>,  // Lowercased
+    glob: bool,
+    whole_path: bool,
+}
+
+impl ExcludePattern {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let source = raw.trim();
+        if source.is_empty() {
+            return None;
+        }
+        Some(Self {
+            source: source.to_string(),
+            pattern: source.to_lowercase().chars().collect(),
+            glob: source.contains(['*', '?', '[']),
+            whole_path: source.contains('/'),
+        })
+    }
+
+    pub fn describe(&self) -> &str {
+        &self.source
+    }
+
+    fn matches(&self, components: &[String]) -> bool {
+        let target = if self.whole_path {
+            components.join("/")
+        } else {
+            components.last().cloned().unwrap_or_default()
+        };
+        let target: Vec<char> = target.to_lowercase().chars().collect();
+        if self.glob {
+            glob_match(&self.pattern, &target)
+        } else {
+            target.windows(self.pattern.len()).any(|window| window == self.pattern.as_slice())
+        }
+    }
+}
+
+/// First bytes of a valid CACHEDIR.TAG (https://bford.info/cachedir/)
+const CACHEDIR_TAG_SIGNATURE: &[u8] = b"Signature: 8a477f597d28d172789f06886806bc55";
+
+/// Files whose presence marks a directory as generated build or dependency output
+const BUILD_OUTPUT_MARKERS: &[(&str, &str)] = &[
+    (".rustc_info.json", "Cargo target directory"),
+    ("CMakeCache.txt", "CMake build directory"),
+    ("pyvenv.cfg", "Python virtualenv"),
+    (".package-lock.json", "npm dependencies"),
+    (".yarn-integrity", "Yarn dependencies"),
+];
+
+/// Effective ignore rule set for one search, compiled once from the config
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    directories: Vec<IgnorePattern>,
+    files: Vec<IgnorePattern>,
+    heuristics: bool,                // Also skip directories that look like build output
+    cache_tags: bool,                // Skip directories holding a CACHEDIR.TAG
+    ignore_files: bool,              // Honor .gitignore, .ignore and git's excludes found on disk
+    included: Vec<IgnorePattern>,    // --include-dir names, exempt from the heuristics too
+    excluded: Vec<ExcludePattern>,   // --exclude patterns, not affected by the --no-ignore-* flags
+}
+
+impl IgnoreRules {
+    pub fn from_config(config: &Config) -> Self {
+        let directories = config.ignore_directories
+            .iter()
+            .filter_map(|p| IgnorePattern::parse(p))
+            .map(|mut p| {
+                p.dir_only = true; // Entries of ignore_directories only ever apply to directories
+                p
+            })
+            .collect();
+
+        let files = config.ignore_file_patterns
+            .iter()
+            .filter_map(|p| IgnorePattern::parse(p))
+            .collect();
+
+        Self { directories, files, heuristics: config.heuristic_ignores, cache_tags: true, ignore_files: config.respect_ignore_files, included: Vec::new(), excluded: Vec::new() }
+    }
+
+    /// Drop every ignore_directories rule and the build-output heuristics for this search (--no-ignore-dirs)
+    pub fn clear_directories(&mut self) {
+        self.directories.clear();
+        self.heuristics = false;
+    }
+
+    /// Drop every ignore_file_patterns rule for this search (--no-ignore-files)
+    pub fn clear_files(&mut self) {
+        self.files.clear();
+    }
+
+    /// Skip one more directory pattern, like an extra ignore_directories entry
+    pub fn ignore_directory(&mut self, raw: &str) {
+        if let Some(mut pattern) = IgnorePattern::parse(raw) {
+            pattern.dir_only = true;
+            self.directories.push(pattern);
+        }
+    }
+
+    /// Re-include a directory the config would ignore (--include-dir), works like `!name/`
+    pub fn include_directory(&mut self, name: &str) {
+        if let Some(mut pattern) = IgnorePattern::parse(name.trim_start_matches('!')) {
+            pattern.dir_only = true;
+            self.included.push(pattern.clone());
+            pattern.negated = true;
+            self.directories.push(pattern);
+        }
+    }
+
+    /// Search directories marked with CACHEDIR.TAG too (--include-caches)
+    pub fn include_caches(&mut self) {
+        self.cache_tags = false;
+    }
+
+    pub fn cache_tags_respected(&self) -> bool {
+        self.cache_tags
+    }
+
+    /// Search entries that .gitignore, .ignore or git's excludes leave out (--no-ignore)
+    pub fn skip_ignore_files(&mut self) {
+        self.ignore_files = false;
+    }
+
+    pub fn ignore_files_respected(&self) -> bool {
+        self.ignore_files
+    }
+
+    /// Whether the directory is a tagged cache that should be skipped. --include-dir names are exempt.
+    pub fn is_tagged_cache(&self, dir: &Path, relative_path: &Path) -> bool {
+        use std::io::Read;
+
+        if !self.cache_tags || self.explicitly_included(relative_path) {
+            return false;
+        }
+        let Ok(file) = std::fs::File::open(dir.join("CACHEDIR.TAG")) else { return false };
+        let mut header = Vec::with_capacity(CACHEDIR_TAG_SIGNATURE.len());
+        file.take(CACHEDIR_TAG_SIGNATURE.len() as u64).read_to_end(&mut header).is_ok()
+            && header == CACHEDIR_TAG_SIGNATURE
+    }
+
+    /// Whether --include-dir names this directory (the search root always counts)
+    pub fn explicitly_included(&self, relative_path: &Path) -> bool {
+        let components = path_components(relative_path);
+        components.is_empty() || self.included.iter().any(|p| p.matches(&components, true))
+    }
+
+    pub fn heuristics_enabled(&self) -> bool {
+        self.heuristics
+    }
+
+    /// Why a directory looks like generated build or dependency output, judged by marker files
+    /// inside it. Costs a few stats per directory, so only called for directories not already ignored.
+    pub fn build_output_reason(&self, dir: &Path, relative_path: &Path) -> Option<&'static str> {
+        if !self.heuristics || self.explicitly_included(relative_path) {
+            return None;
+        }
+
+        BUILD_OUTPUT_MARKERS
+            .iter()
+            .find(|(marker, _)| dir.join(marker).is_file())
+            .map(|(_, reason)| *reason)
+    }
+
+    /// Leave out entries matching a --exclude pattern, and everything inside excluded directories
+    pub fn exclude(&mut self, raw: &str) {
+        self.excluded.extend(ExcludePattern::parse(raw));
+    }
+
+    pub fn exclude_patterns(&self) -> &[ExcludePattern] {
+        &self.excluded
+    }
+
+    pub fn is_excluded(&self, relative_path: &Path) -> bool {
+        if self.excluded.is_empty() {
+            return false;
+        }
+        let components = path_components(relative_path);
+        !components.is_empty() && self.excluded.iter().any(|p| p.matches(&components))
+    }
+
+    pub fn directory_patterns(&self) -> &[IgnorePattern] {
+        &self.directories
+    }
+
+    pub fn file_patterns(&self) -> &[IgnorePattern] {
+        &self.files
+    }
+
+    /// Decide whether an entry should be skipped. The search root itself is never ignored.
+    pub fn is_ignored(&self, relative_path: &Path, is_dir: bool) -> bool {
+        let components = path_components(relative_path);
+
+        if components.is_empty() {
+            return false;
+        }
+
+        last_match_ignores(&self.directories, &components, is_dir)
+            || last_match_ignores(&self.files, &components, is_dir)
+    }
+}
+
+fn path_components(relative_path: &Path) -> Vec<String> {
+    relative_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Later patterns override earlier ones, so `!keep.log` after `*.log` re-includes it
+fn last_match_ignores(patterns: &[IgnorePattern], components: &[String], is_dir: bool) -> bool {
+    patterns
+        .iter()
+        .rev()
+        .find(|p| p.matches(components, is_dir))
+        .is_some_and(|p| !p.negated)
+}
+
+/// Match pattern segments against path components, `**` spans any number of directories
+fn match_segments(segments: &[Vec<char>], components: &[String]) -> bool {
+    match segments.split_first() {
+        None => components.is_empty(),
+        Some((first, rest)) if first.as_slice() == ['*', '*'] => {
+            if rest.is_empty() {
+                // Trailing `/**` matches everything inside, but not the directory itself
+                return !components.is_empty();
+            }
+            (0..=components.len()).any(|skip| match_segments(rest, &components[skip..]))
+        }
+        Some((first, rest)) => match components.split_first() {
+            Some((component, remaining)) => {
+                glob_match(first, &component.chars().collect::<Vec<_>>())
+                    && match_segments(rest, remaining)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Single-segment wildcard match supporting `*`, `?` and `[...]` classes
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') => {
+            let rest = &pattern[1..];
+            (0..=text.len()).any(|skip| glob_match(rest, &text[skip..]))
+        }
+        Some('?') => !text.is_empty() && glob_match(&pattern[1..], &text[1..]),
+        Some('[') => {
+            let Some(&c) = text.first() else { return false };
+            match match_class(&pattern[1..], c) {
+                Some((matched, consumed)) => matched && glob_match(&pattern[1 + consumed..], &text[1..]),
+                None => c == '[' && glob_match(&pattern[1..], &text[1..]), // Unclosed bracket is literal
+            }
+        }
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &text[1..])
+        }
+        Some(&p) => text.first() == Some(&p) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Evaluate a bracket class, returns (matched, chars consumed including the closing `]`)
+fn match_class(class: &[char], c: char) -> Option<(bool, usize)> {
+    let negated = matches!(class.first(), Some('!') | Some('^'));
+    let mut i = if negated { 1 } else { 0 };
+    let mut matched = false;
+    let mut first = true;
+
+    while i < class.len() {
+        if class[i] == ']' && !first {
+            return Some((matched != negated, i + 1));
+        }
+        first = false;
+
+        if i + 2 < class.len() && class[i + 1] == '-' && class[i + 2] != ']' {
+            if class[i] <= c && c <= class[i + 2] {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if class[i] == c {
+                matched = true;
+            }
+            i += 1;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use super::*;
+
+    fn matches(pattern: &str, path: &str, is_dir: bool) -> bool {
+        IgnorePattern::parse(pattern).unwrap().matches(&path_components(Path::new(path)), is_dir)
+    }
+
+    fn rules(directories: &[&str], files: &[&str]) -> IgnoreRules {
+        let config = Config {
+            ignore_directories: directories.iter().map(|p| p.to_string()).collect(),
+            ignore_file_patterns: files.iter().map(|p| p.to_string()).collect(),
+            ..Config::default()
+        };
+        IgnoreRules::from_config(&config)
+    }
+
+    #[test]
+    fn blank_lines_and_comments_are_not_patterns() {
+        assert!(IgnorePattern::parse("").is_none());
+        assert!(IgnorePattern::parse("   ").is_none());
+        assert!(IgnorePattern::parse("# comment").is_none());
+        assert!(IgnorePattern::parse("/").is_none());
+        assert!(IgnorePattern::parse(r"\#literal").is_some());
+    }
+
+    #[test]
+    fn unanchored_patterns_match_the_name_at_any_depth() {
+        assert!(matches("*.log", "debug.log", false));
+        assert!(matches("*.log", "a/b/debug.log", false));
+        assert!(!matches("*.log", "debug.log.txt", false));
+        assert!(matches("node_modules", "web/node_modules", true));
+    }
+
+    #[test]
+    fn a_slash_anchors_the_pattern_to_the_root() {
+        assert!(matches("/build", "build", true));
+        assert!(!matches("/build", "src/build", true));
+        assert!(matches("docs/*.md", "docs/readme.md", false));
+        assert!(!matches("docs/*.md", "src/docs/readme.md", false));
+        assert!(!matches("docs/*.md", "docs/guide/readme.md", false));
+    }
+
+    #[test]
+    fn trailing_slash_only_matches_directories() {
+        assert!(matches("cache/", "cache", true));
+        assert!(!matches("cache/", "cache", false));
+    }
+
+    #[test]
+    fn double_star_spans_directories() {
+        assert!(matches("**/fixtures", "fixtures", true));
+        assert!(matches("**/fixtures", "a/b/fixtures", true));
+        assert!(matches("src/**/*.rs", "src/main.rs", false));
+        assert!(matches("src/**/*.rs", "src/a/b/lib.rs", false));
+        assert!(!matches("src/**/*.rs", "tests/lib.rs", false));
+        // A trailing /** matches what's inside, not the directory itself
+        assert!(matches("logs/**", "logs/today.txt", false));
+        assert!(!matches("logs/**", "logs", true));
+    }
+
+    #[test]
+    fn wildcards_and_character_classes() {
+        assert!(matches("file?.txt", "file1.txt", false));
+        assert!(!matches("file?.txt", "file.txt", false));
+        assert!(matches("*.[ch]", "main.c", false));
+        assert!(!matches("*.[ch]", "main.o", false));
+        assert!(matches("v[0-9].txt", "v7.txt", false));
+        assert!(!matches("v[!0-9].txt", "v7.txt", false));
+        assert!(matches("v[^0-9].txt", "vx.txt", false));
+        assert!(matches("[]].txt", "].txt", false));
+        // An unclosed bracket is a literal
+        assert!(matches("[abc", "[abc", false));
+        assert!(matches(r"\*.txt", "*.txt", false));
+        assert!(!matches(r"\*.txt", "a.txt", false));
+    }
+
+    #[test]
+    fn later_negations_re_include() {
+        let keeping = rules(&[], &["*.log", "!keep.log"]);
+        assert!(keeping.is_ignored(Path::new("debug.log"), false));
+        assert!(!keeping.is_ignored(Path::new("keep.log"), false));
+
+        // The last matching pattern decides
+        let ignoring = rules(&[], &["!keep.log", "*.log"]);
+        assert!(ignoring.is_ignored(Path::new("keep.log"), false));
+    }
+
+    #[test]
+    fn directory_rules_skip_only_directories_and_never_the_root() {
+        let rules = rules(&["target"], &[]);
+        assert!(rules.is_ignored(Path::new("target"), true));
+        assert!(rules.is_ignored(Path::new("crates/a/target"), true));
+        assert!(!rules.is_ignored(Path::new("target"), false));
+        assert!(!rules.is_ignored(Path::new(""), true));
+    }
+
+    #[test]
+    fn include_directory_overrides_ignore_directories() {
+        let mut rules = rules(&["build"], &[]);
+        rules.include_directory("build");
+        assert!(!rules.is_ignored(Path::new("build"), true));
+        assert!(rules.explicitly_included(Path::new("build")));
+    }
+
+    #[test]
+    fn describe_keeps_negation_and_directory_slash() {
+        assert_eq!(IgnorePattern::parse("!keep.log").unwrap().describe(), "!keep.log");
+        assert_eq!(IgnorePattern::parse("cache/").unwrap().describe(), "cache/");
+        let mut rules = rules(&["dist"], &[]);
+        rules.include_directory("dist");
+        assert_eq!(rules.directory_patterns()[0].describe(), "dist/");
+        assert_eq!(rules.directory_patterns()[1].describe(), "!dist/");
+    }
+
+    #[test]
+    fn exclude_patterns_are_substrings_or_globs() {
+        let mut rules = IgnoreRules::default();
+        rules.exclude("test");
+        rules.exclude("*.LOCK");
+        rules.exclude("docs/old");
+        assert!(rules.is_excluded(Path::new("src/my_tests.rs")));
+        assert!(rules.is_excluded(Path::new("Cargo.lock")));
+        assert!(rules.is_excluded(Path::new("docs/old")));
+        assert!(!rules.is_excluded(Path::new("old")));
+        assert!(!rules.is_excluded(Path::new("src/main.rs")));
+    }
+}
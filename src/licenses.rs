@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::OnceLock;
+use colored::Colorize;
+use regex::Regex;
+use crate::config::Config;
+use crate::file_types::{self, Category};
+use crate::search::{self, SearchOptions};
+use crate::util;
+use crate::walker;
+use crate::warnings::Warnings;
+
+/// Bytes read from the top of each file, a header never needs more
+const HEADER_BYTES: u64 = 16 * 1024;
+
+/// Phrases of the standard license notices, for headers without an SPDX line. GPL and BSD
+/// notices don't reliably say which version or variant, so they only name the family.
+const NOTICE_PHRASES: &[(&str, &str)] = &[
+    ("licensed under the apache license, version 2.0", "Apache-2.0"),
+    ("permission is hereby granted, free of charge", "MIT"),
+    ("gnu affero general public license", "AGPL"),
+    ("gnu lesser general public license", "LGPL"),
+    ("gnu general public license", "GPL"),
+    ("mozilla public license, v. 2.0", "MPL-2.0"),
+    ("redistribution and use in source and binary forms", "BSD"),
+    ("this is free and unencumbered software released into the public domain", "Unlicense"),
+];
+
+/// What a file's header says about its license
+struct Header {
+    license: Option<License>,
+    copyright: bool,
+}
+
+struct License {
+    id: String,
+    from_spdx: bool,  // An SPDX-License-Identifier line, otherwise recognized notice text
+}
+
+impl License {
+    /// A dual-licensed file (MIT OR Apache-2.0) agrees with either license, and a notice naming
+    /// only the family (GPL) with any identifier of it (GPL-3.0-only)
+    fn agrees_with(&self, expected: &str) -> bool {
+        let expected = expected.to_lowercase();
+        self.id
+            .split(" OR ")
+            .map(|id| id.trim_matches(|c: char| c == '(' || c == ')' || c.is_whitespace()).to_lowercase())
+            .any(|id| id == expected || (!self.from_spdx && expected.starts_with(&id)))
+    }
+}
+
+#[derive(Default)]
+struct Findings {
+    files: usize,
+    copyright: usize,
+    licenses: HashMap<String, (usize, usize)>,  // Files per license id, (SPDX, notice text)
+    declared: Vec<(PathBuf, License)>,
+    missing: Vec<PathBuf>,
+}
+
+/// `ff licenses` - check the top of every source file for an SPDX identifier or a license notice
+pub fn run_licenses(root: &Path, options: &SearchOptions, config: &Config, header_lines: usize, expect: Option<&str>) {
+    eprintln!("{} License headers: {}", "📜".green(), root.display().to_string().cyan());
+
+    let filter = search::entry_filter(root, options, config, Arc::new(AtomicBool::new(true)));
+    let mut findings = Findings::default();
+    let mut warnings = Warnings::default();
+    let mut unreadable = Vec::new();
+
+    for entry in walker::for_backend(options.backend).walk(filter.clone()) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warnings.walk_error(&e);
+                continue;
+            }
+        };
+        let extension = entry.path().extension().and_then(|ext| ext.to_str());
+        if entry.is_dir() || file_types::category(extension) != Some(Category::Code) {
+            continue;
+        }
+
+        let header = match read_header(entry.path(), header_lines) {
+            Ok(Some(header)) => header,
+            Ok(None) => continue,
+            Err(e) => {
+                unreadable.push((entry.path().to_path_buf(), e.to_string()));
+                continue;
+            }
+        };
+        findings.files += 1;
+        findings.copyright += header.copyright as usize;
+        let relative = entry.path().strip_prefix(root).unwrap_or(entry.path()).to_path_buf();
+        match header.license {
+            Some(license) => {
+                let counts = findings.licenses.entry(license.id.clone()).or_default();
+                if license.from_spdx { counts.0 += 1 } else { counts.1 += 1 }
+                findings.declared.push((relative, license));
+            }
+            None => findings.missing.push(relative),
+        }
+    }
+
+    warnings.abandoned_dirs(&filter.abandoned_dirs());
+    warnings.reparse_points(&filter.skipped_reparse_points());
+    warnings.capped_dirs(&filter.capped_dirs(), options.dir_entry_cap);
+    warnings.cache_dirs(&filter.skipped_caches());
+    warnings.ignored_entries(&filter.skipped_ignored());
+    warnings.build_dirs(&filter.skipped_build_dirs());
+    warnings.symlink_loops(&filter.skipped_loops());
+    warnings.unreadable_files(&mut unreadable);
+
+    print_report(&mut findings, expect);
+    warnings.print(options.verbose);
+}
+
+/// The license and copyright notice in the first `header_lines` lines. None for binary files.
+fn read_header(path: &Path, header_lines: usize) -> std::io::Result<Option<Header>> {
+    let mut bytes = Vec::new();
+    File::open(path)?.take(HEADER_BYTES).read_to_end(&mut bytes)?;
+    let Some(text) = crate::encoding::decode(&bytes) else { return Ok(None) };
+    let lines: Vec<&str> = text.lines().take(header_lines).collect();
+
+    static SPDX: OnceLock<Regex> = OnceLock::new();
+    static COPYRIGHT: OnceLock<Regex> = OnceLock::new();
+    let spdx = SPDX.get_or_init(|| Regex::new(r"SPDX-License-Identifier:\s*(.+)").unwrap());
+    let copyright = COPYRIGHT.get_or_init(|| Regex::new(r"(?i)\bcopyright\b|©").unwrap());
+
+    let spdx_license = lines.iter().find_map(|line| spdx.captures(line)).map(|captures| {
+        // The identifier runs to the end of the line, minus whatever closes the comment
+        let id = captures[1].trim().trim_end_matches("*/").trim_end_matches("-->").trim();
+        License { id: id.to_string(), from_spdx: true }
+    });
+    let license = spdx_license.or_else(|| {
+        // Notices wrap over several comment lines, so compare them as one run of words
+        let words: Vec<&str> = lines
+            .iter()
+            .map(|line| line.trim_start_matches(|c: char| c.is_whitespace() || "/*#;-!'\"".contains(c)))
+            .flat_map(str::split_whitespace)
+            .collect();
+        let prose = words.join(" ").to_lowercase();
+        NOTICE_PHRASES
+            .iter()
+            .find(|(phrase, _)| prose.contains(phrase))
+            .map(|(_, id)| License { id: id.to_string(), from_spdx: false })
+    });
+
+    Ok(Some(Header { license, copyright: lines.iter().any(|line| copyright.is_match(line)) }))
+}
+
+fn print_report(findings: &mut Findings, expect: Option<&str>) {
+    let declared = findings.declared.len();
+    println!();
+    println!("{} {} source files: {} with a license header, {} without, {} with a copyright notice",
+             "📊".yellow(),
+             findings.files.to_string().bright_green().bold(),
+             declared.to_string().bright_green().bold(),
+             findings.missing.len().to_string().bright_red().bold(),
+             findings.copyright.to_string().bright_green().bold());
+    if findings.files == 0 {
+        return;
+    }
+
+    let mut licenses: Vec<_> = findings.licenses.iter().collect();
+    licenses.sort_by(|a, b| (b.1.0 + b.1.1).cmp(&(a.1.0 + a.1.1)).then(a.0.cmp(b.0)));
+    if !licenses.is_empty() {
+        println!("\n{}", "LICENSES:".yellow().bold());
+        for (id, (spdx, notice)) in &licenses {
+            println!("  {:<20} {:>8} files  {}", id.blue(), spdx + notice,
+                     format!("(SPDX {}, notice text {})", spdx, notice).dimmed());
+        }
+    }
+
+    if !findings.missing.is_empty() {
+        findings.missing.sort();
+        println!("\n{}", format!("MISSING HEADER ({} files):", findings.missing.len()).yellow().bold());
+        for path in &findings.missing {
+            println!("  {}", util::display_path(path).cyan());
+        }
+    }
+
+    // Without --expect the project's license is taken to be the one most files declare
+    let (expected, reason) = match expect {
+        Some(expected) => (expected.to_string(), "--expect"),
+        None => match licenses.first() {
+            Some((id, _)) => (id.to_string(), "most common"),
+            None => return,
+        },
+    };
+    let mut mismatched: Vec<_> = findings.declared.iter().filter(|(_, license)| !license.agrees_with(&expected)).collect();
+    if !mismatched.is_empty() {
+        mismatched.sort_by(|a, b| a.0.cmp(&b.0));
+        println!("\n{}", format!("MISMATCHED, expected {} ({}) ({} files):", expected, reason, mismatched.len()).yellow().bold());
+        for (path, license) in mismatched {
+            println!("  {:<20} {}", license.id.red(), util::display_path(path).cyan());
+        }
+    }
+}
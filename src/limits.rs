@@ -0,0 +1,145 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+use colored::Colorize;
+
+/// Outcome of checking a running walk against its limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitDecision {
+    Continue,
+    Stop,
+}
+
+/// Entry-count and elapsed-time limits for one walk. Interactive runs are asked
+/// whether to keep going; piped runs stop at the entry cap and only warn about time.
+pub struct SearchLimits {
+    max_files: usize,
+    cap_step: usize,
+    time_limit: Option<Duration>,
+    deadline: Option<Instant>,
+    strict_time_limit: bool,  // A budget (ff deep): stop at the deadline without asking
+    interactive: bool,
+    time_warned: bool,
+    cap_reached: bool,
+}
+
+impl SearchLimits {
+    pub fn new(max_files: usize, time_limit: Option<Duration>, strict_time_limit: bool) -> Self {
+        Self {
+            max_files,
+            cap_step: max_files,
+            time_limit,
+            deadline: time_limit.map(|limit| Instant::now() + limit),
+            strict_time_limit,
+            interactive: io::stdin().is_terminal() && io::stderr().is_terminal(),
+            time_warned: false,
+            cap_reached: false,
+        }
+    }
+
+    /// Whether the walk stopped at the entry cap (as opposed to finishing or being narrowed)
+    pub fn cap_reached(&self) -> bool {
+        self.cap_reached
+    }
+
+    /// Call before visiting each entry, with the number of entries visited so far
+    pub fn check(&mut self, visited: usize) -> LimitDecision {
+        if visited >= self.max_files {
+            if !self.interactive {
+                self.cap_reached = true;
+                return LimitDecision::Stop;
+            }
+
+            let reason = format!("Visited {} entries (max_files_per_search)", visited);
+            return match ask(&reason) {
+                Choice::KeepGoing => {
+                    self.max_files = self.max_files.saturating_add(self.cap_step);
+                    LimitDecision::Continue
+                }
+                Choice::Narrow => {
+                    print_narrowing_hints();
+                    LimitDecision::Stop
+                }
+                Choice::Stop => {
+                    self.cap_reached = true;
+                    LimitDecision::Stop
+                }
+            };
+        }
+
+        let (Some(deadline), Some(limit)) = (self.deadline, self.time_limit) else {
+            return LimitDecision::Continue;
+        };
+        if Instant::now() < deadline {
+            return LimitDecision::Continue;
+        }
+
+        if self.strict_time_limit {
+            clear_progress_line();
+            eprintln!("{}  Time budget of {}s used up after {} entries, showing what was found so far",
+                      "⏱️".yellow(), limit.as_secs(), visited);
+            self.deadline = None;
+            return LimitDecision::Stop;
+        }
+
+        if !self.interactive {
+            if !self.time_warned {
+                self.time_warned = true;
+                clear_progress_line();
+                eprintln!("{}  Search has been running for over {}s, still going. Narrow it with {}, {} or {}",
+                          "⏱️".yellow(), limit.as_secs(), "--path".blue(), "--only-dirs".blue(), "--query".blue());
+            }
+            return LimitDecision::Continue;
+        }
+
+        let reason = format!("Still searching after {}s ({} entries so far)", limit.as_secs(), visited);
+        match ask(&reason) {
+            Choice::KeepGoing => {
+                self.deadline = Some(Instant::now() + limit);
+                LimitDecision::Continue
+            }
+            Choice::Narrow => {
+                print_narrowing_hints();
+                LimitDecision::Stop
+            }
+            Choice::Stop => LimitDecision::Stop,
+        }
+    }
+}
+
+enum Choice {
+    KeepGoing,
+    Narrow,
+    Stop,
+}
+
+fn clear_progress_line() {
+    eprint!("\r{}\r", " ".repeat(80));
+}
+
+fn ask(reason: &str) -> Choice {
+    clear_progress_line();
+    loop {
+        eprint!("{}  {}. {}eep going, {}arrow search, or {}top? [K/n/s] ",
+                "⏸️".yellow(), reason, "k".green(), "n".yellow(), "s".red());
+        io::stderr().flush().unwrap();
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input).is_err() {
+            return Choice::Stop;
+        }
+
+        match input.trim().to_lowercase().as_str() {
+            "" | "k" | "keep" | "y" => return Choice::KeepGoing,
+            "n" | "narrow" => return Choice::Narrow,
+            "s" | "stop" | "q" => return Choice::Stop,
+            _ => eprintln!("{} Please answer k, n or s", "❌".red()),
+        }
+    }
+}
+
+fn print_narrowing_hints() {
+    eprintln!("{} Stopping here. To narrow the search next time:", "💡".yellow());
+    eprintln!("   {} - search a subdirectory", "--path <dir>".blue());
+    eprintln!("   {} - only walk some top-level directories", "--only-dirs src,docs".blue());
+    eprintln!("   {} - filter by extension, size or age", "-q 'name:x ext:rs mtime:<7d'".blue());
+}
@@ -2,22 +2,57 @@ mod cli;
 mod util;
 mod search;
 mod config;
+mod config_sync;
+mod ignore_rules;
+mod ignore_files;
+mod watchdog;
+mod stats;
+mod timeline;
+mod licenses;
+mod limits;
+mod walker;
+mod content_cache;
+mod filters;
+mod query;
+mod warnings;
+mod hooks;
+mod export_env;
+mod report;
+mod content_query;
+mod project;
+mod extract;
+mod encoding;
+mod archive;
+mod file_types;
+mod syntax;
+mod git;
+mod elevate;
+mod redact;
 
 use clap::Parser;
 use colored::*;
-use std::path::{PathBuf};
-use crate::cli::{Cli};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use crate::cli::{Cli, Commands, ConfigCommand, SyncCommand};
 use figlet_rs::FIGfont;
 use config::Config;
+use ignore_rules::IgnoreRules;
 
 #[derive(Debug)]
 struct SearchResult {
     path: PathBuf,
+    archive_entry: Option<String>,  // Path inside the archive at `path`, for --archives results
     score: i64,
     is_dir: bool,
     size: Option<u64>,
+    disk_size: Option<u64>,  // Only set for sparse files, when less is allocated than `size`
     modified: Option<std::time::SystemTime>,
+    name_match: Option<search::NameMatch>,  // None when only the content matched
+    name_floor: Option<i64>,  // Fuzzy score the name had to reach, from --min-score
     pub content_matches: Vec<ContentMatch>,
+    content_count: usize,  // Matches in the file, also set when --count stores none of them
+    content_truncated: bool,  // Content scan stopped at max_content_bytes_per_file
+    peek: Option<search::DirPeek>,  // First entries of a directory result, with --peek
     pub search_type: SearchType,
 }
 
@@ -27,6 +62,8 @@ pub struct ContentMatch {
     pub line_content: String,
     pub match_start: usize,
     pub match_end: usize,
+    pub location: Option<String>,  // Where in an extracted document, e.g. "cell 12, line 3"
+    pub pattern: Option<std::sync::Arc<str>>,  // Which of several --content patterns matched
 }
 
 #[derive(Debug, Clone)]
@@ -36,15 +73,28 @@ pub enum SearchType {
     Hybrid, // Both filename and content
 }
 
-fn show_banner() {
-    let font = FIGfont::standard().unwrap();
-    let banner = font.convert("ff-fast file").unwrap();
+/// The FIGlet banner, or None where it would only get in the way: a dumb terminal, a terminal
+/// too narrow for it (wrapped FIGlet is unreadable), or a font that failed to load
+fn render_banner() -> Option<String> {
+    if std::env::var("TERM").is_ok_and(|term| term == "dumb") {
+        return None;
+    }
+    let text = FIGfont::standard().ok()?.convert("ff-fast file")?.to_string();
+    let width = text.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+    if let Some((terminal_size::Width(columns), _)) = terminal_size::terminal_size_of(std::io::stderr())
+        && (columns as usize) < width
+    {
+        return None;
+    }
+    Some(text)
+}
 
-    let text = banner.to_string();
+fn show_banner() {
+    let Some(text) = render_banner() else { return };
     let lines: Vec<&str> = text.lines().collect();
 
     // Gradient palette
-    let gradient = vec![
+    let gradient = [
         Color::Red,
         Color::Green,
         Color::Blue,
@@ -56,50 +106,700 @@ fn show_banner() {
     // Print each line with gradient color
     for (i, line) in lines.iter().enumerate() {
         let color = gradient[i % gradient.len()];
-        println!("{}", line.color(color).bold());
+        eprintln!("{}", line.color(color).bold());
+    }
+}
+
+fn show_welcome_help(banner: bool) {
+    if banner {
+        show_banner();
+    }
+
+    eprintln!("\n{}", "Fast File Finder".bright_cyan().bold());
+
+    eprintln!("\n{}", "USAGE:".yellow().bold());
+    eprintln!("  {} {} {}", "ff".green().bold(), "<args>".white(), "<options>".yellow());
+
+    eprintln!("\n{}", "EXAMPLES:".yellow().bold());
+    eprintln!("  {} {}      {}", "ff".green(), "<your_file_name>".white(), "→ Locate file(s)".dimmed());
+    eprintln!("  {} {}          {}", "ff".green(), "main.rs".white(), "→ Find main.rs files".dimmed());
+
+    eprintln!("\n{}", "OPTIONS:".yellow().bold());
+    eprintln!("  {:<12} {:<10} {}", "--path".blue(), "<dir>".white(), "Search in directory".dimmed());
+    eprintln!("  {:<12} {:<10} {}", "--copy".blue(), "" , "Copy path to clipboard".dimmed());
+    eprintln!("  {:<12} {:<10} {}", "--hidden".blue(), "" , "Include hidden files".dimmed());
+    eprintln!("  {:<12} {:<10} {}", "--dirs-only".blue(), "" , "Find only directories".dimmed());
+    eprintln!("  {:<12} {:<10} {}", "--files-only".blue(), "" , "Find only files".dimmed());
+
+    eprintln!("\n  Type {} for detailed help", "ff --help".green().bold());
+    eprintln!("  {} Press {} anytime to cancel", "⚠️".bright_yellow(), "Ctrl+C".red().bold());
+}
+
+fn describe_ignore_overrides(cli: &Cli) -> String {
+    let mut parts = Vec::new();
+    if cli.no_ignore_dirs {
+        parts.push("no ignored dirs".to_string());
+    }
+    if cli.no_ignore_files {
+        parts.push("no ignored files".to_string());
+    }
+    if !cli.include_dir.is_empty() {
+        parts.push(format!("include {}", cli.include_dir.join(", ")));
+    }
+    if cli.include_caches {
+        parts.push("include caches".to_string());
+    }
+    if cli.no_ignore {
+        parts.push("no ignore files".to_string());
+    }
+    parts.join(" | ")
+}
+
+/// The SEARCH SUMMARY block printed before results
+fn show_search_summary(
+    search_path: &Path,
+    options: &search::SearchOptions,
+    cli: &Cli,
+    config: &Config,
+    hidden: bool,
+    deep: bool,
+) {
+    eprintln!("{}", "🔎 SEARCH SUMMARY".yellow().bold());
+    if let Some(ref pattern) = options.filename_pattern {
+        eprintln!(" Filename pattern: {}", pattern.bright_white().bold());
+    }
+    if let Some(ref pattern) = options.content_pattern {
+        eprintln!(" Content pattern: {}", pattern.bright_white().bold());
+    }
+    if let Some(scope) = options.content_scope {
+        eprintln!("   Matching in: {} only", scope.name().blue());
+    }
+    eprintln!("   Path: {}", search_path.display().to_string().cyan());
+    if options.dirs_only {
+        eprintln!("   Filter: {} only", "directories".blue());
+    } else if options.files_only {
+        eprintln!("   Filter: {} only", "files".blue());
+    }
+    if !options.filters.is_empty() {
+        eprintln!("   Filters: {}", options.filters.describe().blue());
+    }
+    if !cli.exclude.is_empty() {
+        eprintln!("   Excluding: {}", cli.exclude.join(", ").blue());
+    }
+    if deep {
+        let budget = options.time_limit.map(|limit| format!("{}s budget", limit.as_secs())).unwrap_or("no time budget".to_string());
+        eprintln!("   Deep search: {} | hidden included | {} system directories skipped",
+                  budget.blue(), config.deep_search.skip_directories.len());
+    }
+    if hidden {
+        eprintln!("   Including: {} files", "hidden".blue());
+    }
+    if cli.no_ignore_dirs || cli.no_ignore_files || !cli.include_dir.is_empty() || cli.include_caches || cli.no_ignore {
+        eprintln!("   Ignore overrides: {}", describe_ignore_overrides(cli).blue());
+    }
+    eprintln!();
+}
+
+/// --no-summary: pattern, root and mode on one line, so results stay on short terminals
+fn show_compact_header(search_path: &Path, options: &search::SearchOptions, parallel: bool) {
+    let patterns = [
+        options.filename_pattern.as_ref().map(|pattern| pattern.bright_white().bold().to_string()),
+        options.content_pattern.as_ref().map(|pattern| format!("content {}", pattern.bright_white().bold())),
+    ];
+    let patterns: Vec<String> = patterns.into_iter().flatten().collect();
+    let mode = format!("{:?}", options.match_mode).to_lowercase();
+    eprintln!("{} {} in {} ({}{})", "🔎".yellow(), patterns.join(" + "),
+              search_path.display().to_string().cyan(), mode, if parallel { ", parallel" } else { "" });
+}
+
+/// Print the fully resolved search plan for --dry-run without touching the filesystem
+fn show_search_plan(search_path: &Path, options: &search::SearchOptions, config: &Config, parallel: bool) {
+    let on_off = |flag: bool| if flag { "on".green() } else { "off".dimmed() };
+
+    println!("{}", "🧭 SEARCH PLAN (dry run, nothing will be scanned)".yellow().bold());
+    println!("   Config: {}", config::CONFIG_FILE.cyan());
+    println!("   Root: {}", std::path::absolute(search_path)
+        .unwrap_or_else(|_| search_path.to_path_buf())
+        .display().to_string().cyan());
+    if let Some(ref pattern) = options.filename_pattern {
+        println!("   Filename pattern: {}", pattern.bright_white().bold());
+    }
+    if let Some(ref pattern) = options.content_pattern {
+        println!("   Content pattern: {}", pattern.bright_white().bold());
+    }
+    if let Some(scope) = options.content_scope {
+        println!("   Content matches in: {} only (files in unknown languages are skipped)", scope.name().blue());
+    }
+    if options.multiline {
+        println!("   Multiline: {}", "on (each file matched whole, matches can span lines)".blue());
+    }
+    if options.count_only {
+        println!("   Output: {}", "match counts per file (no lines kept)".blue());
+    }
+    if options.files_without_match {
+        println!("   Output: {}", "files the content pattern does NOT match".blue());
+    }
+    println!("   Match mode: {}", format!("{:?}", options.match_mode).blue());
+    let case_for = |pattern: &Option<String>| pattern.as_deref().map(|pattern| options.case_mode.sensitive_for(pattern));
+    let case = match (case_for(&options.filename_pattern), case_for(&options.content_pattern)) {
+        (Some(name), Some(content)) => format!("name {}, content {}", on_off(name), on_off(content)),
+        (Some(sensitive), None) | (None, Some(sensitive)) => on_off(sensitive).to_string(),
+        (None, None) => on_off(false).to_string(),
+    };
+    println!("   Case-sensitive: {} ({})", case, options.case_mode.label());
+    println!("   Names matched against: {}", if options.full_path { "relative path" } else { "file name (relative path for patterns with /)" });
+    if options.transliterate {
+        println!("   Transliterate names: {}", if matches!(options.match_mode, cli::MatchMode::Regex) {
+            "off for regex patterns".dimmed().to_string()
+        } else {
+            "on (ASCII forms, - and _ as spaces)".blue().to_string()
+        });
+    }
+    if matches!(options.match_mode, cli::MatchMode::Fuzzy)
+        && let Some(pattern) = &options.filename_pattern
+    {
+        println!("   Minimum fuzzy score: {}", match search::fuzzy_score_floor(pattern, options.min_score) {
+            Some(floor) => format!("{} ({} per character)", floor, options.min_score),
+            None => "(none)".dimmed().to_string(),
+        });
+    }
+
+    let entry_filter = if options.dirs_only {
+        "directories only"
+    } else if options.files_only {
+        "files only"
+    } else {
+        "files and directories"
+    };
+    println!("   Entries: {}", entry_filter.blue());
+    if !options.filters.is_empty() {
+        println!("   Filters: {}", options.filters.describe().blue());
+    }
+    println!("   Hidden files: {}", on_off(options.include_hidden));
+    if options.archives {
+        println!("   Archives: {}", "entries of zip, tar and tar.gz files searched".blue());
+    }
+    println!("   Follow symlinks: {}", on_off(options.follow_symlinks));
+    if options.skip_reparse {
+        println!("   Links and reparse points: {}", "skipped".blue());
+    }
+    // Content-only searches stop once the quota is met, with a name pattern the best results could come later
+    let stop = if options.filename_pattern.is_none() { ", scanning stops once reached" } else { "" };
+    match (options.limit_files, options.limit_matches) {
+        (Some(files), _) => println!("   Result limit: {} files with matches{}", files.to_string().blue(), stop),
+        (None, Some(matches)) => println!("   Result limit: first {} matches across files{}", matches.to_string().blue(), stop),
+        (None, None) => println!("   Result limit: {}", options.limit.to_string().blue()),
+    }
+
+    println!("   Walker backend: {}", walker::for_backend(options.backend).name().blue());
+    if parallel {
+        println!("   Matching: {} ({} threads)", "rayon parallel".blue(), options.threads);
+        if let Some(limit) = options.content_threads {
+            println!("   Concurrent content scans: {}", limit.to_string().blue());
+        }
+        if let Some(budget) = options.memory_budget {
+            println!("   Path list budget: {} (max_memory_mb)", util::format_size(budget));
+        }
+    } else {
+        println!("   Matching: {}", "sequential".blue());
+    }
+
+    let max_files = if options.max_files == usize::MAX { "unlimited".to_string() } else { options.max_files.to_string() };
+    println!("   Max file size: {}MB | Max files per search: {}", config.max_file_size_mb, max_files);
+    match options.time_limit {
+        Some(limit) if options.strict_time_limit => println!("   Time budget: {}s (then stop)", limit.as_secs()),
+        Some(limit) => println!("   Time limit: {}s (then ask whether to keep going)", limit.as_secs()),
+        None => println!("   Time limit: {}", "none".dimmed()),
+    }
+    match options.stall_timeout {
+        Some(_) if options.backend == walker::Backend::Walkdir => {
+            println!("   Stall timeout: {}", "not checked by the walkdir backend (use --backend ignore)".dimmed())
+        }
+        Some(timeout) => println!("   Stall timeout: {}ms", timeout.as_millis()),
+        None => println!("   Stall timeout: {}", "disabled".dimmed()),
+    }
+    match options.dir_entry_cap {
+        Some(cap) => println!("   Entries per directory: {} (then the rest is skipped)", cap),
+        None => println!("   Entries per directory: {}", "unlimited".dimmed()),
+    }
+    if options.content_pattern.is_some() {
+        println!("   Content always searched: {}", config.content_search_extensions.join(" ").dimmed());
+        println!("   Content never searched: {}", config.content_skip_extensions.join(" ").dimmed());
+        println!("   Other files: {}", if config.probe_unknown_files { "searched unless binary" } else { "skipped" });
+        println!("   Gzipped files: {}", if config.search_compressed { "searched decompressed" } else { "skipped" });
+        println!("   Cloud placeholders: {}", if options.hydrate { "searched (--hydrate)" } else { "skipped" });
+        let budget = |bytes: u64| if bytes == 0 { "unlimited".dimmed().to_string() } else { util::format_size(bytes) };
+        println!("   Content bytes: {} per file | {} per search",
+                 budget(config.max_content_bytes_per_file), budget(config.max_total_content_bytes));
+        println!("   Content cache: {}", match options.content_cache_entries {
+            0 => "disabled".to_string(),
+            max => format!("up to {} files", max),
+        });
+    }
+
+    let describe = |patterns: &[ignore_rules::IgnorePattern]| {
+        if patterns.is_empty() {
+            "(none)".dimmed().to_string()
+        } else {
+            patterns.iter().map(|p| p.describe()).collect::<Vec<_>>().join(" ")
+        }
+    };
+    if options.min_depth > 0 || options.max_depth.is_some() {
+        println!("   Depth: {} to {}", options.min_depth.max(1),
+            options.max_depth.map_or("any".to_string(), |max| max.to_string()));
+    }
+    if !options.only_dirs.is_empty() {
+        println!("   Only top-level directories: {}", options.only_dirs.join(", ").blue());
+    }
+    let excluded = options.ignore_rules.exclude_patterns();
+    if !excluded.is_empty() {
+        println!("   Excluded: {}", excluded.iter().map(|p| p.describe()).collect::<Vec<_>>().join(" ").blue());
+    }
+    println!("   Ignored directories: {}", describe(options.ignore_rules.directory_patterns()));
+    println!("   Ignored files: {}", describe(options.ignore_rules.file_patterns()));
+    println!("   Build output heuristics: {}", on_off(options.ignore_rules.heuristics_enabled()));
+    println!("   Skip CACHEDIR.TAG directories: {}", on_off(options.ignore_rules.cache_tags_respected()));
+    println!("   Respect .gitignore/.ignore/.ffignore: {}", on_off(options.ignore_rules.ignore_files_respected()));
+    if !config.output_options.redact_patterns.is_empty() {
+        println!("   Redacted in previews: {} pattern(s) from output_options.redact_patterns",
+                 config.output_options.redact_patterns.len().to_string().blue());
+    }
+    if let Some(ref git_files) = options.git_files {
+        println!("   Only git files: {} ({})", git_files.file_count().to_string().blue(), git_files.label);
+    }
+}
+
+/// `--show-effective`: every setting that has a flag/config fallback, with the layer that decided it
+fn show_effective_settings(cli: &Cli, config: &Config, threads: usize) {
+    let defaults = Config::default();
+    let source = |from_flag: bool, from_config: bool| {
+        if from_flag {
+            "flag".green()
+        } else if from_config {
+            "config".yellow()
+        } else {
+            "default".dimmed()
+        }
+    };
+    let row = |name: &str, value: String, from: ColoredString| {
+        println!("   {:<18} {:<24} {}", name, value, from);
+    };
+
+    let search_defaults = &config.default_search_options;
+    println!("{}", "⚙️  EFFECTIVE SETTINGS (flag > config > default)".yellow().bold());
+    println!("   Config: {}", config::CONFIG_FILE.cyan());
+
+    let match_mode = cli.match_mode.clone().unwrap_or_else(|| config.default_match_mode());
+    row("match mode", format!("{:?}", match_mode).to_lowercase(), source(
+        cli.match_mode.is_some(),
+        !search_defaults.match_mode.eq_ignore_ascii_case(&defaults.default_search_options.match_mode)));
+    row("case", effective_case_mode(cli, config).label().to_string(), source(
+        cli.case_sensitive || cli.ignore_case || cli.smart_case,
+        search_defaults.case_sensitive != defaults.default_search_options.case_sensitive
+            || search_defaults.smart_case != defaults.default_search_options.smart_case));
+    row("full path", (cli.full_path || search_defaults.full_path).to_string(), source(
+        cli.full_path,
+        search_defaults.full_path != defaults.default_search_options.full_path));
+    row("transliterate", (cli.transliterate || search_defaults.transliterate).to_string(), source(
+        cli.transliterate,
+        search_defaults.transliterate != defaults.default_search_options.transliterate));
+    row("min score", effective_min_score(cli, config).to_string(), source(
+        cli.min_score.is_some(),
+        search_defaults.min_score != defaults.default_search_options.min_score));
+    row("limit", cli.limit.unwrap_or(search_defaults.limit).to_string(), source(
+        cli.limit.is_some(),
+        search_defaults.limit != defaults.default_search_options.limit));
+    row("details", (cli.details || config.output_options.show_details).to_string(), source(
+        cli.details,
+        config.output_options.show_details != defaults.output_options.show_details));
+    row("parallel", (cli.parallel || search_defaults.parallel).to_string(), source(
+        cli.parallel,
+        search_defaults.parallel != defaults.default_search_options.parallel));
+    row("threads", threads.to_string(), source(
+        cli.threads.is_some() || cli.max_cpu,
+        config.max_parallel_threads.is_some()));
+    row("hidden", (cli.hidden || config.include_hidden).to_string(), source(
+        cli.hidden,
+        config.include_hidden != defaults.include_hidden));
+    row("follow symlinks", effective_follow_symlinks(cli, config).to_string(), source(
+        cli.follow || cli.no_follow,
+        config.follow_symlinks != defaults.follow_symlinks));
+    row("ignore files", (!cli.no_ignore && config.respect_ignore_files).to_string(), source(
+        cli.no_ignore,
+        config.respect_ignore_files != defaults.respect_ignore_files));
+    row("max files", cli.max_files.unwrap_or(config.max_files_per_search).to_string(), source(
+        cli.max_files.is_some(),
+        config.max_files_per_search != defaults.max_files_per_search));
+    row("stall timeout ms", cli.stall_timeout.unwrap_or(config.dir_stall_timeout_ms).to_string(), source(
+        cli.stall_timeout.is_some(),
+        config.dir_stall_timeout_ms != defaults.dir_stall_timeout_ms));
+    row("dir entry cap", effective_dir_entry_cap(cli, config).unwrap_or(0).to_string(), source(
+        cli.no_dir_cap,
+        config.max_entries_per_dir != defaults.max_entries_per_dir));
+    row("time limit s", config.search_time_limit_secs.to_string(), source(
+        false,
+        config.search_time_limit_secs != defaults.search_time_limit_secs));
+    row("backend", walker::for_backend(effective_backend(cli, config)).name().to_string(), source(
+        cli.backend.is_some(),
+        !config.walker_backend.eq_ignore_ascii_case(&defaults.walker_backend)));
+    row("content cache", if cli.no_cache { 0 } else { config.content_cache_entries }.to_string(), source(
+        cli.no_cache,
+        config.content_cache_entries != defaults.content_cache_entries));
+    row("bytes per file", config.max_content_bytes_per_file.to_string(), source(
+        false,
+        config.max_content_bytes_per_file != defaults.max_content_bytes_per_file));
+    row("bytes per search", config.max_total_content_bytes.to_string(), source(
+        false,
+        config.max_total_content_bytes != defaults.max_total_content_bytes));
+    row("max line length", config.output_options.max_line_length.to_string(), source(
+        false,
+        config.output_options.max_line_length != defaults.output_options.max_line_length));
+}
+
+/// Effective ignore rules: config lists minus any per-search overrides
+fn effective_ignore_rules(cli: &Cli, config: &Config) -> IgnoreRules {
+    let mut ignore_rules = config.ignore_rules();
+    if cli.no_ignore_dirs {
+        ignore_rules.clear_directories();
+    }
+    if cli.no_ignore_files {
+        ignore_rules.clear_files();
+    }
+    for dir in &cli.include_dir {
+        ignore_rules.include_directory(dir);
+    }
+    if cli.include_caches {
+        ignore_rules.include_caches();
+    }
+    if cli.no_ignore {
+        ignore_rules.skip_ignore_files();
+    }
+    for pattern in &cli.exclude {
+        ignore_rules.exclude(pattern);
     }
+    ignore_rules
 }
 
-fn show_welcome_help() {
+/// --follow / --no-follow, falling back to the config's follow_symlinks
+fn effective_follow_symlinks(cli: &Cli, config: &Config) -> bool {
+    !cli.no_follow && (cli.follow || config.follow_symlinks)
+}
+
+fn effective_backend(cli: &Cli, config: &Config) -> walker::Backend {
+    cli.backend.unwrap_or_else(|| walker::Backend::from_config(&config.walker_backend))
+}
+
+fn resolve_search_path(path: Option<&String>) -> Option<PathBuf> {
+    let search_path = path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::current_dir().unwrap());
+
+    if !search_path.exists() {
+        eprintln!("{} Search path does not exist: {}", "❌".red(), search_path.display().to_string().red());
+        eprintln!("{} Current directory: {}", "📍".yellow(), std::env::current_dir().unwrap().display().to_string().cyan());
+        return None;
+    }
+    Some(search_path)
+}
+
+/// --project: the root holding the current directory, None (searching the current directory) when there isn't one
+fn project_root(config: &Config) -> Option<String> {
+    let cwd = std::env::current_dir().ok()?;
+    match project::find_root(&cwd, &config.project_roots) {
+        Some(root) => {
+            eprintln!("{} Project root: {} ({})", "📂".yellow(), root.path.display().to_string().cyan(), root.marker);
+            Some(root.path.to_string_lossy().into_owned())
+        }
+        None => {
+            eprintln!("{} --project: no project marker above {}, searching it instead",
+                      "⚠️".yellow(), cwd.display().to_string().cyan());
+            None
+        }
+    }
+}
+
+/// A typo in --only-dirs would otherwise just look like "no results"
+fn warn_missing_only_dirs(root: &Path, only_dirs: &[String]) {
+    for name in only_dirs {
+        if !root.join(name).is_dir() {
+            eprintln!("{} --only-dirs: no directory named {} in {}",
+                     "⚠️".yellow(), name.yellow(), root.display().to_string().cyan());
+        }
+    }
+}
+
+/// The existing file a pattern with a separator names, relative to the current directory or the search root
+fn existing_path_pattern(pattern: &str, root: &Path) -> Option<PathBuf> {
+    if !pattern.contains('/') && !pattern.contains(std::path::MAIN_SEPARATOR) {
+        return None;
+    }
+    [PathBuf::from(pattern), root.join(pattern)]
+        .into_iter()
+        .find(|path| path.symlink_metadata().is_ok() && !path.is_dir())
+}
+
+/// Details of one known file, then the same actions a selected search result gets
+fn show_path_info(path: &Path, pattern: &str, cli: &Cli, config: &Config) {
+    let meta = match std::fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(e) => {
+            eprintln!("{} Couldn't read {}: {}", "❌".red(), path.display().to_string().red(), e);
+            return;
+        }
+    };
+    let result = SearchResult {
+        path: path.to_path_buf(),
+        archive_entry: None,
+        score: 0,
+        is_dir: meta.is_dir(),
+        size: meta.is_file().then_some(meta.len()),
+        disk_size: util::sparse_disk_size(&meta),
+        modified: meta.modified().ok(),
+        name_match: None,
+        name_floor: None,
+        content_matches: Vec::new(),
+        content_count: 0,
+        content_truncated: false,
+        peek: None,
+        search_type: SearchType::FileName,
+    };
+
+    eprintln!("{} {} is an existing file, showing it instead of searching", "📍".yellow(), util::display_path(path).cyan());
+    util::display_results(std::slice::from_ref(&result), false, config.output_options.max_line_length, false);
+    if let Ok(absolute) = std::path::absolute(path) {
+        println!("   Full path: {}", util::display_path(&absolute).cyan());
+    }
+    println!("   Size: {}", util::format_size(meta.len()));
+    if let Ok(target) = std::fs::read_link(path) {
+        println!("   Links to: {}", util::display_path(&target).cyan());
+    }
+    if let Some(elapsed) = result.modified.and_then(|modified| modified.elapsed().ok()) {
+        println!("   Modified: {}", util::format_time_ago(elapsed));
+    }
+    if meta.permissions().readonly() {
+        println!("   Read-only: {}", "yes".yellow());
+    }
+
+    if cli.copy || cli.terminal || cli.link_to.is_some() || config.hooks.on_select.is_some() {
+        eprintln!();
+        run_actions(&result, cli, config);
+    } else {
+        eprintln!();
+        eprintln!("{} Use {} or {} to act on it, or {} to match it against names",
+                  "💡".yellow(), "--c".blue(), "--t".blue(), format!("--query \"name:{}\"", pattern).blue());
+    }
+}
 
-    show_banner();
+/// --copy / --terminal / --link-to and the on_select hook, for the result the user picked
+fn run_actions(selected: &SearchResult, cli: &Cli, config: &Config) {
+    if let Some(ref dir) = cli.link_to {
+        util::link_into(&selected.path, dir);
+    }
+    if cli.copy {
+        let path = selected.path.display().to_string();
+        if let Err(e) = util::copy_to_clipboard(&path, &config.output_options.clipboard_backend) {
+            eprintln!("{} Couldn't copy the path: {}", "❌".red(), e);
+            eprintln!("{} Copy it from here instead (or set output_options.clipboard_backend):", "💡".yellow());
+            println!("{}", path);
+        }
+    } else if cli.terminal {
+        util::change_directory(&selected.path);
+    }
+    if let Some(ref hook) = config.hooks.on_select {
+        hooks::run("on_select", hook, &hooks::Placeholders::for_result(selected));
+    }
+}
 
-    println!("\n{}", "Fast File Finder".bright_cyan().bold());
+/// The user's home directory, where `ff deep` starts by default
+fn home_dir() -> Option<String> {
+    std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")).ok()
+}
 
-    println!("\n{}", "USAGE:".yellow().bold());
-    println!("  {} {} {}", "ff".green().bold(), "<args>".white(), "<options>".yellow());
+/// `ff deep`: widen a search to a whole home folder or drive. Hidden directories are user
+/// locations too, system ones are skipped, the multi-threaded walker runs without an entry
+/// cap, and the time budget stops the walk instead of asking.
+fn apply_deep_preset(options: &mut search::SearchOptions, cli: &Cli, config: &Config) {
+    let deep = &config.deep_search;
+    options.include_hidden = true;
+    if !cli.no_ignore_dirs {
+        for dir in &deep.skip_directories {
+            options.ignore_rules.ignore_directory(dir);
+        }
+        // Later rules win, so --include-dir has to come after the preset's
+        for dir in &cli.include_dir {
+            options.ignore_rules.include_directory(dir);
+        }
+    }
+    if cli.backend.is_none() {
+        options.backend = walker::Backend::Ignore;
+    }
+    if cli.max_files.is_none() {
+        options.max_files = usize::MAX;
+    }
+    options.time_limit = Some(deep.time_limit_secs)
+        .filter(|&secs| secs > 0)
+        .map(std::time::Duration::from_secs);
+    options.strict_time_limit = true;
+}
 
-    println!("\n{}", "EXAMPLES:".yellow().bold());
-    println!("  {} {}      {}", "ff".green(), "<your_file_name>".white(), "→ Locate file(s)".dimmed());
-    println!("  {} {}          {}", "ff".green(), "main.rs".white(), "→ Find main.rs files".dimmed());
+/// Config max_entries_per_dir, unless --no-dir-cap lifts it
+fn effective_dir_entry_cap(cli: &Cli, config: &Config) -> Option<usize> {
+    Some(config.max_entries_per_dir).filter(|&cap| cap > 0 && !cli.no_dir_cap)
+}
 
-    println!("\n{}", "OPTIONS:".yellow().bold());
-    println!("  {:<12} {:<10} {}", "--path".blue(), "<dir>".white(), "Search in directory".dimmed());
-    println!("  {:<12} {:<10} {}", "--copy".blue(), "" , "Copy path to clipboard".dimmed());
-    println!("  {:<12} {:<10} {}", "--hidden".blue(), "" , "Include hidden files".dimmed());
-    println!("  {:<12} {:<10} {}", "--dirs-only".blue(), "" , "Find only directories".dimmed());
-    println!("  {:<12} {:<10} {}", "--files-only".blue(), "" , "Find only files".dimmed());
+/// --min-score, falling back to the config default
+fn effective_min_score(cli: &Cli, config: &Config) -> i64 {
+    cli.min_score.unwrap_or(config.default_search_options.min_score)
+}
 
-    println!("\n  Type {} for detailed help", "ff --help".green().bold());
-    println!("  {} Press {} anytime to cancel", "⚠️".bright_yellow(), "Ctrl+C".red().bold());
+/// --case-sensitive / --ignore-case / --smart-case, falling back to the config default
+fn effective_case_mode(cli: &Cli, config: &Config) -> search::CaseMode {
+    let defaults = &config.default_search_options;
+    if cli.case_sensitive || (!cli.ignore_case && !cli.smart_case && defaults.case_sensitive) {
+        search::CaseMode::Sensitive
+    } else if cli.smart_case || (!cli.ignore_case && defaults.smart_case) {
+        search::CaseMode::Smart
+    } else {
+        search::CaseMode::Insensitive
+    }
 }
 
+fn run_command(
+    command: &Commands,
+    cli: &Cli,
+    config: &Config,
+    stall_timeout: Option<std::time::Duration>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Walk settings shared by the subcommands, which only differ in what they aggregate
+    let walk_options = |hidden: bool| search::SearchOptions {
+        include_hidden: hidden || cli.hidden || config.include_hidden,
+        follow_symlinks: effective_follow_symlinks(cli, config),
+        ignore_rules: effective_ignore_rules(cli, config),
+        stall_timeout,
+        dir_entry_cap: effective_dir_entry_cap(cli, config),
+        backend: effective_backend(cli, config),
+        only_dirs: cli.only_dirs.clone(),
+        min_depth: cli.min_depth.unwrap_or(0),
+        max_depth: cli.max_depth,
+        skip_reparse: cli.skip_reparse,
+        case_mode: effective_case_mode(cli, config),
+        full_path: cli.full_path || config.default_search_options.full_path,
+        transliterate: cli.transliterate || config.default_search_options.transliterate,
+        min_score: effective_min_score(cli, config),
+        ..Default::default()
+    };
+
+    match command {
+        Commands::Stats { path, hidden, top } => {
+            let Some(root) = resolve_search_path(path.as_ref().or(cli.path.as_ref())) else {
+                return Ok(());
+            };
+            let options = walk_options(*hidden);
+            warn_missing_only_dirs(&root, &options.only_dirs);
+            stats::run_stats(&root, &options, config, *top);
+        }
+        Commands::Timeline { pattern, path, hidden, recent } => {
+            let Some(root) = resolve_search_path(path.as_ref().or(cli.path.as_ref())) else {
+                return Ok(());
+            };
+            let options = search::SearchOptions {
+                filename_pattern: pattern.clone(),
+                ..walk_options(*hidden)
+            };
+            warn_missing_only_dirs(&root, &options.only_dirs);
+            timeline::run_timeline(&root, &options, config, *recent);
+        }
+        Commands::Licenses { path, hidden, expect, header_lines } => {
+            let Some(root) = resolve_search_path(path.as_ref().or(cli.path.as_ref())) else {
+                return Ok(());
+            };
+            let options = walk_options(*hidden);
+            warn_missing_only_dirs(&root, &options.only_dirs);
+            licenses::run_licenses(&root, &options, config, *header_lines, expect.as_deref());
+        }
+        Commands::Config { action: ConfigCommand::Sync { action } } => {
+            let result = match action {
+                SyncCommand::Export { file } => config_sync::export(config, file.as_deref()),
+                SyncCommand::Import { file } => config_sync::import(config, file),
+            };
+            if let Err(e) = result {
+                eprintln!("{} {}", "❌".red(), e);
+            }
+        }
+        Commands::Deep { .. } => unreachable!("ff deep runs as a regular search"),
+    }
+    Ok(())
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = Config::load_with_safeguard();
+    let mut config = Config::load_with_safeguard();
     let cli = Cli::parse();
+    // An elevated search reads the working directory's config as admin, it never runs hooks
+    if cli.no_hooks || cli.elevated {
+        config.hooks = hooks::Hooks::default();
+    }
+    let _hold_window = if cli.elevated && cfg!(windows) { Some(elevate::HoldWindow) } else { None };
 
-    //Calculate effective values (CLI overrides config)
+    // Calculate effective values. Precedence: --query field > CLI flag > config > built-in default
     let effective_hidden = cli.hidden || config.include_hidden;
     let effective_details = cli.details || config.output_options.show_details;
+    let effective_match_mode = cli.match_mode.clone().unwrap_or_else(|| config.default_match_mode());
+    let effective_limit = cli.limit.unwrap_or(config.default_search_options.limit);
+    let effective_parallel = cli.parallel || config.default_search_options.parallel;
+    let effective_banner = !cli.no_banner && config.output_options.banner;
     let optimal_threads = config.get_effective_thread_count(cli.threads, cli.max_cpu);
+    let stall_timeout = Some(cli.stall_timeout.unwrap_or(config.dir_stall_timeout_ms))
+        .filter(|&ms| ms > 0)
+        .map(std::time::Duration::from_millis);
+
+    if cli.show_effective {
+        show_effective_settings(&cli, &config, optimal_threads);
+        return Ok(());
+    }
+
+    if let (Some(min), Some(max)) = (cli.min_depth, cli.max_depth) && min > max {
+        eprintln!("{} --min-depth {} is deeper than --max-depth {}, nothing could match", "❌".red(), min, max);
+        return Ok(());
+    }
+
+    // `ff deep` is a regular search with a preset, the other subcommands run their own walk and exit
+    let deep = match &cli.command {
+        Some(Commands::Deep { pattern, path }) => Some((pattern.clone(), path.clone().or_else(home_dir))),
+        Some(command) => return run_command(command, &cli, &config, stall_timeout),
+        None => None,
+    };
+    let effective_parallel = effective_parallel || deep.is_some();
+
+    // A --query fills in (and overrides) the individual flags
+    let mut query = match cli.query.as_deref().map(query::parse).transpose() {
+        Ok(query) => query.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("{} Invalid query:", "❌".red());
+            eprintln!("{}", e.render(cli.query.as_deref().unwrap_or_default()).yellow());
+            eprintln!("{} Fields: {}", "💡".yellow(), "name: content: ext: size: mtime: mode: type: hidden:".green());
+            return Ok(());
+        }
+    };
+    for list in &cli.ext {
+        query.filters.add_extensions(list);
+    }
+    query.filters.categories.extend(&cli.file_type);
+    query.filters.sizes.extend(cli.size.iter().cloned());
+    query.filters.ages.extend(cli.newer_than.iter().chain(&cli.older_than).chain(&cli.mtime).cloned());
 
-    let filename_pattern = cli.pattern.clone();
-    let content_pattern = cli.content.clone();
+    let filename_pattern = query.name.clone()
+        .or_else(|| deep.as_ref().map(|(pattern, _)| pattern.clone()))
+        .or_else(|| cli.pattern.clone());
+    // Repeated --content flags are searched for together, shown joined with |
+    let content_patterns = match query.content.clone() {
+        Some(pattern) => vec![pattern],
+        None => cli.content.clone(),
+    };
+    let content_pattern = (!content_patterns.is_empty()).then(|| content_patterns.join(" | "));
 
     // Validate that we have at least one search pattern
     if filename_pattern.is_none() && content_pattern.is_none() {
-        show_welcome_help();
+        show_welcome_help(effective_banner);
         return Ok(());
     }
 
@@ -107,125 +807,320 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let filename_pattern = filename_pattern.as_deref();
     let content_pattern = content_pattern.as_deref();
 
-    if cli.parallel {
-        rayon::ThreadPoolBuilder::new()
+    if effective_parallel {
+        // Fails only when the global pool already exists, which is fine to keep
+        let _ = rayon::ThreadPoolBuilder::new()
             .num_threads(optimal_threads)
             .thread_name(|i| format!("ff-{}", i))
-            .build_global()
-            .unwrap_or_else(|_| {});
+            .build_global();
     }
 
     // NEW - handles both filename and content patterns:
     match (&filename_pattern, &content_pattern) {
         (Some(fp), _) if fp.trim().is_empty() => {
-            println!("{} Search pattern cannot be empty", "❌".red());
-            println!("{} Example: {}", "💡".yellow(), "ff config.json".green());
+            eprintln!("{} Search pattern cannot be empty", "❌".red());
+            eprintln!("{} Example: {}", "💡".yellow(), "ff config.json".green());
             return Ok(());
         }
-        (None, Some(cp)) if cp.trim().is_empty() => {
-            println!("{} Content pattern cannot be empty", "❌".red());
-            println!("{} Example: {}", "💡".yellow(), "ff --content \"hello world\"".green());
+        (None, Some(_)) if content_patterns.iter().any(|pattern| pattern.trim().is_empty()) => {
+            eprintln!("{} Content pattern cannot be empty", "❌".red());
+            eprintln!("{} Example: {}", "💡".yellow(), "ff --content \"hello world\"".green());
             return Ok(());
         }
         (None, None) => {
-            show_welcome_help();
+            show_welcome_help(effective_banner);
             return Ok(());
         }
         _ => {} // Continue with search
     }
     
-    let search_path = cli.path
-        .map(PathBuf::from)
-        .unwrap_or_else(|| std::env::current_dir().unwrap());
+    let project_root = if cli.project && deep.is_none() { project_root(&config) } else { None };
+    let root = deep.as_ref().and_then(|(_, path)| path.as_ref()).or(cli.path.as_ref()).or(project_root.as_ref());
+    let Some(search_path) = resolve_search_path(root) else {
+        return Ok(());
+    };
 
-    if !search_path.exists() {
-        println!("{} Search path does not exist: {}", "❌".red(), search_path.display().to_string().red());
-        println!("{} Current directory: {}", "📍".yellow(), std::env::current_dir().unwrap().display().to_string().cyan());
+    // `ff src/config.rs` names a file rather than describing one, show it instead of searching.
+    // Directories are searched, so `ff tests/helpers` lists what's inside.
+    if query.name.is_none() && content_pattern.is_none()
+        && let Some(path) = filename_pattern.and_then(|pattern| existing_path_pattern(pattern, &search_path))
+    {
+        show_path_info(&path, filename_pattern.unwrap_or_default(), &cli, &config);
+        return Ok(());
+    }
+
+    let mut options = search::SearchOptions {
+        filename_pattern: filename_pattern.map(str::to_string),
+        content_pattern: content_pattern.map(str::to_string),
+        content_patterns,
+        include_hidden: query.hidden.unwrap_or(effective_hidden),
+        follow_symlinks: effective_follow_symlinks(&cli, &config),
+        dirs_only: cli.dirs_only || query.dirs_only,
+        files_only: cli.files_only || query.files_only,
+        // Grouping aggregates over every matching file, --limit then caps the groups shown
+        // --limit-files replaces the result limit, --limit-matches lifts it so its quota decides
+        limit: match cli.limit_files {
+            Some(files) => files,
+            None if cli.group_by_match || cli.limit_matches.is_some() => usize::MAX,
+            None => effective_limit,
+        },
+        show_details: effective_details,
+        match_mode: query.match_mode.clone().unwrap_or(effective_match_mode),
+        case_mode: effective_case_mode(&cli, &config),
+        full_path: cli.full_path || config.default_search_options.full_path,
+        transliterate: cli.transliterate || config.default_search_options.transliterate,
+        min_score: effective_min_score(&cli, &config),
+        content_scope: cli.scope,
+        threads: optimal_threads,
+        content_threads: cli.content_threads.map(|n| n as usize),
+        max_files: match cli.max_files.unwrap_or(config.max_files_per_search) {
+            0 => usize::MAX,
+            max => max,
+        },
+        time_limit: Some(config.search_time_limit_secs)
+            .filter(|&secs| secs > 0)
+            .map(std::time::Duration::from_secs),
+        strict_time_limit: false,
+        memory_budget: Some(config.max_memory_mb as u64 * 1024 * 1024).filter(|&bytes| bytes > 0),
+        ignore_rules: effective_ignore_rules(&cli, &config),
+        stall_timeout,
+        dir_entry_cap: effective_dir_entry_cap(&cli, &config),
+        hydrate: cli.hydrate,
+        backend: effective_backend(&cli, &config),
+        content_cache_entries: if cli.no_cache { 0 } else { config.content_cache_entries },
+        verbose: cli.verbose,
+        only_dirs: cli.only_dirs.clone(),
+        min_depth: cli.min_depth.unwrap_or(0),
+        max_depth: cli.max_depth,
+        skip_reparse: cli.skip_reparse,
+        filters: query.filters,
+        archives: cli.archives,
+        multiline: cli.multiline,
+        count_only: cli.count,
+        files_without_match: cli.files_without_match,
+        limit_files: cli.limit_files,
+        limit_matches: cli.limit_matches,
+        git_files: None,
+    };
+    if deep.is_some() {
+        apply_deep_preset(&mut options, &cli, &config);
+    }
+    warn_missing_only_dirs(&search_path, &options.only_dirs);
+
+    let git_files = if cli.git_tracked {
+        Some(("--git-tracked", git::GitFiles::tracked(&search_path)))
+    } else if cli.git_dirty || cli.git_diff.is_some() {
+        let flag = if cli.git_dirty { "--git-dirty" } else { "--git-diff" };
+        Some((flag, git::GitFiles::changed(&search_path, cli.git_diff.as_deref())))
+    } else {
+        None
+    };
+    match git_files {
+        Some((_, Ok(files))) => options.git_files = Some(std::sync::Arc::new(files)),
+        Some((flag, Err(e))) => {
+            eprintln!("{} {} couldn't get its file list from git: {}", "❌".red(), flag.blue(), e);
+            return Ok(());
+        }
+        None => {}
+    }
+
+    // --content "foo AND bar NOT baz"; a plain phrase parses as a single term
+    let content_query = match options.content_query().transpose() {
+        Ok(query) => query,
+        Err(e) => {
+            eprintln!("{} Invalid content query:", "❌".red());
+            eprintln!("{}", e.render(options.content_pattern.as_deref().unwrap_or_default()).yellow());
+            eprintln!("{} Combine terms with {}, quote a keyword to search for it", "💡".yellow(), "AND OR NOT".green());
+            return Ok(());
+        }
+    };
+
+    if options.content_patterns.len() > 1 && matches!(options.match_mode, cli::MatchMode::Regex) {
+        eprintln!("{} Repeated {} patterns are matched literally, combine regexes with {} in one pattern instead",
+                  "❌".red(), "--content".blue(), "|".green());
+        return Ok(());
+    }
+
+    if options.multiline && !matches!(options.match_mode, cli::MatchMode::Regex) {
+        eprintln!("{} {} works with regex content patterns, add {}", "❌".red(), "--multiline".blue(), "--match-mode regex".green());
+        return Ok(());
+    }
+
+    if options.files_without_match && (content_query.is_none() || options.filename_pattern.is_some()) {
+        eprintln!("{} {} lists the files a {} doesn't match; narrow them with {} or {} rather than a name pattern",
+                  "❌".red(), "--files-without-match".blue(), "--content PATTERN".green(), "--ext".green(), "--type".green());
+        return Ok(());
+    }
+
+    if options.count_only && content_query.is_none() {
+        eprintln!("{} {} counts content matches, add {}", "❌".red(), "--count".blue(), "--content PATTERN".green());
+        return Ok(());
+    }
+
+    // A bad regex would just match nothing, so report it before walking
+    if let cli::MatchMode::Regex = options.match_mode {
+        let content_terms = content_query.iter().flat_map(|query| query.terms());
+        let patterns = options.filename_pattern.as_deref().into_iter().chain(content_terms);
+        if let Some(e) = patterns.filter_map(|pattern| search::build_regex(pattern, options.case_mode.sensitive_for(pattern)).err()).next() {
+            eprintln!("{} Invalid regex:\n{}", "❌".red(), e.to_string().yellow());
+            return Ok(());
+        }
+    }
+
+    let report = match cli.report.as_deref().map(report::ReportTarget::parse).transpose() {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{} Invalid --report: {}", "❌".red(), e);
+            return Ok(());
+        }
+    };
+
+    let redactor = match redact::Redactor::new(&config.output_options.redact_patterns) {
+        Ok(redactor) => redactor,
+        Err(e) => {
+            eprintln!("{} Invalid regex in {}, not searching so nothing it should hide is shown:\n{}",
+                      "❌".red(), "output_options.redact_patterns".green(), e.to_string().yellow());
+            return Ok(());
+        }
+    };
+
+    if cli.dry_run {
+        show_search_plan(&search_path, &options, &config, effective_parallel);
         return Ok(());
     }
 
-    // Show search summary
-    println!("{}", "🔎 SEARCH SUMMARY".yellow().bold());
-    if let Some(ref pattern) = cli.pattern {
-        println!(" Filename pattern: {}", pattern.bright_white().bold());
-    }
-    if let Some(ref pattern) = cli.content {
-        println!(" Content pattern: {}", pattern.bright_white().bold());
-    }
-    println!("   Path: {}", search_path.display().to_string().cyan());
-    if cli.dirs_only {
-        println!("   Filter: {} only", "directories".blue());
-    } else if cli.files_only {
-        println!("   Filter: {} only", "files".blue());
-    }
-    if cli.hidden {
-        println!("   Including: {} files", "hidden".blue());
-    }
-    println!();
-
-    // Perform search with cancellation support
-    let start_time = std::time::Instant::now();
-    let results = if cli.parallel {
-        search::search_files_parallel(
-            &search_path,
-            filename_pattern,
-            content_pattern,
-            effective_hidden,
-            cli.dirs_only,
-            cli.files_only,
-            cli.limit,
-            effective_details,
-            &cli.match_mode,
-            optimal_threads,
-            &config,
-        )
+    if cli.elevate {
+        if !elevate::is_allowed(&search_path, &config) {
+            eprintln!("{} {} only runs in directories listed in {}, {} isn't",
+                      "❌".red(), "--elevate".blue(), elevate::roots_file_name().green(), util::display_path(&search_path).cyan());
+            return Ok(());
+        }
+        eprintln!("{} Relaunching the search with admin rights...", "🔒".yellow());
+        match elevate::relaunch() {
+            Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+            Err(e) => {
+                eprintln!("{} Couldn't relaunch elevated: {}", "❌".red(), e);
+                return Ok(());
+            }
+        }
+    }
+
+    let hidden = cli.hidden || query.hidden == Some(true);
+    if cli.no_summary || !config.output_options.show_summary {
+        show_compact_header(&search_path, &options, effective_parallel);
     } else {
-        search::search_files(
-            &search_path,
-            filename_pattern,
-            content_pattern,
-            effective_hidden,
-            cli.dirs_only,
-            cli.files_only,
-            cli.limit,
-            effective_details,
-            &cli.match_mode,
-            &config,
-        )
-    };
-
-    let search_duration = start_time.elapsed();
-
-    // Display results
-    util::display_results(&results, cli.details);
-
-    if !results.is_empty() {
-        println!();
-        println!(
+        show_search_summary(&search_path, &options, &cli, &config, hidden, deep.is_some());
+    }
+
+    // Perform search with cancellation support. Narrowing from the selection prompt
+    // runs the same search again inside the chosen directory.
+    let mut search_path = search_path;
+    loop {
+        let start_time = std::time::Instant::now();
+        let mut warnings = warnings::Warnings::default();
+        let mut results = if effective_parallel {
+            search::search_files_parallel(&search_path, &options, &config, &mut warnings)
+        } else {
+            search::search_files(&search_path, &options, &config, &mut warnings)
+        };
+
+        let search_duration = start_time.elapsed();
+
+        // Taken before --only-top-type so the breakdown shows what was dropped
+        let breakdown = util::type_breakdown(&results);
+        if cli.only_top_type && breakdown.len() > 1 {
+            util::keep_top_type(&mut results, &breakdown);
+        }
+
+        // Before anything is shown or written to a report
+        if let Some(ref redactor) = redactor {
+            redactor.apply(&mut results);
+        }
+
+        if let Some(count) = cli.peek {
+            search::peek_dirs(&mut results, &search_path, &options, &config, count);
+        }
+
+        // Display results
+        if cli.print0 {
+            util::print_null_separated(&results)?;
+        } else if let Some(ref name) = cli.export_env {
+            export_env::print_assignments(&results, name, cli.env_shell.unwrap_or_else(export_env::EnvShell::native))?;
+        } else if cli.count {
+            util::display_counts(&results, !cli.no_pager);
+        } else if cli.group_by_match {
+            util::display_match_groups(&results, effective_limit, !cli.no_pager);
+        } else {
+            util::display_results(&results, options.show_details, config.output_options.max_line_length, !cli.no_pager);
+        }
+        if cli.type_breakdown || cli.only_top_type {
+            util::print_type_breakdown(&breakdown);
+        }
+        if cli.only_top_type && breakdown.len() > 1 {
+            eprintln!("{} Showing only {} results ({} of {}), drop {} to see all",
+                      "🔎".yellow(), breakdown[0].0.cyan(), results.len(),
+                      breakdown.iter().map(|(_, count)| count).sum::<usize>(), "--only-top-type".blue());
+        }
+        if let Some(ref target) = report {
+            let issues = warnings.summary_lines();
+            let record = report::SearchRecord { root: &search_path, options: &options, duration: search_duration, issues: &issues };
+            match report::write(target, &record, &results) {
+                Ok(()) => eprintln!("{} Report written to {}", "📝".green(), target.file.display().to_string().green()),
+                Err(e) => eprintln!("{} Couldn't write the report to {}: {}", "❌".red(), target.file.display(), e),
+            }
+        }
+        let denied = warnings.denied_paths();
+        warnings.print(options.verbose);
+        elevate::print_hint(&denied, &search_path, &config);
+
+        if let Some(ref hook) = config.hooks.post_search {
+            let placeholders = hooks::Placeholders::for_search(
+                &search_path, options.filename_pattern.as_deref(), options.content_pattern.as_deref(), results.len());
+            hooks::run("post_search", hook, &placeholders);
+        }
+
+        if results.is_empty() {
+            break;
+        }
+        eprintln!();
+        eprintln!(
             "{} Search completed in {:.1}ms",
             "⚡".yellow(),
             search_duration.as_millis()
         );
 
+        // The grouped and --count views have no per-file numbering to select from, --print0 and --export-env output is for other programs
+        if cli.group_by_match || cli.count || cli.print0 || cli.export_env.is_some() {
+            break;
+        }
+
         // Only do interactive selection if an action is requested
-        if cli.copy || cli.terminal {
-            if let Some(selected) = util::interactive_select(&results) {
-                if cli.copy {
-                    util::copy_to_clipboard(&selected.path.display().to_string())?;
-                } else if cli.terminal {
-                    util::change_directory(&selected.path);
+        let on_select = config.hooks.on_select.as_deref();
+        if cli.copy || cli.terminal || cli.link_to.is_some() || (on_select.is_some() && std::io::stdin().is_terminal()) {
+            match util::interactive_select(&results) {
+                Some(util::Selection::Result(selected)) => run_actions(selected, &cli, &config),
+                Some(util::Selection::NarrowTo(dir)) => {
+                    eprintln!();
+                    eprintln!("{} Narrowing to: {}", "🔎".yellow(), util::display_path(&dir).cyan());
+                    // --only-dirs names top-level directories of the original root
+                    options.only_dirs.clear();
+                    search_path = dir;
+                    continue;
                 }
+                None => {}
             }
         } else {
             // Default behavior: just show available actions
-            println!();
-            println!("{} Found {} files. Use these flags for actions:",
+            eprintln!();
+            eprintln!("{} Found {} files. Use these flags for actions:",
                      "💡".yellow(),
                      results.len().to_string().green()
             );
-            println!("   {} - Open selected file's directory in new terminal", "--t".blue());
-            println!("   {} - Copy selected file's path to clipboard", "--c".blue());
+            eprintln!("   {} - Open selected file's directory in new terminal", "--t".blue());
+            eprintln!("   {} - Copy selected file's path to clipboard", "--c".blue());
+            eprintln!("   {} - Symlink selected file into a folder", "--link-to DIR".blue());
         }
+        break;
     }
 
     Ok(())
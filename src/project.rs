@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+use crate::config::ProjectRoots;
+
+/// Where --project searches from, and the marker that decided it
+pub struct ProjectRoot {
+    pub path: PathBuf,
+    pub marker: String,
+}
+
+/// The project holding `start`: the nearest directory with a project marker, widened to an
+/// enclosing workspace (a Cargo `[workspace]`, npm `"workspaces"`, ...) within the same repository
+pub fn find_root(start: &Path, rules: &ProjectRoots) -> Option<ProjectRoot> {
+    let (nearest, marker) = start.ancestors().find_map(|dir| {
+        let marker = rules.markers.iter().find(|marker| dir.join(marker).exists())?;
+        Some((dir, marker))
+    })?;
+    let mut root = ProjectRoot { path: nearest.to_path_buf(), marker: marker.clone() };
+
+    // A repository boundary ends the climb, a workspace outside it belongs to something else
+    for dir in nearest.ancestors() {
+        if let Some(marker) = rules.workspace_markers.iter().find(|marker| declares_workspace(dir, marker)) {
+            root = ProjectRoot { path: dir.to_path_buf(), marker: format!("{} workspace", marker_file(marker)) };
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    Some(root)
+}
+
+/// `file` or `file:text`, e.g. `Cargo.toml:[workspace]` for a manifest that mentions the text
+fn declares_workspace(dir: &Path, marker: &str) -> bool {
+    match marker.split_once(':') {
+        Some((file, text)) => std::fs::read_to_string(dir.join(file)).is_ok_and(|contents| contents.contains(text)),
+        None => dir.join(marker).is_file(),
+    }
+}
+
+fn marker_file(marker: &str) -> &str {
+    marker.split_once(':').map_or(marker, |(file, _)| file)
+}
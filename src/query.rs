@@ -0,0 +1,270 @@
+use std::fmt;
+use clap::ValueEnum;
+use crate::cli::MatchMode;
+use crate::file_types::Category;
+use crate::filters::{AgeFilter, ResultFilters, SizeFilter};
+
+const FIELDS: &str = "name, content, ext, size, mtime, mode, type, hidden";
+
+/// A whole search in one string, e.g. `name:config ext:toml content:"port" size:<1M mtime:<7d`.
+/// Bare words are filename patterns. Fields left out keep their CLI/config values.
+#[derive(Debug, Default)]
+pub struct Query {
+    pub name: Option<String>,
+    pub content: Option<String>,
+    pub match_mode: Option<MatchMode>,
+    pub files_only: bool,
+    pub dirs_only: bool,
+    pub hidden: Option<bool>,
+    pub filters: ResultFilters,
+}
+
+/// Parse failure with the byte offset in the query it refers to
+#[derive(Debug)]
+pub struct QueryError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl QueryError {
+    pub fn new(position: usize, message: impl Into<String>) -> Self {
+        Self { position, message: message.into() }
+    }
+
+    /// The query with a caret under the offending position
+    pub fn render(&self, input: &str) -> String {
+        let column = input[..self.position.min(input.len())].chars().count();
+        format!("   {}\n   {}^ {}", input, " ".repeat(column), self.message)
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at position {})", self.message, self.position)
+    }
+}
+
+/// One `key:value` or bare term, with offsets for error reporting
+struct Term {
+    key: Option<(String, usize)>,
+    value: String,
+    value_start: usize,
+}
+
+pub fn parse(input: &str) -> Result<Query, QueryError> {
+    let terms = tokenize(input)?;
+    if terms.is_empty() {
+        return Err(QueryError::new(0, "query is empty"));
+    }
+
+    let mut query = Query::default();
+    for term in terms {
+        let Some((key, key_start)) = term.key else {
+            set_once(&mut query.name, term.value, term.value_start, "name")?;
+            continue;
+        };
+        let at = |message: String| QueryError::new(term.value_start, message);
+
+        match key.as_str() {
+            "name" => set_once(&mut query.name, term.value, term.value_start, "name")?,
+            "content" => set_once(&mut query.content, term.value, term.value_start, "content")?,
+            "ext" => query.filters.add_extensions(&term.value),
+            "size" => query.filters.sizes.push(SizeFilter::parse(&term.value).map_err(at)?),
+            "mtime" => query.filters.ages.push(AgeFilter::parse(&term.value).map_err(at)?),
+            "mode" => {
+                let mode = MatchMode::from_str(&term.value, true)
+                    .map_err(|_| at(format!("unknown mode `{}` (use fuzzy, exact or regex)", term.value)))?;
+                query.match_mode = Some(mode);
+            }
+            "type" => match term.value.to_lowercase().as_str() {
+                "f" | "file" => query.files_only = true,
+                "d" | "dir" | "directory" => query.dirs_only = true,
+                value => match Category::from_str(value, true) {
+                    Ok(category) => query.filters.categories.push(category),
+                    Err(_) => return Err(at(format!(
+                        "unknown type `{}` (use file, dir, code, data, doc, image, video, audio or archive)", term.value))),
+                },
+            },
+            "hidden" => {
+                let hidden = match term.value.to_lowercase().as_str() {
+                    "yes" | "true" | "on" => true,
+                    "no" | "false" | "off" => false,
+                    _ => return Err(at(format!("expected yes or no, got `{}`", term.value))),
+                };
+                query.hidden = Some(hidden);
+            }
+            _ => return Err(QueryError::new(key_start, format!("unknown field `{}` (expected {})", key, FIELDS))),
+        }
+    }
+
+    if query.files_only && query.dirs_only {
+        return Err(QueryError::new(0, "type:file and type:dir can't be combined"));
+    }
+    Ok(query)
+}
+
+fn set_once(slot: &mut Option<String>, value: String, position: usize, field: &str) -> Result<(), QueryError> {
+    if value.trim().is_empty() {
+        return Err(QueryError::new(position, format!("{} can't be empty", field)));
+    }
+    if slot.is_some() {
+        return Err(QueryError::new(position, format!("{} is given more than once", field)));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+fn tokenize(input: &str) -> Result<Vec<Term>, QueryError> {
+    let mut terms = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        // A leading `word:` is a field name, anything else is a bare value
+        let mut key = None;
+        let mut word = String::new();
+        let mut value_start = start;
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() || c == '"' {
+                break;
+            }
+            chars.next();
+            if c == ':' && key.is_none() && !word.is_empty() && word.chars().all(|c| c.is_ascii_alphabetic()) {
+                key = Some((word.to_lowercase(), start));
+                word.clear();
+                value_start = i + 1;
+                continue;
+            }
+            word.push(c);
+        }
+
+        // Quoted values may contain spaces and \" escapes
+        if let Some(&(quote_start, '"')) = chars.peek() {
+            if !word.is_empty() {
+                return Err(QueryError::new(quote_start, "unexpected quote inside a value"));
+            }
+            chars.next();
+            value_start = quote_start;
+            let mut closed = false;
+            while let Some((_, c)) = chars.next() {
+                match c {
+                    '"' => {
+                        closed = true;
+                        break;
+                    }
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            word.push(escaped);
+                        }
+                    }
+                    _ => word.push(c),
+                }
+            }
+            if !closed {
+                return Err(QueryError::new(quote_start, "unterminated quote"));
+            }
+            if chars.peek().is_some_and(|&(_, c)| !c.is_whitespace()) {
+                let (position, _) = *chars.peek().unwrap();
+                return Err(QueryError::new(position, "expected a space after the closing quote"));
+            }
+        }
+
+        if key.is_some() && word.is_empty() {
+            return Err(QueryError::new(value_start, "missing value after `:`"));
+        }
+        terms.push(Term { key, value: word, value_start });
+    }
+
+    Ok(terms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::Bound;
+
+    fn error_at(input: &str) -> (usize, String) {
+        let error = parse(input).unwrap_err();
+        (error.position, error.message)
+    }
+
+    #[test]
+    fn bare_words_are_the_name_pattern() {
+        let query = parse("config").unwrap();
+        assert_eq!(query.name.as_deref(), Some("config"));
+        assert!(query.content.is_none());
+        assert!(query.filters.is_empty());
+    }
+
+    #[test]
+    fn fields_fill_their_part_of_the_search() {
+        let query = parse(r#"name:config ext:toml,.JSON content:"listen port" size:<1M mtime:<7d mode:exact type:f hidden:yes"#).unwrap();
+        assert_eq!(query.name.as_deref(), Some("config"));
+        assert_eq!(query.content.as_deref(), Some("listen port"));
+        assert_eq!(query.filters.extensions, ["toml", "json"]);
+        assert_eq!(query.filters.sizes[0].bound, Bound::Below);
+        assert_eq!(query.filters.sizes[0].bytes, 1024 * 1024);
+        assert_eq!(query.filters.ages.len(), 1);
+        assert!(matches!(query.match_mode, Some(MatchMode::Exact)));
+        assert!(query.files_only);
+        assert_eq!(query.hidden, Some(true));
+    }
+
+    #[test]
+    fn field_names_are_case_insensitive() {
+        let query = parse("NAME:main Type:Dir").unwrap();
+        assert_eq!(query.name.as_deref(), Some("main"));
+        assert!(query.dirs_only);
+    }
+
+    #[test]
+    fn type_accepts_categories() {
+        let query = parse("type:code type:image").unwrap();
+        assert_eq!(query.filters.categories, [Category::Code, Category::Image]);
+    }
+
+    #[test]
+    fn colons_after_the_field_name_belong_to_the_value() {
+        let query = parse("content:http://localhost").unwrap();
+        assert_eq!(query.content.as_deref(), Some("http://localhost"));
+        assert!(parse("C:stuff").is_err());
+        assert_eq!(parse("2024:report").unwrap().name.as_deref(), Some("2024:report"));
+    }
+
+    #[test]
+    fn quotes_keep_spaces_and_escapes() {
+        let query = parse(r#"content:"say \"hi\" there""#).unwrap();
+        assert_eq!(query.content.as_deref(), Some(r#"say "hi" there"#));
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_part() {
+        assert_eq!(error_at(""), (0, "query is empty".to_string()));
+        assert_eq!(error_at("name:a colour:red").0, 7);
+        assert_eq!(error_at("size:big").0, 5);
+        assert_eq!(error_at("mode:fast").0, 5);
+        assert_eq!(error_at("hidden:maybe").0, 7);
+        assert_eq!(error_at(r#"content:"open"#), (8, "unterminated quote".to_string()));
+        assert_eq!(error_at(r#"content:"a"b"#), (11, "expected a space after the closing quote".to_string()));
+        assert_eq!(error_at(r#"ab"c""#), (2, "unexpected quote inside a value".to_string()));
+        assert_eq!(error_at("name:"), (5, "missing value after `:`".to_string()));
+    }
+
+    #[test]
+    fn fields_are_given_once_and_types_not_mixed() {
+        assert_eq!(error_at("main name:lib"), (10, "name is given more than once".to_string()));
+        assert_eq!(error_at("content:a content:b").1, "content is given more than once");
+        assert!(parse("type:file type:dir").is_err());
+        assert_eq!(error_at(r#"content:"  ""#).1, "content can't be empty");
+    }
+
+    #[test]
+    fn render_puts_a_caret_under_the_position() {
+        let error = QueryError::new(5, "here");
+        assert_eq!(error.render("name:x"), "   name:x\n        ^ here");
+    }
+}
@@ -0,0 +1,82 @@
+use regex::Regex;
+use crate::{ContentMatch, SearchResult};
+
+/// What a redacted span is replaced with
+const MASK: &str = "[REDACTED]";
+
+/// Masks what config `output_options.redact_patterns` matches in the stored lines of content
+/// matches, so secrets don't reach the terminal, the pager or a --report
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// None without patterns. An invalid pattern is an error rather than skipped, a secret
+    /// it was meant to hide would otherwise be shown.
+    pub fn new(patterns: &[String]) -> Result<Option<Self>, regex::Error> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        let patterns = patterns.iter().map(|pattern| Regex::new(pattern)).collect::<Result<_, _>>()?;
+        Ok(Some(Self { patterns }))
+    }
+
+    pub fn apply(&self, results: &mut [SearchResult]) {
+        for content_match in results.iter_mut().flat_map(|result| result.content_matches.iter_mut()) {
+            self.redact(content_match);
+        }
+    }
+
+    fn redact(&self, content_match: &mut ContentMatch) {
+        let line = &content_match.line_content;
+        let mut spans: Vec<(usize, usize)> = self
+            .patterns
+            .iter()
+            .flat_map(|pattern| pattern.find_iter(line).map(|found| (found.start(), found.end())))
+            .filter(|(start, end)| start < end)
+            .collect();
+        if spans.is_empty() {
+            return;
+        }
+
+        // Overlapping hits of different patterns become one mask
+        spans.sort();
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in spans {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        let mut redacted = String::with_capacity(line.len());
+        let mut copied = 0;
+        for &(start, end) in &merged {
+            redacted.push_str(&line[copied..start]);
+            redacted.push_str(MASK);
+            copied = end;
+        }
+        redacted.push_str(&line[copied..]);
+
+        content_match.match_start = shifted(content_match.match_start, &merged, false);
+        content_match.match_end = shifted(content_match.match_end, &merged, true);
+        content_match.line_content = redacted;
+    }
+}
+
+/// Where a byte offset lands once `spans` are masked. An offset inside a mask moves to its
+/// start, or its end for the end of a match, so a highlighted secret highlights the mask.
+fn shifted(offset: usize, spans: &[(usize, usize)], is_end: bool) -> usize {
+    let mut growth = 0isize;
+    for &(start, end) in spans {
+        if offset <= start {
+            break;
+        }
+        let masked_start = (start as isize + growth) as usize;
+        if offset < end {
+            return if is_end { masked_start + MASK.len() } else { masked_start };
+        }
+        growth += MASK.len() as isize - (end - start) as isize;
+    }
+    (offset as isize + growth) as usize
+}
@@ -0,0 +1,313 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use clap::ValueEnum;
+use crate::search::SearchOptions;
+use crate::syntax::{Scope, ScopeTracker};
+use crate::{util, ContentMatch, SearchResult};
+
+/// Document type --report renders
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Markdown, e.g. for pasting into an issue or pull request
+    Md,
+    /// A standalone page with highlighted excerpts
+    Html,
+}
+
+/// Where --report writes, from its two values `FORMAT FILE`
+pub struct ReportTarget {
+    pub format: ReportFormat,
+    pub file: PathBuf,
+}
+
+impl ReportTarget {
+    pub fn parse(values: &[String]) -> Result<Self, String> {
+        let [format, file] = values else { return Err("expected a format and a file".to_string()) };
+        let format = ReportFormat::from_str(format, true)
+            .map_err(|_| format!("unknown report format `{}`, use md or html", format))?;
+        Ok(Self { format, file: PathBuf::from(file) })
+    }
+}
+
+/// What the report says about the search besides its results
+pub struct SearchRecord<'a> {
+    pub root: &'a Path,
+    pub options: &'a SearchOptions,
+    pub duration: Duration,
+    pub issues: &'a [String],
+}
+
+/// Render the report and write it in one go, so a failed write leaves no half a report behind
+pub fn write(target: &ReportTarget, search: &SearchRecord, results: &[SearchResult]) -> io::Result<()> {
+    let content = match target.format {
+        ReportFormat::Md => markdown(search, results),
+        ReportFormat::Html => html(search, results),
+    };
+    util::write_atomically(&target.file, &content)
+}
+
+/// The search settings worth recording, as label and value
+fn search_facts(search: &SearchRecord) -> Vec<(&'static str, String)> {
+    let options = search.options;
+    let mut facts = vec![("Root", util::display_path(search.root))];
+    if let Some(ref pattern) = options.filename_pattern {
+        facts.push(("Name pattern", pattern.clone()));
+    }
+    if let Some(ref pattern) = options.content_pattern {
+        facts.push(("Content pattern", pattern.clone()));
+    }
+    if let Some(scope) = options.content_scope {
+        facts.push(("Content matches in", scope.name().to_string()));
+    }
+    facts.push(("Match mode", format!("{:?}", options.match_mode).to_lowercase()));
+    facts.push(("Case", options.case_mode.label().to_string()));
+    if options.dirs_only {
+        facts.push(("Entries", "directories only".to_string()));
+    } else if options.files_only {
+        facts.push(("Entries", "files only".to_string()));
+    }
+    if !options.filters.is_empty() {
+        facts.push(("Filters", options.filters.describe()));
+    }
+    facts.push(("Hidden files", if options.include_hidden { "included" } else { "skipped" }.to_string()));
+    if options.archives {
+        facts.push(("Archives", "searched".to_string()));
+    }
+    facts.push(("Generated", format_timestamp(SystemTime::now())));
+    facts.push(("Duration", format!("{:.1}s", search.duration.as_secs_f64())));
+    facts.push(("ff version", env!("CARGO_PKG_VERSION").to_string()));
+    facts
+}
+
+/// Totals over the results, as label and value
+fn result_stats(results: &[SearchResult]) -> Vec<(&'static str, String)> {
+    let files = results.iter().filter(|result| !result.is_dir).count();
+    let total_size: u64 = results.iter().filter_map(|result| result.size).sum();
+    let matching_lines: usize = results.iter().map(|result| lines_of(&result.content_matches).len()).sum();
+    let types: Vec<String> = util::type_breakdown(results).iter().map(|(kind, count)| format!("{} {}", count, kind)).collect();
+
+    let mut stats = vec![
+        ("Results", results.len().to_string()),
+        ("Files", files.to_string()),
+        ("Directories", (results.len() - files).to_string()),
+        ("Total size", util::format_size(total_size)),
+    ];
+    if matching_lines > 0 {
+        stats.push(("Matching lines", matching_lines.to_string()));
+    }
+    if !types.is_empty() {
+        stats.push(("Types", types.join(", ")));
+    }
+    stats
+}
+
+/// Matches grouped by line, since a line with several matches is stored once per match
+fn lines_of(matches: &[ContentMatch]) -> Vec<Vec<&ContentMatch>> {
+    let mut lines: Vec<Vec<&ContentMatch>> = Vec::new();
+    for content_match in matches {
+        match lines.last_mut() {
+            Some(line) if line[0].line_number == content_match.line_number && line[0].location == content_match.location => {
+                line.push(content_match)
+            }
+            _ => lines.push(vec![content_match]),
+        }
+    }
+    lines
+}
+
+fn position(content_match: &ContentMatch) -> String {
+    match content_match.location {
+        Some(ref location) => location.clone(),
+        None => format!("L{}", content_match.line_number),
+    }
+}
+
+fn size_and_age(result: &SearchResult) -> (String, String) {
+    let size = if result.is_dir { String::new() } else { result.size.map(util::format_size).unwrap_or_default() };
+    (size, result.modified.map(format_timestamp).unwrap_or_default())
+}
+
+/// `2024-05-01 14:03 UTC`
+fn format_timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()) as i64;
+    let (days, seconds) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's civil_from_days)
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, seconds / 3600, seconds % 3600 / 60)
+}
+
+fn markdown(search: &SearchRecord, results: &[SearchResult]) -> String {
+    let mut out = String::from("# ff search report\n\n## Search\n\n");
+    for (label, value) in search_facts(search) {
+        out.push_str(&format!("- **{}:** {}\n", label, markdown_code(&value)));
+    }
+
+    out.push_str("\n## Summary\n\n");
+    for (label, value) in result_stats(results) {
+        out.push_str(&format!("- **{}:** {}\n", label, value));
+    }
+    if !search.issues.is_empty() {
+        out.push_str("\n## Issues\n\n");
+        for issue in search.issues {
+            out.push_str(&format!("- {}\n", issue));
+        }
+    }
+
+    out.push_str("\n## Results\n\n");
+    if results.is_empty() {
+        out.push_str("No matches.\n");
+        return out;
+    }
+    out.push_str("| # | Path | Size | Modified | Match |\n|---|------|------|----------|-------|\n");
+    for (index, result) in results.iter().enumerate() {
+        let (size, modified) = size_and_age(result);
+        let path = markdown_code(&util::result_path(result)).replace('|', "\\|");
+        out.push_str(&format!("| {} | {} | {} | {} | {} |\n", index + 1, path, size, modified, util::match_provenance(result)));
+    }
+
+    for (index, result) in results.iter().enumerate().filter(|(_, result)| !result.content_matches.is_empty()) {
+        out.push_str(&format!("\n### {}. {}\n\n", index + 1, markdown_code(&util::result_path(result))));
+        let excerpt: Vec<String> = lines_of(&result.content_matches)
+            .iter()
+            .map(|line| format!("{}: {}", position(line[0]), line[0].line_content.trim_end()))
+            .collect();
+        // The fence has to be longer than any run of backticks in the excerpt
+        let longest_run = excerpt.iter().flat_map(|line| line.split(|c| c != '`')).map(str::len).max().unwrap_or(0);
+        let fence = "`".repeat((longest_run + 1).max(3));
+        // Renderers highlight fenced code by its language, named like the file's extension
+        let language = util::result_extension(result).unwrap_or_default().to_lowercase();
+        out.push_str(&format!("{}{}\n{}\n{}\n", fence, language, excerpt.join("\n"), fence));
+    }
+    out
+}
+
+/// Inline code that survives backticks in the text
+fn markdown_code(text: &str) -> String {
+    if text.contains('`') { format!("`` {} ``", text) } else { format!("`{}`", text) }
+}
+
+const HTML_STYLE: &str = "\
+body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 72rem; padding: 0 1rem; color: #1f2328; }
+table { border-collapse: collapse; margin-bottom: 1rem; }
+th, td { border: 1px solid #d0d7de; padding: 0.3rem 0.6rem; text-align: left; vertical-align: top; }
+th { background: #f6f8fa; }
+code, pre { font-family: ui-monospace, monospace; font-size: 0.9em; }
+pre { background: #f6f8fa; padding: 0.8rem; overflow-x: auto; }
+.position { color: #57606a; user-select: none; }
+.comment { color: #6e7781; font-style: italic; }
+.string { color: #0a3069; }
+mark { background: #fff8c5; font-weight: bold; }
+";
+
+fn html(search: &SearchRecord, results: &[SearchResult]) -> String {
+    let title = search.options.filename_pattern.as_deref().or(search.options.content_pattern.as_deref()).unwrap_or("search");
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>ff report: {}</title>\n<style>\n{}</style>\n</head>\n<body>\n<h1>ff search report</h1>\n",
+        escape_html(title), HTML_STYLE);
+
+    out.push_str("<h2>Search</h2>\n<table>\n");
+    for (label, value) in search_facts(search) {
+        out.push_str(&format!("<tr><th>{}</th><td><code>{}</code></td></tr>\n", label, escape_html(&value)));
+    }
+    out.push_str("</table>\n<h2>Summary</h2>\n<table>\n");
+    for (label, value) in result_stats(results) {
+        out.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", label, escape_html(&value)));
+    }
+    out.push_str("</table>\n");
+    if !search.issues.is_empty() {
+        out.push_str("<h2>Issues</h2>\n<ul>\n");
+        for issue in search.issues {
+            out.push_str(&format!("<li>{}</li>\n", escape_html(issue)));
+        }
+        out.push_str("</ul>\n");
+    }
+
+    out.push_str("<h2>Results</h2>\n");
+    if results.is_empty() {
+        out.push_str("<p>No matches.</p>\n</body>\n</html>\n");
+        return out;
+    }
+    out.push_str("<table>\n<tr><th>#</th><th>Path</th><th>Size</th><th>Modified</th><th>Match</th></tr>\n");
+    for (index, result) in results.iter().enumerate() {
+        let (size, modified) = size_and_age(result);
+        let path = escape_html(&util::result_path(result));
+        // Results with excerpts link down to them
+        let path = if result.content_matches.is_empty() { path } else { format!("<a href=\"#result-{}\">{}</a>", index + 1, path) };
+        out.push_str(&format!("<tr><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                              index + 1, path, size, modified, escape_html(&util::match_provenance(result))));
+    }
+    out.push_str("</table>\n");
+
+    for (index, result) in results.iter().enumerate().filter(|(_, result)| !result.content_matches.is_empty()) {
+        let name = util::result_path(result);
+        out.push_str(&format!("<h3 id=\"result-{}\">{}. <code>{}</code></h3>\n<pre>\n", index + 1, index + 1, escape_html(&name)));
+        for line in lines_of(&result.content_matches) {
+            out.push_str(&format!("<span class=\"position\">{}:</span> {}\n",
+                              escape_html(&position(line[0])), highlight_line(Path::new(&name), &line)));
+        }
+        out.push_str("</pre>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// One excerpt line as HTML: comments and strings colored for languages --in knows, matches marked
+fn highlight_line(path: &Path, matches: &[&ContentMatch]) -> String {
+    let line = matches[0].line_content.trim_end();
+    let regions = ScopeTracker::line_regions(path, line).unwrap_or_default();
+    let ranges: Vec<(usize, usize)> = matches
+        .iter()
+        .map(|m| (m.match_start, m.match_end))
+        .filter(|&(start, end)| start < end && line.get(start..end).is_some())
+        .collect();
+
+    // Cut the line wherever a region or a match starts or ends, then style each piece
+    let mut cuts: Vec<usize> = regions.iter().map(|&(start, _)| start)
+        .chain(ranges.iter().flat_map(|&(start, end)| [start, end]))
+        .chain([0, line.len()])
+        .filter(|&cut| cut <= line.len())
+        .collect();
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let mut out = String::new();
+    for piece in cuts.windows(2) {
+        let (start, end) = (piece[0], piece[1]);
+        let mut text = escape_html(&line[start..end]);
+        if ranges.iter().any(|&(match_start, match_end)| match_start <= start && end <= match_end) {
+            text = format!("<mark>{}</mark>", text);
+        }
+        match regions.iter().rev().find(|&&(region_start, _)| region_start <= start).map(|&(_, scope)| scope) {
+            Some(Scope::Comments) => out.push_str(&format!("<span class=\"comment\">{}</span>", text)),
+            Some(Scope::Strings) => out.push_str(&format!("<span class=\"string\">{}</span>", text)),
+            _ => out.push_str(&text),
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
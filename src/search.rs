@@ -2,80 +2,1146 @@ use rayon::iter::ParallelIterator;
 use std::{io, thread};
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 use colored::Colorize;
 use fuzzy_matcher::FuzzyMatcher;
-use rayon::iter::IntoParallelIterator;
+use rayon::iter::{IntoParallelIterator, ParallelBridge};
 use rayon::prelude::ParallelSliceMut;
-use walkdir::WalkDir;
 use crate::cli::MatchMode;
 use crate::{util, SearchResult};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
 use crate::{ContentMatch, SearchType};
+use crate::ignore_files::IgnoreFiles;
+use crate::ignore_rules::IgnoreRules;
+use crate::watchdog::StallGuard;
+use crate::walker::{self, Backend, EntryFilter};
+use crate::content_cache::ContentCache;
+use crate::filters::ResultFilters;
+use crate::limits::{LimitDecision, SearchLimits};
+use crate::warnings::Warnings;
+use crate::content_query::{self, ContentQuery};
+use crate::extract;
+use crate::archive;
+use crate::encoding::{self, TextEncoding};
+use crate::syntax::{Scope, ScopeTracker};
+use regex::{Regex, RegexBuilder};
+use std::borrow::Cow;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use aho_corasick::{AhoCorasick, MatchKind};
+
+/// Per-search settings resolved once from CLI flags and config, shared by both search paths
+#[derive(Default)]
+pub struct SearchOptions {
+    pub filename_pattern: Option<String>,
+    pub content_pattern: Option<String>,
+    pub content_patterns: Vec<String>,  // Each --content flag, several of them are matched in one pass
+    pub include_hidden: bool,
+    pub follow_symlinks: bool,  // --follow / --no-follow, falling back to the config
+    pub dirs_only: bool,
+    pub files_only: bool,
+    pub limit: usize,
+    pub show_details: bool,
+    pub match_mode: MatchMode,
+    pub case_mode: CaseMode,  // Applies to file names and content in every match mode
+    pub content_scope: Option<Scope>,  // --in: only content matches in comments, strings or code
+    pub full_path: bool,      // Match name patterns against the relative path, not just the file name
+    pub min_score: i64,       // Fuzzy name score required per pattern character, 0 keeps every match
+    pub threads: usize,
+    pub content_threads: Option<usize>,  // Concurrent content scans in parallel mode, None = one per thread
+    pub max_files: usize,  // Entries visited before the walk stops, usize::MAX for no cap
+    pub time_limit: Option<Duration>,  // Elapsed time before asking whether to keep going
+    pub strict_time_limit: bool,       // Stop at the time limit instead of asking (or only warning when piped)
+    pub memory_budget: Option<u64>,    // Bytes the parallel path list may take before the walk stops
+    pub ignore_rules: IgnoreRules,
+    pub stall_timeout: Option<Duration>,
+    pub dir_entry_cap: Option<usize>,  // Entries read per directory, None to read them all
+    pub hydrate: bool,
+    pub backend: Backend,
+    pub content_cache_entries: usize,  // 0 disables the persistent content cache
+    pub verbose: bool,
+    pub only_dirs: Vec<String>,  // Top-level directories of the root to search, empty for all
+    pub min_depth: usize,          // Shallowest depth reported, 1 = entries directly in the root
+    pub max_depth: Option<usize>,  // Deepest depth read, None for no limit
+    pub skip_reparse: bool,      // Leave out symlinks, junctions and other reparse points
+    pub filters: ResultFilters,
+    pub archives: bool,          // Also match the entries of zip and tar archives
+    pub transliterate: bool,     // Compare names as ASCII, `muller` finds `Müller.pdf`
+    pub multiline: bool,         // Content regexes run over whole files and can span lines
+    pub count_only: bool,        // --count: only count content matches, store no lines
+    pub files_without_match: bool,  // Content searches list the scanned files the query doesn't match
+    pub limit_files: Option<usize>,    // --limit-files: a content search stops at this many matching files
+    pub limit_matches: Option<usize>,  // --limit-matches: ... or at this many matches across files
+    pub git_files: Option<Arc<crate::git::GitFiles>>,  // --git-tracked / --git-dirty / --git-diff: only what git lists
+}
+
+impl SearchOptions {
+    /// The name pattern as it is compared against names, ASCII-folded with --transliterate
+    pub fn name_pattern(&self) -> Option<Cow<'_, str>> {
+        let pattern = self.filename_pattern.as_deref()?;
+        Some(if self.transliterating() { transliterate(pattern) } else { Cow::Borrowed(pattern) })
+    }
+
+    /// The --content query. Repeated --content flags are literal patterns, any of which may match.
+    pub fn content_query(&self) -> Option<Result<ContentQuery, crate::query::QueryError>> {
+        if self.content_patterns.len() > 1 {
+            return Some(Ok(ContentQuery::AnyOf(self.content_patterns.clone())));
+        }
+        self.content_pattern.as_deref().map(content_query::parse)
+    }
+
+    /// Regex patterns are left alone, folding `_` would change what they mean
+    fn transliterating(&self) -> bool {
+        self.transliterate && !matches!(self.match_mode, MatchMode::Regex)
+    }
+}
+
+/// How letter case is compared. Smart resolves per pattern, so a hybrid search can
+/// match `README` case-sensitively and `todo` insensitively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaseMode {
+    Sensitive,
+    Insensitive,
+    #[default]
+    Smart,  // Sensitive only when the pattern has an uppercase letter, like ripgrep's --smart-case
+}
+
+impl CaseMode {
+    pub fn sensitive_for(self, pattern: &str) -> bool {
+        match self {
+            CaseMode::Sensitive => true,
+            CaseMode::Insensitive => false,
+            CaseMode::Smart => has_uppercase(pattern),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CaseMode::Sensitive => "sensitive",
+            CaseMode::Insensitive => "insensitive",
+            CaseMode::Smart => "smart",
+        }
+    }
+}
+
+/// Uppercase letters outside escapes, so regex classes like `\S` or `\W` don't count
+fn has_uppercase(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Which filename algorithm produced a result's score, kept for --details
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameMatch {
+    Prefix,
+    Substring,
+    Fuzzy,
+    Regex,
+}
+
+impl NameMatch {
+    pub fn label(self) -> &'static str {
+        match self {
+            NameMatch::Prefix => "name prefix",
+            NameMatch::Substring => "name substring",
+            NameMatch::Fuzzy => "name fuzzy",
+            NameMatch::Regex => "name regex",
+        }
+    }
+}
+
+/// Longest stretch of a line kept per match. Minified files can have multi-megabyte lines
+/// with many matches, and previews only ever show a window around the match anyway.
+const MAX_STORED_LINE_BYTES: usize = 4096;
 
-pub fn search_file_content(
+/// Leading bytes checked for NULs and the text encoding before a file is scanned
+pub const BINARY_PROBE_BYTES: usize = 8 * 1024;
+
+/// How much of a file is scanned between Ctrl+C checks
+const CANCEL_CHECK_BYTES: usize = 256 * 1024;
+
+/// Content matches of a scan. With --count only their number is kept, no lines are stored.
+#[derive(Default)]
+pub struct Matches {
+    found: Vec<ContentMatch>,
+    count: usize,
+    count_only: bool,
+}
+
+impl Matches {
+    fn new(count_only: bool) -> Self {
+        Self { count_only, ..Default::default() }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// `make` only runs when the match is stored
+    fn push(&mut self, make: impl FnOnce() -> ContentMatch) {
+        self.count += 1;
+        if !self.count_only {
+            self.found.push(make());
+        }
+    }
+
+    fn extend(&mut self, other: Matches) {
+        self.count += other.count;
+        self.found.extend(other.found);
+    }
+}
+
+/// Result of scanning one file
+pub struct ScanOutcome {
+    matches: Matches,
+    bytes_read: u64,
+    truncated: bool,  // Stopped at the per-file byte budget before the end of the file
+}
+
+/// Scan up to `max_bytes` of a file for the pattern, with the persistent cache in front. Unchanged files
+/// known not to match are never opened, and known matches only recheck their own lines.
+fn cached_search_file_content(
     file_path: &Path,
-    pattern: &str,
-    match_mode: &MatchMode,
-) -> Result<Vec<ContentMatch>, Box<dyn std::error::Error>> {
+    line_matcher: &LineMatcher,
+    running: &AtomicBool,
+    cache: Option<&Mutex<ContentCache>>,
+    max_bytes: u64,
+) -> Result<ScanOutcome, Box<dyn std::error::Error>> {
+    let Some(cache) = cache else {
+        return scan_lines(file_path, line_matcher, running, None, max_bytes);
+    };
+
+    let metadata = std::fs::metadata(file_path)?;
+    let query = line_matcher.cache_key();
+
+    let cached_lines = cache.lock().unwrap().lookup(&query, file_path, &metadata);
+    match cached_lines {
+        Some(lines) if lines.is_empty() => Ok(ScanOutcome { matches: Matches::default(), bytes_read: 0, truncated: false }),
+        Some(lines) => scan_lines(file_path, line_matcher, running, Some(&lines), max_bytes),
+        None => {
+            let outcome = scan_lines(file_path, line_matcher, running, None, max_bytes)?;
+
+            // Cancelled or budget-truncated scans are incomplete, and --count scans don't know their lines
+            if running.load(Ordering::Relaxed) && !outcome.truncated && !line_matcher.count_only {
+                let mut lines: Vec<usize> = outcome.matches.found.iter().map(|m| m.line_number).collect();
+                lines.dedup();
+                cache.lock().unwrap().store(&query, file_path, &metadata, lines);
+            }
+            Ok(outcome)
+        }
+    }
+}
+
+/// A content query ready to run against every file. In regex mode each term is compiled
+/// once, and the workers share the compiled regexes.
+struct PreparedQuery {
+    query: ContentQuery,
+    regexes: HashMap<String, Regex>,  // Term -> its content regex, regex mode only
+}
+
+/// A --content pattern as a query, a plain term if it doesn't parse (main reports that before searching)
+fn parse_content_query(options: &SearchOptions) -> Option<PreparedQuery> {
+    let query = options.content_query()?;
+    let query = query.unwrap_or_else(|_| ContentQuery::Term(options.content_pattern.clone().unwrap_or_default()));
+    let mut regexes = HashMap::new();
+    if matches!(options.match_mode, MatchMode::Regex) {
+        for term in query.terms() {
+            // Over a whole file, ^ and $ still have to mean the start and end of a line
+            let source = if options.multiline { Cow::Owned(format!("(?m){}", term)) } else { Cow::Borrowed(term) };
+            if let Ok(regex) = build_regex(&source, options.case_mode.sensitive_for(term)) {
+                regexes.insert(term.to_string(), regex);
+            }
+        }
+    }
+    Some(PreparedQuery { query, regexes })
+}
+
+/// Evaluate a content query against one file, scanning and caching each term on its own
+fn search_content_query(
+    file_path: &Path,
+    query: &PreparedQuery,
+    options: &SearchOptions,
+    running: &AtomicBool,
+    cache: Option<&Mutex<ContentCache>>,
+    max_bytes: u64,
+) -> Result<(bool, ScanOutcome), Box<dyn std::error::Error>> {
+    evaluate_content_query(query, options, |line_matcher| {
+        cached_search_file_content(file_path, line_matcher, running, cache, max_bytes)
+    })
+}
+
+/// Evaluate a content query. Repeated --content patterns take a single scan, other queries
+/// one per term (see `evaluate_terms`).
+fn evaluate_content_query(
+    query: &PreparedQuery,
+    options: &SearchOptions,
+    mut scan: impl FnMut(&LineMatcher) -> Result<ScanOutcome, Box<dyn std::error::Error>>,
+) -> Result<(bool, ScanOutcome), Box<dyn std::error::Error>> {
+    let (hit, outcome) = match &query.query {
+        ContentQuery::AnyOf(patterns) => {
+            let key = patterns.join("\n");
+            let line_matcher = LineMatcher::any_of(patterns, &key, options)?;
+            let outcome = scan(&line_matcher)?;
+            (!outcome.matches.is_empty(), outcome)
+        }
+        _ => evaluate_terms(query, options, scan)?,
+    };
+    if options.files_without_match {
+        // A file listed for missing the query has no lines of it to show
+        return Ok((!hit, ScanOutcome { matches: Matches::default(), ..outcome }));
+    }
+    Ok((hit, outcome))
+}
+
+/// Run `scan` for each term only while the answer still depends on it. Matches of the terms
+/// not under a NOT are kept, in file order.
+fn evaluate_terms(
+    prepared: &PreparedQuery,
+    options: &SearchOptions,
+    mut scan: impl FnMut(&LineMatcher) -> Result<ScanOutcome, Box<dyn std::error::Error>>,
+) -> Result<(bool, ScanOutcome), Box<dyn std::error::Error>> {
+    let query = &prepared.query;
+    let mut scanned: Vec<(String, ScanOutcome)> = Vec::new();
+    let hit = query.evaluate(&mut |term| {
+        if let Some((_, outcome)) = scanned.iter().find(|(scanned_term, _)| scanned_term == term) {
+            return Ok(!outcome.matches.is_empty());
+        }
+        let line_matcher = LineMatcher::new(
+            term, &options.match_mode, options.case_mode.sensitive_for(term), options.content_scope, options.multiline, options.count_only,
+            prepared.regexes.get(term).cloned())?;
+        let outcome = scan(&line_matcher)?;
+        let found = !outcome.matches.is_empty();
+        scanned.push((term.to_string(), outcome));
+        Ok::<_, Box<dyn std::error::Error>>(found)
+    })?;
+
+    let shown = if hit { query.shown_terms() } else { Vec::new() };
+    let mut combined = ScanOutcome { matches: Matches::default(), bytes_read: 0, truncated: false };
+    for (term, outcome) in scanned {
+        combined.bytes_read += outcome.bytes_read;
+        combined.truncated |= outcome.truncated;
+        if shown.contains(&term.as_str()) {
+            combined.matches.extend(outcome.matches);
+        }
+    }
+    if shown.len() > 1 {
+        combined.matches.found.sort_by_key(|m| (m.line_number, m.match_start));
+    }
+    Ok((hit, combined))
+}
+
+/// Line-by-line scan of at most `max_bytes`, optionally restricted to the given (sorted) line numbers
+fn scan_lines(
+    file_path: &Path,
+    line_matcher: &LineMatcher,
+    running: &AtomicBool,
+    only_lines: Option<&[usize]>,
+    max_bytes: u64,
+) -> Result<ScanOutcome, Box<dyn std::error::Error>> {
+    // A gzipped file is scanned as the file it holds
+    let gunzipped_name = archive::gunzipped_name(file_path);
+
+    // --in needs to know the language, files ff can't tokenize have nothing in scope
+    let mut scope = match line_matcher.scope {
+        Some(scope) => match ScopeTracker::for_file(gunzipped_name.unwrap_or(file_path), scope) {
+            Some(tracker) => Some(tracker),
+            None => return Ok(ScanOutcome { matches: Matches::default(), bytes_read: 0, truncated: false }),
+        },
+        None => None,
+    };
+    if let Some(extract) = extract::extractor_for(file_path) {
+        return scan_sections(file_path, extract, line_matcher, only_lines, max_bytes);
+    }
+
     let file = File::open(file_path)?;
-    let reader = BufReader::new(file);
-    let mut matches = Vec::new();
-
-    let pattern_lower = pattern.to_lowercase();
-
-    for (line_num, line_result) in reader.lines().enumerate() {
-        let line = line_result?;
-        let line_lower = line.to_lowercase();
-
-        let found = match match_mode {
-            MatchMode::Exact => line_lower.contains(&pattern_lower),
-            MatchMode::Fuzzy => {
-                // Simple fuzzy: exact match OR word boundary match
-                line_lower.contains(&pattern_lower) ||
-                    fuzzy_matcher::skim::SkimMatcherV2::default()
-                        .fuzzy_match(&line, pattern).is_some()
+    // The byte budget counts decompressed bytes, which is what the scan works through
+    let source: Box<dyn Read> = match gunzipped_name {
+        Some(_) => Box::new(flate2::read::MultiGzDecoder::new(BufReader::new(file))),
+        None => Box::new(file),
+    };
+    let mut reader = BufReader::with_capacity(BINARY_PROBE_BYTES, source);
+    let mut matches = Matches::new(line_matcher.count_only);
+
+    // UTF-16 and legacy 8-bit text is transcoded to UTF-8 on the fly. Binary files (NUL in
+    // the first block) can't contain a line worth showing.
+    let mut reader: Box<dyn BufRead> = match encoding::sniff(reader.fill_buf()?) {
+        TextEncoding::Utf8 => Box::new(reader),
+        TextEncoding::Decoded(encoding) => Box::new(BufReader::new(encoding::decoder(reader, encoding))),
+        TextEncoding::Binary => return Ok(ScanOutcome { matches, bytes_read: 0, truncated: false }),
+    };
+    if line_matcher.multiline {
+        return scan_whole(reader, line_matcher, scope, only_lines, max_bytes);
+    }
+    let mut bytes_since_check = 0;
+
+    // Reading through `take` bounds even a single enormous line by the budget, which counts
+    // the UTF-8 bytes lines are matched in
+    let mut limited = (&mut reader).take(max_bytes);
+    for (line_num, line_result) in (&mut limited).lines().enumerate() {
+        let line = match line_result {
+            Ok(line) => line,
+            // The budget can cut a multi-byte character in half, that's the end of the scan, not an error
+            Err(e) if e.kind() == io::ErrorKind::InvalidData && limited.limit() == 0 => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        bytes_since_check += line.len() + 1;
+        if bytes_since_check >= CANCEL_CHECK_BYTES {
+            if !running.load(Ordering::Relaxed) {
+                break;
             }
+            bytes_since_check = 0;
+        }
+
+        if let Some(ref mut tracker) = scope {
+            tracker.advance(&line);
+        }
+        if only_lines.is_some_and(|lines| lines.binary_search(&(line_num + 1)).is_err()) {
+            continue;
+        }
+        line_matcher.find_in(&line, line_num + 1, None, scope.as_ref(), &mut matches);
+    }
+
+    let bytes_read = max_bytes - limited.limit();
+    let truncated = limited.limit() == 0 && !reader.fill_buf()?.is_empty();
+    Ok(ScanOutcome { matches, bytes_read, truncated })
+}
+
+/// --multiline scan: up to `max_bytes` read into memory and matched as one text
+fn scan_whole(
+    mut reader: impl BufRead,
+    line_matcher: &LineMatcher,
+    scope: Option<ScopeTracker>,
+    only_lines: Option<&[usize]>,
+    max_bytes: u64,
+) -> Result<ScanOutcome, Box<dyn std::error::Error>> {
+    let mut bytes = Vec::new();
+    (&mut reader).take(max_bytes).read_to_end(&mut bytes)?;
+    let truncated = bytes.len() as u64 == max_bytes && !reader.fill_buf()?.is_empty();
+    let bytes_read = bytes.len() as u64;
+
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        // The budget can cut a multi-byte character in half, the text just ends before it
+        Err(e) if truncated && e.utf8_error().error_len().is_none() => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid);
+            String::from_utf8(bytes)?
+        }
+        Err(_) => return Err(io::Error::from(io::ErrorKind::InvalidData).into()),
+    };
+
+    let mut matches = Matches::new(line_matcher.count_only);
+    line_matcher.find_across(&text, scope, only_lines, &mut matches);
+    Ok(ScanOutcome { matches, bytes_read, truncated })
+}
+
+/// Scan text that is already in memory, such as a file read out of an archive. `name` tells --in the language.
+fn scan_text(name: &Path, text: &str, line_matcher: &LineMatcher) -> ScanOutcome {
+    let mut scope = match line_matcher.scope {
+        Some(scope) => match ScopeTracker::for_file(name, scope) {
+            Some(tracker) => Some(tracker),
+            None => return ScanOutcome { matches: Matches::default(), bytes_read: 0, truncated: false },
+        },
+        None => None,
+    };
+
+    let mut matches = Matches::new(line_matcher.count_only);
+    if line_matcher.multiline {
+        line_matcher.find_across(text, scope, None, &mut matches);
+        return ScanOutcome { matches, bytes_read: text.len() as u64, truncated: false };
+    }
+    for (line_num, line) in text.lines().enumerate() {
+        if let Some(ref mut tracker) = scope {
+            tracker.advance(line);
+        }
+        line_matcher.find_in(line, line_num + 1, None, scope.as_ref(), &mut matches);
+    }
+    ScanOutcome { matches, bytes_read: text.len() as u64, truncated: false }
+}
+
+/// Scan the text an extractor pulls out of a structured file. Lines are numbered through
+/// all sections, so the cache can point at them, and shown as "cell 12, line 3".
+fn scan_sections(
+    file_path: &Path,
+    extract: extract::Extractor,
+    line_matcher: &LineMatcher,
+    only_lines: Option<&[usize]>,
+    max_bytes: u64,
+) -> Result<ScanOutcome, Box<dyn std::error::Error>> {
+    let size = std::fs::metadata(file_path)?.len();
+    if size > max_bytes {
+        // A cut-off document can't be parsed, so it isn't searched at all
+        return Ok(ScanOutcome { matches: Matches::default(), bytes_read: 0, truncated: true });
+    }
+
+    let mut matches = Matches::new(line_matcher.count_only);
+    let mut line_number = 0;
+    for section in extract(file_path)? {
+        for (section_line, line) in section.text.lines().enumerate() {
+            line_number += 1;
+            if only_lines.is_some_and(|lines| lines.binary_search(&line_number).is_err()) {
+                continue;
+            }
+            let location = format!("{}, {} {}", section.label, section.line_name, section_line + 1);
+            line_matcher.find_in(line, line_number, Some(&location), None, &mut matches);
+        }
+    }
+    Ok(ScanOutcome { matches, bytes_read: size, truncated: false })
+}
+
+/// One content pattern, prepared for matching line after line
+struct LineMatcher<'a> {
+    pattern: &'a str,
+    match_mode: &'a MatchMode,
+    case_sensitive: bool,
+    scope: Option<Scope>,
+    regex: Option<Regex>,
+    literal: Option<Regex>,  // Case-insensitive `pattern`, escaped
+    multiline: bool,
+    count_only: bool,
+    set: Option<PatternSet>,
+}
+
+/// Literal patterns found together in one pass over each line, for repeated --content flags
+struct PatternSet {
+    automaton: AhoCorasick,
+    patterns: Vec<Arc<str>>,  // As typed, to tag each match with the one that hit
+}
+
+impl<'a> LineMatcher<'a> {
+    fn new(
+        pattern: &'a str,
+        match_mode: &'a MatchMode,
+        case_sensitive: bool,
+        scope: Option<Scope>,
+        multiline: bool,
+        count_only: bool,
+        compiled: Option<Regex>,  // The regex for `pattern` from `PreparedQuery`, otherwise compiled here
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let regex = match match_mode {
+            MatchMode::Regex if compiled.is_some() => compiled,
+            // Over a whole file, ^ and $ still have to mean the start and end of a line
+            MatchMode::Regex if multiline => Some(cached_regex(&format!("(?m){}", pattern), case_sensitive).ok_or("invalid regex")?),
+            MatchMode::Regex => Some(cached_regex(pattern, case_sensitive).ok_or("invalid regex")?),
+            _ => None,
+        };
+        // Lowercasing a line can change its byte length ('İ' becomes two characters), so a
+        // case-insensitive literal is found by a regex over the line as it is
+        let literal = match match_mode {
+            MatchMode::Exact | MatchMode::Fuzzy if !case_sensitive => {
+                Some(cached_regex(&regex::escape(pattern), false).ok_or("invalid pattern")?)
+            }
+            _ => None,
         };
+        Ok(Self {
+            pattern,
+            match_mode,
+            case_sensitive,
+            scope,
+            regex,
+            literal,
+            multiline,
+            count_only,
+            set: None,
+        })
+    }
+
+    /// Matcher for all of `patterns` at once. `key` is the patterns joined, it stands in for the
+    /// single pattern in cache keys. Fuzzy content matching only ever marks substrings, so
+    /// literal matching covers both non-regex modes.
+    fn any_of(patterns: &'a [String], key: &'a str, options: &'a SearchOptions) -> Result<Self, Box<dyn std::error::Error>> {
+        let case_sensitive = options.case_mode.sensitive_for(key);
+        let mut line_matcher = Self::new(key, &options.match_mode, case_sensitive, options.content_scope, false, options.count_only, None)?;
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostLongest)
+            .ascii_case_insensitive(!case_sensitive)
+            .build(patterns.iter().map(|pattern| fold_case(pattern, case_sensitive).into_owned()))?;
+        line_matcher.set = Some(PatternSet {
+            automaton,
+            patterns: patterns.iter().map(|pattern| Arc::from(pattern.as_str())).collect(),
+        });
+        Ok(line_matcher)
+    }
+
+    /// Content-cache key. Keys without case or scope keep the old format so existing entries stay valid.
+    fn cache_key(&self) -> String {
+        let scope = self.scope.map(|scope| format!("+in:{}", scope.name())).unwrap_or_default();
+        let multiline = if self.multiline { "+multiline" } else { "" };
+        let any = if self.set.is_some() { "+any" } else { "" };
+        format!("{:?}{}{}{}{}:{}", self.match_mode, if self.case_sensitive { "+case" } else { "" }, scope, multiline, any, self.pattern)
+    }
+
+    /// Push every match in `line` that --in allows. `location` replaces the line number when matches are shown.
+    fn find_in(
+        &self,
+        line: &str,
+        line_number: usize,
+        location: Option<&str>,
+        scope: Option<&ScopeTracker>,
+        matches: &mut Matches,
+    ) {
+        if let Some(ref set) = self.set {
+            let pattern = |found: &aho_corasick::Match| Some(&set.patterns[found.pattern().as_usize()]);
+            // The automaton ignores ASCII case by itself. Other lines are lowercased first, which
+            // can change their byte length, so the matches are mapped back onto the line.
+            if self.case_sensitive || line.is_ascii() {
+                for found in set.automaton.find_iter(line) {
+                    push_match(line, found.range(), line_number, location, pattern(&found), scope, matches);
+                }
+            } else {
+                let (line_folded, origins) = fold_with_origins(line);
+                for found in set.automaton.find_iter(&line_folded) {
+                    let span = original_span(line, &origins, found.range());
+                    push_match(line, span, line_number, location, pattern(&found), scope, matches);
+                }
+            }
+            return;
+        }
+
+        let mut push = |start: usize, end: usize| push_match(line, start..end, line_number, location, None, scope, matches);
+
+        // Regex offsets come straight from the original line
+        if let Some(ref regex) = self.regex {
+            for found in regex.find_iter(line) {
+                push(found.start(), found.end());
+                // Patterns like `^` or `x*` match the empty string everywhere, once per line is enough
+                if found.is_empty() {
+                    break;
+                }
+            }
+            return;
+        }
+
+        // Find all occurrences in this line. Fuzzy content matching only marks substrings too.
+        let mut start = 0;
+        while start <= line.len() {
+            let span = match self.literal {
+                Some(ref literal) => literal.find_at(line, start).map(|found| found.range()),
+                None => line[start..].find(self.pattern).map(|pos| start + pos..start + pos + self.pattern.len()),
+            };
+            let Some(span) = span else { break };
+            // One character on, so overlapping occurrences count as well
+            start = span.start + line[span.start..].chars().next().map_or(1, char::len_utf8);
+            push(span.start, span.end);
+        }
+    }
+
+    /// Push every --multiline regex match in `text` that --in allows, reported at the line it
+    /// starts on and highlighted to the end of that line
+    fn find_across(
+        &self,
+        text: &str,
+        mut scope: Option<ScopeTracker>,
+        only_lines: Option<&[usize]>,
+        matches: &mut Matches,
+    ) {
+        let Some(ref regex) = self.regex else { return };
+        let mut lines = text.split_inclusive('\n');
+        let (mut line, mut line_start, mut line_number) = ("", 0, 0);
+        let mut empty_match_line = 0;
+
+        for found in regex.find_iter(text) {
+            // Walk forward to the match's first line, the tracker has to see every line on the way
+            while line_number == 0 || found.start() >= line_start + line.len() {
+                let Some(next) = lines.next() else { return };
+                line_start += line.len();
+                line = next;
+                line_number += 1;
+                if let Some(ref mut tracker) = scope {
+                    tracker.advance(without_newline(line));
+                }
+            }
+            if only_lines.is_some_and(|lines| lines.binary_search(&line_number).is_err()) {
+                continue;
+            }
+            // Patterns like `^` or `x*` match the empty string everywhere, once per line is enough
+            if found.is_empty() {
+                if empty_match_line == line_number {
+                    continue;
+                }
+                empty_match_line = line_number;
+            }
+            let shown = without_newline(line);
+            let start = (found.start() - line_start).min(shown.len());
+            let end = (found.end() - line_start).min(shown.len());
+            push_match(shown, start..end, line_number, None, None, scope.as_ref(), matches);
+        }
+    }
+}
+
+/// A line from `split_inclusive` the way `lines()` would have returned it
+fn without_newline(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// Record one match in `line` unless --in rules out where it starts. `pattern` is the one of
+/// several --content patterns that matched.
+fn push_match(
+    line: &str,
+    span: std::ops::Range<usize>,
+    line_number: usize,
+    location: Option<&str>,
+    pattern: Option<&Arc<str>>,
+    scope: Option<&ScopeTracker>,
+    matches: &mut Matches,
+) {
+    if scope.is_some_and(|tracker| !tracker.allows(span.start)) {
+        return;
+    }
+    matches.push(|| {
+        let (line_content, offset) = line_window(line, span.start, span.end);
+        ContentMatch {
+            line_number,
+            line_content,
+            match_start: span.start - offset,
+            match_end: span.end - offset,
+            location: location.map(str::to_string),
+            pattern: pattern.cloned(),
+        }
+    });
+}
+
+/// The part of a long line worth keeping around a match, and the byte offset it starts at
+fn line_window(line: &str, match_start: usize, match_end: usize) -> (String, usize) {
+    if line.len() <= MAX_STORED_LINE_BYTES {
+        return (line.to_string(), 0);
+    }
+
+    let margin = MAX_STORED_LINE_BYTES.saturating_sub(match_end - match_start) / 2;
+    let mut start = match_start.saturating_sub(margin).min(line.len());
+    let mut end = match_end.saturating_add(margin).min(line.len());
+    while !line.is_char_boundary(start) {
+        start -= 1;
+    }
+    while !line.is_char_boundary(end) {
+        end += 1;
+    }
+    (line[start..end].to_string(), start)
+}
+
+/// Short, groupable reason for a failed content read
+fn read_error_reason(error: &(dyn std::error::Error + 'static)) -> String {
+    let io_error = error.downcast_ref::<io::Error>();
+    match io_error.map(io::Error::kind) {
+        Some(io::ErrorKind::PermissionDenied) => "permission denied".to_string(),
+        // Extractors report documents they can't parse as invalid data too, with a message of their own
+        Some(io::ErrorKind::InvalidData) if io_error.is_some_and(|e| e.get_ref().is_none()) => "not valid UTF-8".to_string(),
+        Some(io::ErrorKind::NotFound) => "deleted during search".to_string(),
+        _ => error.to_string(),
+    }
+}
+
+/// Byte budgets for content scans: a cap per file and a cap for the whole search
+struct ContentBudget {
+    per_file: u64,
+    total: u64,
+    used: AtomicU64,
+    truncated_files: AtomicUsize,
+    skipped_files: AtomicUsize,
+}
+
+impl ContentBudget {
+    fn new(config: &crate::config::Config) -> Self {
+        let unlimited_if_zero = |bytes: u64| if bytes == 0 { u64::MAX } else { bytes };
+        Self {
+            per_file: unlimited_if_zero(config.max_content_bytes_per_file),
+            total: unlimited_if_zero(config.max_total_content_bytes),
+            used: AtomicU64::new(0),
+            truncated_files: AtomicUsize::new(0),
+            skipped_files: AtomicUsize::new(0),
+        }
+    }
+
+    /// Whether the search-wide budget is spent, counting the file as skipped if so
+    fn exhausted(&self) -> bool {
+        if self.used.load(Ordering::Relaxed) < self.total {
+            return false;
+        }
+        self.skipped_files.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    fn record(&self, outcome: &ScanOutcome) {
+        self.used.fetch_add(outcome.bytes_read, Ordering::Relaxed);
+        if outcome.truncated {
+            self.truncated_files.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn report(&self, warnings: &mut Warnings) {
+        warnings.content_budget(
+            self.truncated_files.load(Ordering::Relaxed),
+            self.per_file,
+            self.skipped_files.load(Ordering::Relaxed),
+            self.total,
+        );
+    }
+}
+
+/// --limit-files / --limit-matches: once enough content matches are found the rest of the files
+/// aren't scanned. Only content-only searches stop early, with a name pattern in the mix the
+/// best-scoring results could still be ahead.
+struct ResultQuota {
+    files: Option<usize>,
+    matches: Option<usize>,
+    found_files: AtomicUsize,
+    found_matches: AtomicUsize,
+}
+
+impl ResultQuota {
+    fn new(options: &SearchOptions) -> Self {
+        let stops_early = options.filename_pattern.is_none();
+        Self {
+            files: options.limit_files.filter(|_| stops_early),
+            matches: options.limit_matches.filter(|_| stops_early),
+            found_files: AtomicUsize::new(0),
+            found_matches: AtomicUsize::new(0),
+        }
+    }
+
+    /// Count a file the content query matched
+    fn record(&self, matches: &Matches) {
+        self.found_files.fetch_add(1, Ordering::Relaxed);
+        self.found_matches.fetch_add(matches.count, Ordering::Relaxed);
+    }
+
+    fn met(&self) -> bool {
+        self.files.is_some_and(|files| self.found_files.load(Ordering::Relaxed) >= files)
+            || self.matches.is_some_and(|matches| self.found_matches.load(Ordering::Relaxed) >= matches)
+    }
+
+    fn report(&self) {
+        if self.met() {
+            let reached = match self.files {
+                Some(files) if self.found_files.load(Ordering::Relaxed) >= files => format!("--limit-files {}", files),
+                _ => format!("--limit-matches {}", self.matches.unwrap_or_default()),
+            };
+            eprintln!("{} Stopped scanning once {} was reached", "🎯".green(), reached.blue());
+        }
+    }
+}
+
+/// Sorted results cut down to the first `limit` content matches, the last file keeping only
+/// the part of its matches that fits
+fn keep_first_matches(results: &mut Vec<SearchResult>, limit: Option<usize>) {
+    let Some(mut remaining) = limit else { return };
+    let mut kept = 0;
+    for result in results.iter_mut() {
+        if remaining == 0 {
+            break;
+        }
+        kept += 1;
+        if result.content_count > remaining {
+            result.content_count = remaining;
+            result.content_matches.truncate(remaining);
+        }
+        remaining -= result.content_count;
+    }
+    results.truncate(kept);
+}
+
+/// Caps how many files are content-scanned at once (--content-threads), so walking and
+/// scoring can use every thread while disk reads stay bounded
+struct ScanPermits {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+struct ScanPermit<'a>(&'a ScanPermits);
+
+impl ScanPermits {
+    fn new(permits: usize) -> Self {
+        Self { available: Mutex::new(permits), released: Condvar::new() }
+    }
+
+    fn acquire(&self) -> ScanPermit<'_> {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.released.wait(available).unwrap();
+        }
+        *available -= 1;
+        ScanPermit(self)
+    }
+}
+
+impl Drop for ScanPermit<'_> {
+    fn drop(&mut self) {
+        *self.0.available.lock().unwrap() += 1;
+        self.0.released.notify_one();
+    }
+}
+
+/// Whether an entry matches and its score. Hybrid searches take either, a content hit adds to the name score.
+fn match_score(search_type: &SearchType, filename_score: Option<i64>, content_hit: bool) -> (bool, i64) {
+    match search_type {
+        SearchType::FileName => (filename_score.is_some(), filename_score.unwrap_or(0)),
+        SearchType::Content => (content_hit, if content_hit { 100 } else { 0 }),
+        SearchType::Hybrid => {
+            let has_filename = filename_score.is_some();
+            let has_content = content_hit;
+            let score = filename_score.unwrap_or(0) + if has_content { 50 } else { 0 };
+            (has_filename || has_content, score)
+        }
+    }
+}
+
+/// Search the archives a walk collected for --archives. Returns the matching entries, and the
+/// archives that couldn't be read with the reason.
+fn search_archives(
+    archives: Vec<PathBuf>,
+    search_path: &Path,
+    options: &SearchOptions,
+    config: &crate::config::Config,
+    budget: &ContentBudget,
+    quota: &ResultQuota,
+    running: &AtomicBool,
+) -> (Vec<SearchResult>, Vec<(PathBuf, String)>) {
+    let searched: Vec<_> = archives
+        .into_par_iter()
+        .filter(|_| running.load(Ordering::Relaxed) && !quota.met())
+        .map(|archive_path| {
+            let found = search_archive(&archive_path, search_path, options, config, budget, quota);
+            (archive_path, found)
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    let mut unreadable = Vec::new();
+    for (archive_path, found) in searched {
+        match found {
+            Ok(found) => results.extend(found),
+            Err(e) => unreadable.push((archive_path, read_error_reason(&e))),
+        }
+    }
+    (results, unreadable)
+}
 
-        if found {
-            // Find all occurrences in this line
-            let mut start = 0;
-            while let Some(pos) = line_lower[start..].find(&pattern_lower) {
-                let actual_pos = start + pos;
-                matches.push(ContentMatch {
-                    line_number: line_num + 1,
-                    line_content: line.clone(),
-                    match_start: actual_pos,
-                    match_end: actual_pos + pattern.len(),
-                });
-                start = actual_pos + 1;
+/// --archives: match the entries of one archive the walk came across. Results keep the archive
+/// as their path and name the entry separately, shown as `logs.zip!/app/today.log`.
+fn search_archive(
+    archive_path: &Path,
+    search_path: &Path,
+    options: &SearchOptions,
+    config: &crate::config::Config,
+    budget: &ContentBudget,
+    quota: &ResultQuota,
+) -> io::Result<Vec<SearchResult>> {
+    let filename_pattern = options.filename_pattern.as_deref();
+    let name_pattern = options.name_pattern();
+    let content_query = parse_content_query(options);
+    let search_type = match (filename_pattern, &content_query) {
+        (Some(_), Some(_)) => SearchType::Hybrid,
+        (None, Some(_)) => SearchType::Content,
+        _ => SearchType::FileName,
+    };
+    let name_case = filename_pattern.is_some_and(|pattern| options.case_mode.sensitive_for(pattern));
+    let matcher = name_matcher(name_case);
+    let name_floor = filename_pattern.and_then(|pattern| fuzzy_score_floor(pattern, options.min_score));
+    let mut results = Vec::new();
+
+    let wants_content = |entry: &archive::ArchiveEntry| {
+        content_query.is_some()
+            && !entry.is_dir
+            && config.is_content_searchable(Path::new(&entry.name))
+            && !budget.exhausted()
+            && !quota.met()
+    };
+
+    archive::for_each_entry(archive_path, budget.per_file, wants_content, |entry, content| {
+        if options.dirs_only && !entry.is_dir { return; }
+        if options.files_only && entry.is_dir { return; }
+        // Joined onto the archive so extension filters and --full-path see the entry's own name
+        let entry_path = archive_path.join(&entry.name);
+        if !options.filters.matches_stats(&entry_path, entry.is_dir, || Some((entry.size, entry.modified))) { return; }
+        let Some(file_name) = entry_path.file_name().map(|n| n.to_string_lossy()) else { return };
+
+        let filename_match = name_pattern.as_deref().and_then(|pattern| {
+            let target = name_target(&file_name, &entry_path, search_path, pattern, options);
+            keep_confident(get_best_match_score(&target, pattern, &matcher, &options.match_mode, name_case), name_floor)
+        });
+
+        let mut content_hit = false;
+        let mut content_matches = Matches::default();
+        let mut content_truncated = false;
+        if let (Some(query), Some(content)) = (&content_query, content)
+            && let Some(text) = encoding::decode(&content)
+        {
+            let scanned = evaluate_content_query(query, options, |line_matcher| {
+                Ok(scan_text(Path::new(&entry.name), &text, line_matcher))
+            });
+            if let Ok((hit, mut outcome)) = scanned {
+                outcome.truncated = entry.size > budget.per_file;
+                budget.record(&outcome);
+                if hit {
+                    quota.record(&outcome.matches);
+                }
+                content_hit = hit;
+                content_truncated = outcome.truncated;
+                content_matches = outcome.matches;
             }
         }
+
+        let filename_score = filename_match.map(|(score, _)| score);
+        let (is_match, score) = match_score(&search_type, filename_score, content_hit);
+        if !is_match {
+            return;
+        }
+        let show_details = options.show_details || config.output_options.show_details;
+        results.push(SearchResult {
+            path: archive_path.to_path_buf(),
+            archive_entry: Some(entry.name),
+            score,
+            is_dir: entry.is_dir,
+            size: Some(entry.size).filter(|_| show_details && !entry.is_dir),
+            disk_size: None,
+            modified: entry.modified.filter(|_| show_details),
+            name_match: filename_match.map(|(_, kind)| kind),
+            name_floor,
+            content_matches: content_matches.found,
+            content_count: content_matches.count,
+            content_truncated,
+            peek: None,
+            search_type: search_type.clone(),
+        });
+    })?;
+    Ok(results)
+}
+
+/// Best results first: highest score, or with --count the most content matches
+fn result_order(options: &SearchOptions) -> impl Fn(&SearchResult, &SearchResult) -> std::cmp::Ordering + Sync {
+    let count_only = options.count_only;
+    move |a, b| {
+        let by_count = if count_only { b.content_count.cmp(&a.content_count) } else { std::cmp::Ordering::Equal };
+        by_count.then(b.score.cmp(&a.score))
+    }
+}
+
+/// Traversal rules for this search, shared by whichever walker backend runs it
+pub fn entry_filter(
+    search_path: &Path,
+    options: &SearchOptions,
+    config: &crate::config::Config,
+    running: Arc<AtomicBool>,
+) -> Arc<EntryFilter> {
+    Arc::new(EntryFilter {
+        root: search_path.to_path_buf(),
+        include_hidden: options.include_hidden || config.include_hidden,  // Check both CLI and config
+        follow_symlinks: options.follow_symlinks,
+        ignore_rules: options.ignore_rules.clone(),
+        ignore_files: options.ignore_rules.ignore_files_respected().then(|| IgnoreFiles::new(search_path)),
+        only_dirs: options.only_dirs.clone(),
+        min_depth: options.min_depth,
+        max_depth: options.max_depth,
+        running,
+        // walkdir opens a directory before yielding it, so a probe there would come after any stall
+        // and only read the directory twice. The ignore walker filters entries before opening them.
+        stall_guard: options.stall_timeout.filter(|_| options.backend == Backend::Ignore).map(StallGuard::new),
+        dir_entry_cap: options.dir_entry_cap,
+        dir_entries: Mutex::new(HashMap::new()),
+        capped_dirs: Mutex::new(Vec::new()),
+        skip_reparse: options.skip_reparse,
+        skipped_reparse: Mutex::new(Vec::new()),
+        skipped_build_dirs: Mutex::new(Vec::new()),
+        skipped_caches: Mutex::new(Vec::new()),
+        skipped_ignored: Mutex::new(Vec::new()),
+        git_files: options.git_files.clone(),
+        visited_dirs: Mutex::new(HashSet::new()),
+        skipped_loops: Mutex::new(Vec::new()),
+    })
+}
+
+/// First entries of a directory result, for --peek
+#[derive(Debug)]
+pub struct DirPeek {
+    pub entries: Vec<(String, bool)>,  // Name and whether it is a directory, directories first
+    pub total: usize,  // Entries the walk would have visited, shown or not
+}
+
+/// --peek: list the first `count` entries of each directory result, leaving out what the walk
+/// itself skips (hidden entries, ignore rules and ignore files)
+pub fn peek_dirs(results: &mut [SearchResult], search_path: &Path, options: &SearchOptions, config: &crate::config::Config, count: usize) {
+    let filter = entry_filter(search_path, options, config, Arc::new(AtomicBool::new(true)));
+    for result in results.iter_mut().filter(|result| result.is_dir && result.archive_entry.is_none()) {
+        let Ok(read) = std::fs::read_dir(&result.path) else { continue };
+        let depth = result.path.strip_prefix(search_path).map_or(0, |relative| relative.components().count()) + 1;
+        let mut entries: Vec<(String, bool)> = read
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+                filter
+                    .allows(&entry.path(), depth, is_dir, || entry.metadata().ok())
+                    .then(|| (entry.file_name().to_string_lossy().into_owned(), is_dir))
+            })
+            .collect();
+        entries.sort_by_cached_key(|(name, is_dir)| (!is_dir, name.to_lowercase()));
+        let total = entries.len();
+        entries.truncate(count);
+        result.peek = Some(DirPeek { entries, total });
     }
+}
 
-    Ok(matches)
+/// Ctrl+C flag for a search. ctrlc only accepts one handler per process, so it is installed
+/// once and the flag is re-armed for every search (narrowing re-runs the search in place).
+fn cancellation_flag() -> Arc<AtomicBool> {
+    static RUNNING: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+    let running = RUNNING.get_or_init(|| {
+        let running = Arc::new(AtomicBool::new(true));
+        let r = running.clone();
+        ctrlc::set_handler(move || {
+            eprintln!("\n🛑 Search cancelled by user");
+            r.store(false, Ordering::SeqCst);
+        }).expect("Error setting Ctrl-C handler");
+        running
+    });
+    running.store(true, Ordering::SeqCst);
+    running.clone()
+}
+
+/// Persistent content cache for this search, None for filename-only searches or when disabled
+fn content_cache(options: &SearchOptions) -> Option<Mutex<ContentCache>> {
+    (options.content_pattern.is_some() && options.content_cache_entries > 0)
+        .then(|| Mutex::new(ContentCache::load(options.content_cache_entries)))
 }
 
 pub fn search_files(
     search_path: &Path,
-    filename_pattern: Option<&str>,
-    content_pattern: Option<&str>,
-    include_hidden: bool,
-    dirs_only: bool,
-    files_only: bool,
-    limit: usize,
-    show_details: bool,
-    match_mode: &MatchMode,
+    options: &SearchOptions,
     config: &crate::config::Config,
+    warnings: &mut Warnings,
 ) -> Vec<SearchResult> {
-    let matcher = fuzzy_matcher::skim::SkimMatcherV2::default();
+    let filename_pattern = options.filename_pattern.as_deref();
+    let name_pattern = options.name_pattern();
+    let content_pattern = options.content_pattern.as_deref();
+    let match_mode = &options.match_mode;
+    let name_case = filename_pattern.is_some_and(|pattern| options.case_mode.sensitive_for(pattern));
+    let content_query = parse_content_query(options);
+    let matcher = name_matcher(name_case);
+    let name_floor = filename_pattern.and_then(|pattern| fuzzy_score_floor(pattern, options.min_score));
     let mut results = Vec::new();
     let mut files_scanned = 0;
     let mut dirs_scanned = 0;
+    let mut placeholders_skipped = 0;
+    let mut unreadable = Vec::new();
+    let mut archives = Vec::new();
+    let budget = ContentBudget::new(config);
+    let quota = ResultQuota::new(options);
+    let mut entries_visited = 0;
+    let mut limits = SearchLimits::new(options.max_files, options.time_limit, options.strict_time_limit);
     let mut last_update = std::time::Instant::now();
 
     // Determine search type
@@ -87,62 +1153,36 @@ pub fn search_files(
     };
 
     // Set up Ctrl+C handler (your existing code)
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-    ctrlc::set_handler(move || {
-        println!("\n🛑 Search cancelled by user");
-        r.store(false, Ordering::SeqCst);
-    }).expect("Error setting Ctrl-C handler");
-
-    println!("{} Searching in: {}", "🔍".yellow(), search_path.display().to_string().cyan());
-    println!(" Search type: {} | Press {} to cancel",
-             format!("{:?}", search_type).blue(), "Ctrl+C".red());
+    let running = cancellation_flag();
 
-    let walker = WalkDir::new(search_path)
-        .follow_links(config.follow_symlinks)
-        .into_iter()
-        .filter_entry(|e| {
-            if !running.load(Ordering::SeqCst) {
-                return false;
-            }
-            let effective_hidden = include_hidden || config.include_hidden;
-            if !effective_hidden {  // Check both CLI and config
-                if let Some(name) = e.file_name().to_str() {
-                    if name.starts_with('.') && name.len() > 1 {
-                        return false;
-                    }
-                }
-            }
-
-            //Use config ignore directories
-            if let Some(name) = e.file_name().to_str() {
-                if config.should_ignore_directory(name) {
-                    return false;
-                }
-
-                // 🆕 Use config ignore file patterns
-                if config.should_ignore_file(name) {
-                    return false;
-                }
-            }
-
-            if let Ok(metadata) = e.metadata() {
-                if metadata.is_file() && metadata.len() > config.max_file_size_mb * 1024 * 1024 {
-                    return false;
-                }
-            }
+    eprintln!("{} Searching in: {}", "🔍".yellow(), search_path.display().to_string().cyan());
+    eprintln!(" Search type: {} | Press {} to cancel",
+             format!("{:?}", search_type).blue(), "Ctrl+C".red());
 
-            true
-        });
+    let filter = entry_filter(search_path, options, config, running.clone());
+    let walker = walker::for_backend(options.backend).walk(filter.clone());
+    let cache = content_cache(options);
 
     for entry in walker {
         if !running.load(Ordering::SeqCst) {
-            println!("{} Search stopped", "🛑".red());
+            eprintln!("{} Search stopped", "🛑".red());
             break;
         }
 
         match entry {
             Ok(entry) => {
+                // Skip large files based on config
+                let max_file_size = config.max_file_size_mb * 1024 * 1024;
+                if entry.metadata().is_ok_and(|m| m.is_file() && m.len() > max_file_size) {
+                    continue;
+                }
+
+                // Entry cap and time limit, same checks as parallel mode
+                if limits.check(entries_visited) == LimitDecision::Stop || quota.met() {
+                    break;
+                }
+                entries_visited += 1;
+
                 let path = entry.path();
                 let is_dir = path.is_dir();
 
@@ -153,8 +1193,14 @@ pub fn search_files(
                     files_scanned += 1;
                 }
 
-                if dirs_only && !is_dir { continue; }
-                if files_only && is_dir { continue; }
+                // Entries are matched after the walk, whether or not the archive itself passes the filters
+                if options.archives && !is_dir && archive::is_archive(path) {
+                    archives.push(path.to_path_buf());
+                }
+
+                if options.dirs_only && !is_dir { continue; }
+                if options.files_only && is_dir { continue; }
+                if !options.filters.matches(path, is_dir, || entry.metadata().ok()) { continue; }
 
                 // Progress update (existing code)
                 if last_update.elapsed().as_secs() >= 1 {
@@ -162,68 +1208,82 @@ pub fn search_files(
                             "📁".yellow(), files_scanned, dirs_scanned,
                             "(Ctrl+C to cancel)".dimmed()
                     );
-                    io::stdout().flush().unwrap();
+                    io::stderr().flush().unwrap();
                     last_update = std::time::Instant::now();
                 }
 
-                if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                    let mut filename_score = None;
-                    let mut content_matches = Vec::new();
+                // Names that aren't UTF-8 are matched lossily, the result keeps the raw path
+                if let Some(file_name) = path.file_name().map(|n| n.to_string_lossy()) {
+                    let mut filename_match = None;
+                    let mut content_matches = Matches::default();
+                    let mut content_hit = false;
+                    let mut content_truncated = false;
 
                     // Check filename match
-                    if let Some(pattern) = filename_pattern {
-                        filename_score = get_best_match_score(file_name, pattern, &matcher, match_mode);
+                    if let Some(pattern) = name_pattern.as_deref() {
+                        let target = name_target(&file_name, path, search_path, pattern, options);
+                        filename_match = keep_confident(get_best_match_score(&target, pattern, &matcher, match_mode, name_case), name_floor);
                     }
+                    let filename_score = filename_match.map(|(score, _)| score);
 
                     // Check content match (only for files, not directories)
-                    if let Some(pattern) = content_pattern {
-                        if !is_dir && config.is_content_searchable(&path) {
-                            if let Ok(matches) = search_file_content(path, pattern, match_mode) {
-                                if !matches.is_empty() {
-                                    content_matches = matches;
+                    if let Some(ref query) = content_query
+                        && !is_dir && !entry.is_special() && config.is_content_searchable(path)
+                    {
+                        // Reading a cloud-only file would download it, skip unless --hydrate
+                        if !options.hydrate && util::is_cloud_placeholder(path) {
+                            placeholders_skipped += 1;
+                        } else if !budget.exhausted() {
+                            match search_content_query(path, query, options, &running, cache.as_ref(), budget.per_file) {
+                                Ok((hit, outcome)) => {
+                                    budget.record(&outcome);
+                                    if hit {
+                                        quota.record(&outcome.matches);
+                                    }
+                                    content_hit = hit;
+                                    content_truncated = outcome.truncated;
+                                    content_matches = outcome.matches;
                                 }
+                                Err(e) => unreadable.push((path.to_path_buf(), read_error_reason(e.as_ref()))),
                             }
                         }
                     }
 
                     // Determine if this is a match and calculate score
-                    let (is_match, final_score) = match search_type {
-                        SearchType::FileName => (filename_score.is_some(), filename_score.unwrap_or(0)),
-                        SearchType::Content => (!content_matches.is_empty(), if !content_matches.is_empty() { 100 } else { 0 }),
-                        SearchType::Hybrid => {
-                            let has_filename = filename_score.is_some();
-                            let has_content = !content_matches.is_empty();
-                            let score = filename_score.unwrap_or(0) + if has_content { 50 } else { 0 };
-                            (has_filename || has_content, score)
-                        }
-                    };
+                    let (is_match, final_score) = match_score(&search_type, filename_score, content_hit);
 
                     if is_match {
-                        let (size, modified) = if show_details {
+                        let (size, disk_size, modified) = if options.show_details {
                             util::get_file_metadata(&entry)
                         } else {
-                            (None, None)
+                            (None, None, None)
                         };
 
                         results.push(SearchResult {
                             path: path.to_path_buf(),
+                            archive_entry: None,
                             score: final_score,
                             is_dir,
                             size,
+                            disk_size,
                             modified,
-                            content_matches,
+                            name_match: filename_match.map(|(_, kind)| kind),
+                            name_floor,
+                            content_matches: content_matches.found,
+                            content_count: content_matches.count,
+                            content_truncated,
+                            peek: None,
                             search_type: search_type.clone(),
                         });
                     }
                 }
             }
-            Err(e) => {
-                if !e.to_string().contains("Permission denied") {
-                    eprintln!("{} {}", "⚠️".yellow(), format!("Warning: {}", e).dimmed());
-                }
-            }
+            Err(e) => warnings.walk_error(&e),
         }
     }
+    let (archive_results, unreadable_archives) = search_archives(archives, search_path, options, config, &budget, &quota, &running);
+    results.extend(archive_results);
+    unreadable.extend(unreadable_archives);
 
     // Clean up and sort results (your existing code)
     if last_update.elapsed().as_millis() > 100 {
@@ -231,13 +1291,31 @@ pub fn search_files(
         eprint!("\r");
     }
     if files_scanned > 0 || dirs_scanned > 0 {
-        println!("{} Scanned {} files and {} directories total",
+        eprintln!("{} Scanned {} files and {} directories total",
                  "📊".green(), files_scanned, dirs_scanned);
     }
+    warnings.abandoned_dirs(&filter.abandoned_dirs());
+    warnings.reparse_points(&filter.skipped_reparse_points());
+    warnings.capped_dirs(&filter.capped_dirs(), options.dir_entry_cap);
+    warnings.cache_dirs(&filter.skipped_caches());
+    warnings.ignored_entries(&filter.skipped_ignored());
+    warnings.build_dirs(&filter.skipped_build_dirs());
+    warnings.symlink_loops(&filter.skipped_loops());
+    warnings.skipped_placeholders(placeholders_skipped);
+    warnings.unreadable_files(&mut unreadable);
+    budget.report(warnings);
+    quota.report();
+    if let Some(cache) = cache {
+        cache.into_inner().unwrap().save();
+    }
+    if limits.cap_reached() {
+        warnings.file_cap(entries_visited);
+    }
 
     if running.load(Ordering::SeqCst) {
-        results.sort_by(|a, b| b.score.cmp(&a.score));
-        results.truncate(limit);
+        results.sort_by(result_order(options));
+        results.truncate(options.limit);
+        keep_first_matches(&mut results, options.limit_matches);
     }
 
     results
@@ -246,27 +1324,30 @@ pub fn search_files(
 
 pub fn search_files_parallel(
     search_path: &Path,
-    filename_pattern: Option<&str>,
-    content_pattern: Option<&str>,
-    include_hidden: bool,
-    dirs_only: bool,
-    files_only: bool,
-    limit: usize,
-    show_details: bool,
-    match_mode: &MatchMode,
-    threads: usize,
+    options: &SearchOptions,
     config: &crate::config::Config,
+    warnings: &mut Warnings,
 ) -> Vec<SearchResult> {
-    let matcher = fuzzy_matcher::skim::SkimMatcherV2::default();
+    let filename_pattern = options.filename_pattern.as_deref();
+    let name_pattern = options.name_pattern();
+    let content_pattern = options.content_pattern.as_deref();
+    let match_mode = &options.match_mode;
+    let name_case = filename_pattern.is_some_and(|pattern| options.case_mode.sensitive_for(pattern));
+    let content_query = parse_content_query(options);
+    let matcher = name_matcher(name_case);
+    let name_floor = filename_pattern.and_then(|pattern| fuzzy_score_floor(pattern, options.min_score));
     let cpu_cores = num_cpus::get();
 
-    println!("{} Searching in: {} {}",
+    eprintln!("{} Searching in: {} {}",
              "🔍".yellow(),
              search_path.display().to_string().cyan(),
              format!("(Parallel Mode - {} cores)", cpu_cores).green()
     );
-    println!("   Using {} threads on {} CPU cores", threads, cpu_cores);
-    println!("   Match mode: {} | Press Ctrl+C to cancel", format!("{:?}", match_mode).blue());
+    eprintln!("   Using {} threads on {} CPU cores", options.threads, cpu_cores);
+    if let Some(limit) = options.content_threads.filter(|_| content_pattern.is_some()) {
+        eprintln!("   Content scans limited to {} at a time", limit);
+    }
+    eprintln!("   Match mode: {} | Press Ctrl+C to cancel", format!("{:?}", match_mode).blue());
 
     // Determine and display search type
     let search_type = match (filename_pattern, content_pattern) {
@@ -275,65 +1356,69 @@ pub fn search_files_parallel(
         (None, Some(_)) => SearchType::Content,
         (None, None) => SearchType::FileName, // fallback
     };
-    println!("   Search type: {}", format!("{:?}", search_type).blue());
+    eprintln!("   Search type: {}", format!("{:?}", search_type).blue());
 
     // Add Ctrl+C handling for parallel mode
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-
-    ctrlc::set_handler(move || {
-        println!("\n🛑 Search cancelled by user (parallel mode)");
-        r.store(false, Ordering::SeqCst);
-    }).expect("Error setting Ctrl-C handler");
+    let running = cancellation_flag();
 
     // Collect all paths first
-    let all_paths: Vec<PathBuf> = WalkDir::new(search_path)
-        .follow_links(config.follow_symlinks)
-        .into_iter()
-        .filter_entry(|e| {
-            let effective_hidden = include_hidden || config.include_hidden;
-            if !effective_hidden {  //Check both CLI and config
-                if let Some(name) = e.file_name().to_str() {
-                    if name.starts_with('.') && name.len() > 1 {
-                        return false;
-                    }
-                }
+    let filter = entry_filter(search_path, options, config, running.clone());
+    let mut limits = SearchLimits::new(options.max_files, options.time_limit, options.strict_time_limit);
+    let mut all_paths: Vec<(PathBuf, u64, bool)> = Vec::new();
+    let mut path_list_bytes = 0u64;
+    for entry in walker::for_backend(options.backend).walk(filter.clone()) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warnings.walk_error(&e);
+                continue;
             }
-            if let Some(name) = e.file_name().to_str() {
-                if config.should_ignore_directory(name) {
-                    return false;
-                }
+        };
+        // Skip large files based on config, keep the size for scheduling
+        let size = match entry.metadata() {
+            Ok(metadata) if metadata.is_file() => metadata.len(),
+            _ => 0,
+        };
+        if size > config.max_file_size_mb * 1024 * 1024 {
+            continue;
+        }
 
-                //  Use config ignore file patterns
-                if config.should_ignore_file(name) {
-                    return false;
-                }
-            }
+        if limits.check(all_paths.len()) == LimitDecision::Stop {
+            break;
+        }
+        // The whole list is held until matching starts, so it is what grows with the size of the tree
+        path_list_bytes += (entry.path().as_os_str().len() + std::mem::size_of::<(PathBuf, u64, bool)>()) as u64;
+        if let Some(budget) = options.memory_budget.filter(|&budget| path_list_bytes > budget) {
+            warnings.memory_budget(all_paths.len(), budget);
+            break;
+        }
+        let special = entry.is_special();
+        all_paths.push((entry.into_path(), size, special));
+    }
+    let cap_reached = limits.cap_reached();
 
-            true
+    // Content scans cost roughly their file size, so hand out small files first.
+    // Workers pull from this ordered queue (par_bridge) instead of splitting the
+    // list up front, which keeps early results and the progress counter moving.
+    if content_pattern.is_some() {
+        all_paths.sort_by_key(|(_, size, _)| *size);
+    }
 
-        })
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| {
-            // Skip large files based on config
-            if let Ok(metadata) = entry.metadata() {
-                if metadata.is_file() && metadata.len() > config.max_file_size_mb * 1024 * 1024 {
-                    return false;
-                }
-            }
-            true
-        })
-        .map(|entry| entry.path().to_path_buf())
-        .take(config.max_files_per_search)  //  Use config limit
-        .collect();
+    warnings.abandoned_dirs(&filter.abandoned_dirs());
+    warnings.reparse_points(&filter.skipped_reparse_points());
+    warnings.capped_dirs(&filter.capped_dirs(), options.dir_entry_cap);
+    warnings.cache_dirs(&filter.skipped_caches());
+    warnings.ignored_entries(&filter.skipped_ignored());
+    warnings.build_dirs(&filter.skipped_build_dirs());
+    warnings.symlink_loops(&filter.skipped_loops());
 
-    println!("🚀 Processing {} paths using {} CPU cores",
+    eprintln!("🚀 Processing {} paths using {} CPU cores",
              all_paths.len(), cpu_cores);
 
     let total_paths = all_paths.len();
 
-    if total_paths >= config.max_files_per_search {
-        println!("⚠️  Limited to {} files per config setting", config.max_files_per_search);
+    if cap_reached {
+        warnings.file_cap(total_paths);
     }
 
     // Atomic counters for progress tracking
@@ -342,6 +1427,13 @@ pub fn search_files_parallel(
     let files_scanned = Arc::new(AtomicUsize::new(0));
     let dirs_scanned = Arc::new(AtomicUsize::new(0));
     let processing_complete = Arc::new(AtomicBool::new(false));
+    let placeholders_skipped = AtomicUsize::new(0);
+    let unreadable = Mutex::new(Vec::new());
+    let archives = Mutex::new(Vec::new());
+    let scan_permits = options.content_threads.map(ScanPermits::new);
+    let budget = ContentBudget::new(config);
+    let quota = ResultQuota::new(options);
+    let cache = content_cache(options);
 
     // Progress display thread with cancellation check
     let files_p = files_processed.clone();
@@ -370,7 +1462,7 @@ pub fn search_files_parallel(
                         scanned_d,
                         "(Parallel)".green()
                 );
-                io::stdout().flush().unwrap();
+                io::stderr().flush().unwrap();
                 last_update = Instant::now();
             }
         }
@@ -383,118 +1475,159 @@ pub fn search_files_parallel(
         if running_progress.load(Ordering::Relaxed) {
             let final_files = files_s.load(Ordering::Relaxed);
             let final_dirs = dirs_s.load(Ordering::Relaxed);
-            println!("{} Scanned {} files and {} directories total (parallel processing complete)",
+            eprintln!("{} Scanned {} files and {} directories total (parallel processing complete)",
                      "📊".green(), final_files, final_dirs);
         } else {
-            println!("{} Parallel search stopped", "🛑".red());
+            eprintln!("{} Parallel search stopped", "🛑".red());
         }
     });
 
     // **NEW: Enhanced parallel processing with content search support**
-    let mut results: Vec<SearchResult> = all_paths
-        .into_par_iter()
-        .filter_map(|path| {
-            // Check for cancellation in parallel tasks
-            if !running.load(Ordering::Relaxed) {
-                return None;
-            }
+    let process_path = |(path, _size, special): (PathBuf, u64, bool)| {
+        // Check for cancellation in parallel tasks, and whether enough was found already
+        if !running.load(Ordering::Relaxed) || quota.met() {
+            return None;
+        }
 
-            let is_dir = path.is_dir();
+        let is_dir = path.is_dir();
 
-            // Update processing counters
-            if is_dir {
-                dirs_processed.fetch_add(1, Ordering::Relaxed);
-            } else {
-                files_processed.fetch_add(1, Ordering::Relaxed);
-            }
+        // Update processing counters
+        if is_dir {
+            dirs_processed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            files_processed.fetch_add(1, Ordering::Relaxed);
+        }
 
-            // Apply type filters
-            if dirs_only && !is_dir { return None; }
-            if files_only && is_dir { return None; }
+        // Entries are matched after the walk, whether or not the archive itself passes the filters
+        if options.archives && !is_dir && archive::is_archive(&path) {
+            archives.lock().unwrap().push(path.clone());
+        }
 
-            let file_name = path.file_name()?.to_str()?;
+        // Apply type filters
+        if options.dirs_only && !is_dir { return None; }
+        if options.files_only && is_dir { return None; }
+        if !options.filters.matches(&path, is_dir, || path.metadata().ok()) { return None; }
 
-            // **NEW: Content and filename matching logic**
-            let mut content_matches = Vec::new();
-            let mut filename_score = None;
+        // Names that aren't UTF-8 are matched lossily, the result keeps the raw path
+        let file_name = path.file_name()?.to_string_lossy();
 
-            // Check filename match
-            if let Some(pattern) = filename_pattern {
-                filename_score = get_best_match_score(file_name, pattern, &matcher, match_mode);
-            }
+        // **NEW: Content and filename matching logic**
+        let mut content_matches = Matches::default();
+        let mut content_hit = false;
+        let mut content_truncated = false;
+        let mut filename_match = None;
 
-            // Use config to check if file is content searchable
-            if let Some(pattern) = content_pattern {
-                if !is_dir && config.is_content_searchable(&path) {
-                    if let Ok(matches) = search_file_content(&path, pattern, match_mode) {
-                        if !matches.is_empty() {
-                            content_matches = matches;
+        // Check filename match
+        if let Some(pattern) = name_pattern.as_deref() {
+            let target = name_target(&file_name, &path, search_path, pattern, options);
+            filename_match = keep_confident(get_best_match_score(&target, pattern, &matcher, match_mode, name_case), name_floor);
+        }
+        let filename_score = filename_match.map(|(score, _)| score);
+
+        // Use config to check if file is content searchable
+        if let Some(ref query) = content_query
+            && !is_dir && !special && config.is_content_searchable(&path)
+        {
+            // Reading a cloud-only file would download it, skip unless --hydrate
+            if !options.hydrate && util::is_cloud_placeholder(&path) {
+                placeholders_skipped.fetch_add(1, Ordering::Relaxed);
+            } else if !budget.exhausted() {
+                let _permit = scan_permits.as_ref().map(ScanPermits::acquire);
+                match search_content_query(&path, query, options, &running, cache.as_ref(), budget.per_file) {
+                    Ok((hit, outcome)) => {
+                        budget.record(&outcome);
+                        if hit {
+                            quota.record(&outcome.matches);
                         }
+                        content_hit = hit;
+                        content_truncated = outcome.truncated;
+                        content_matches = outcome.matches;
                     }
+                    Err(e) => unreadable.lock().unwrap().push((path.clone(), read_error_reason(e.as_ref()))),
                 }
             }
+        }
 
-            // **NEW: Determine if this is a match and calculate combined score**
-            let (is_match, final_score) = match search_type {
-                SearchType::FileName => (filename_score.is_some(), filename_score.unwrap_or(0)),
-                SearchType::Content => (!content_matches.is_empty(), if !content_matches.is_empty() { 100 } else { 0 }),
-                SearchType::Hybrid => {
-                    let has_filename = filename_score.is_some();
-                    let has_content = !content_matches.is_empty();
-                    let score = filename_score.unwrap_or(0) + if has_content { 50 } else { 0 };
-                    (has_filename || has_content, score)
-                }
-            };
+        // **NEW: Determine if this is a match and calculate combined score**
+        let (is_match, final_score) = match_score(&search_type, filename_score, content_hit);
 
-            if !is_match {
-                return None;
-            }
+        if !is_match {
+            return None;
+        }
 
-            // Count matched files/dirs
-            if is_dir {
-                dirs_scanned.fetch_add(1, Ordering::Relaxed);
-            } else {
-                files_scanned.fetch_add(1, Ordering::Relaxed);
-            }
+        // Count matched files/dirs
+        if is_dir {
+            dirs_scanned.fetch_add(1, Ordering::Relaxed);
+        } else {
+            files_scanned.fetch_add(1, Ordering::Relaxed);
+        }
 
-            let (size, modified) = if show_details ||
-                config.output_options.show_details {
-                if let Ok(metadata) = path.metadata() {
-                    (
-                        if metadata.is_file() { Some(metadata.len()) } else { None },
-                        metadata.modified().ok()
-                    )
-                } else {
-                    (None, None)
-                }
+        let (size, disk_size, modified) = if options.show_details ||
+            config.output_options.show_details {
+            if let Ok(metadata) = path.metadata() {
+                (
+                    if metadata.is_file() { Some(metadata.len()) } else { None },
+                    util::sparse_disk_size(&metadata),
+                    metadata.modified().ok()
+                )
             } else {
-                (None, None)
-            };
+                (None, None, None)
+            }
+        } else {
+            (None, None, None)
+        };
 
-            Some(SearchResult {
-                path,
-                score: final_score,
-                is_dir,
-                size,
-                modified,
-                content_matches,
-                search_type: search_type.clone(),
-            })
+        Some(SearchResult {
+            path,
+            archive_entry: None,
+            score: final_score,
+            is_dir,
+            size,
+            disk_size,
+            modified,
+            name_match: filename_match.map(|(_, kind)| kind),
+            name_floor,
+            content_matches: content_matches.found,
+            content_count: content_matches.count,
+            content_truncated,
+            peek: None,
+            search_type: search_type.clone(),
         })
-        .collect();
+    };
+
+    let mut results: Vec<SearchResult> = if content_pattern.is_some() {
+        all_paths.into_iter().par_bridge().filter_map(&process_path).collect()
+    } else {
+        all_paths.into_par_iter().filter_map(&process_path).collect()
+    };
+
+    let mut unreadable = unreadable.into_inner().unwrap();
+    let (archive_results, unreadable_archives) =
+        search_archives(archives.into_inner().unwrap(), search_path, options, config, &budget, &quota, &running);
+    results.extend(archive_results);
+    unreadable.extend(unreadable_archives);
 
     // Signal completion and wait for progress thread
     processing_complete.store(true, Ordering::Relaxed);
     progress_thread.join().unwrap();
+    warnings.skipped_placeholders(placeholders_skipped.load(Ordering::Relaxed));
+    warnings.unreadable_files(&mut unreadable);
+    budget.report(warnings);
+    quota.report();
+    if let Some(cache) = cache {
+        cache.into_inner().unwrap().save();
+    }
 
     // Only sort and return results if search wasn't cancelled
     if running.load(Ordering::Relaxed) {
-        results.par_sort_by(|a, b| b.score.cmp(&a.score));
-        results.truncate(limit);
+        results.par_sort_by(result_order(options));
+        results.truncate(options.limit);
+        keep_first_matches(&mut results, options.limit_matches);
     } else {
         // Return partial results if cancelled
-        results.par_sort_by(|a, b| b.score.cmp(&a.score));
-        results.truncate(limit.min(results.len()));
+        results.par_sort_by(result_order(options));
+        results.truncate(options.limit.min(results.len()));
+        keep_first_matches(&mut results, options.limit_matches);
     }
 
     results
@@ -504,38 +1637,249 @@ pub fn search_files_parallel(
 pub fn get_best_match_score(
     filename: &str,
     pattern: &str,
-    matcher: &fuzzy_matcher::skim::SkimMatcherV2,
-    match_mode: &MatchMode
-) -> Option<i64> {
+    matcher: &SkimMatcherV2,
+    match_mode: &MatchMode,
+    case_sensitive: bool,
+) -> Option<(i64, NameMatch)> {
+    let filename_folded = fold_case(filename, case_sensitive);
+    let pattern_folded = fold_case(pattern, case_sensitive);
     match match_mode {
         MatchMode::Fuzzy => {
             // Multi-algorithm fusion for fuzzy mode
             let fuzzy_score = matcher.fuzzy_match(filename, pattern);
-            let exact_score = if filename.to_lowercase().contains(&pattern.to_lowercase()) {
+            let exact_score = if filename_folded.contains(&*pattern_folded) {
                 Some(100)
             } else {
                 None
             };
-            let prefix_score = if filename.to_lowercase().starts_with(&pattern.to_lowercase()) {
+            let prefix_score = if filename_folded.starts_with(&*pattern_folded) {
                 Some(150)
             } else {
                 None
             };
 
-            // Return the best score
-            [fuzzy_score, exact_score, prefix_score]
+            // Return the best score, along with the algorithm that produced it
+            [
+                fuzzy_score.map(|score| (score, NameMatch::Fuzzy)),
+                exact_score.map(|score| (score, NameMatch::Substring)),
+                prefix_score.map(|score| (score, NameMatch::Prefix)),
+            ]
                 .into_iter()
                 .flatten()
-                .max()
+                .max_by_key(|(score, _)| *score)
         }
 
         MatchMode::Exact => {
             // Keep exact mode simple
-            if filename.to_lowercase().contains(&pattern.to_lowercase()) {
-                Some(100)
+            if filename_folded.contains(&*pattern_folded) {
+                Some((100, NameMatch::Substring))
             } else {
                 None
             }
         }
+
+        MatchMode::Regex => cached_regex(pattern, case_sensitive)?
+            .is_match(filename)
+            .then_some((100, NameMatch::Regex)),
+    }
+}
+
+/// Lowest fuzzy score kept for a pattern. --min-score is per pattern character, so a long
+/// pattern needs a proportionally stronger match than a short one. None when disabled.
+pub fn fuzzy_score_floor(pattern: &str, min_score: i64) -> Option<i64> {
+    if min_score <= 0 {
+        return None;
     }
-}
\ No newline at end of file
+    let chars = pattern.chars().filter(|c| !c.is_whitespace()).count();
+    Some(min_score * chars as i64)
+}
+
+/// Drop a fuzzy name match scoring under the floor. Prefix and substring hits are never weak.
+pub fn keep_confident(found: Option<(i64, NameMatch)>, floor: Option<i64>) -> Option<(i64, NameMatch)> {
+    found.filter(|&(score, kind)| kind != NameMatch::Fuzzy || floor.is_none_or(|floor| score >= floor))
+}
+
+/// What a name pattern is matched against: the base name, or with --full-path and for patterns
+/// containing `/` (`tests/helpers`) the path relative to the root, joined with `/` on every platform.
+/// With --transliterate the target is ASCII-folded like the pattern.
+pub fn name_target<'a>(file_name: &'a str, path: &Path, root: &Path, pattern: &str, options: &SearchOptions) -> Cow<'a, str> {
+    let target = if !options.full_path && !pattern.contains('/') {
+        Cow::Borrowed(file_name)
+    } else {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let parts: Vec<_> = relative.components().map(|part| part.as_os_str().to_string_lossy()).collect();
+        Cow::Owned(parts.join("/"))
+    };
+    if !options.transliterating() {
+        return target;
+    }
+    match transliterate(&target) {
+        Cow::Borrowed(_) => target,
+        Cow::Owned(folded) => Cow::Owned(folded),
+    }
+}
+
+/// ASCII approximation of a name for --transliterate: accents dropped (`Müller` -> `Muller`),
+/// other scripts romanized, and `-` and `_` read as spaces so `sao-paulo` matches `São Paulo`
+pub fn transliterate(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() && !text.contains(['-', '_']) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(deunicode::deunicode(text).replace(['-', '_'], " "))
+}
+
+/// Fuzzy matcher for the search's case setting. The skim default would be smart case.
+pub fn name_matcher(case_sensitive: bool) -> SkimMatcherV2 {
+    let matcher = SkimMatcherV2::default();
+    if case_sensitive { matcher.respect_case() } else { matcher.ignore_case() }
+}
+
+/// Lowercase text for comparison, unless the search is case-sensitive. Character by character,
+/// like `fold_with_origins`, so a folded pattern is always found in a folded line.
+fn fold_case(text: &str, case_sensitive: bool) -> Cow<'_, str> {
+    if case_sensitive { Cow::Borrowed(text) } else { Cow::Owned(text.chars().flat_map(char::to_lowercase).collect()) }
+}
+
+/// `line` lowercased, and for each byte of that the offset in `line` of the character it came from,
+/// with `line.len()` at the end
+fn fold_with_origins(line: &str) -> (String, Vec<usize>) {
+    let mut folded = String::with_capacity(line.len());
+    let mut origins = Vec::with_capacity(line.len() + 1);
+    for (offset, c) in line.char_indices() {
+        folded.extend(c.to_lowercase());
+        origins.resize(folded.len(), offset);
+    }
+    origins.push(line.len());
+    (folded, origins)
+}
+
+/// `span` of a line folded by `fold_with_origins`, as a span of the original line. A match that
+/// ends partway through a character's lowercase form takes the whole character.
+fn original_span(line: &str, origins: &[usize], span: std::ops::Range<usize>) -> std::ops::Range<usize> {
+    let start = origins[span.start];
+    if span.is_empty() {
+        return start..start;
+    }
+    let last = origins[span.end - 1];
+    start..last + line[last..].chars().next().map_or(0, char::len_utf8)
+}
+
+/// Regex for --match-mode regex (file names and content), case-insensitive unless asked otherwise
+pub fn build_regex(pattern: &str, case_sensitive: bool) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(!case_sensitive).build()
+}
+
+/// `build_regex` compiled once per thread and pattern, since every entry of a search asks for the same
+/// ones. Content query terms come precompiled (`PreparedQuery`), a hybrid search may still
+/// alternate between a name and a content pattern, so two are kept.
+fn cached_regex(pattern: &str, case_sensitive: bool) -> Option<Regex> {
+    thread_local! {
+        static COMPILED: RefCell<Vec<(String, bool, Option<Regex>)>> = const { RefCell::new(Vec::new()) };
+    }
+    COMPILED.with_borrow_mut(|compiled| {
+        if let Some((_, _, regex)) = compiled.iter().find(|(source, case, _)| source == pattern && *case == case_sensitive) {
+            return regex.clone();
+        }
+        let regex = build_regex(pattern, case_sensitive).ok();
+        if compiled.len() >= 2 {
+            compiled.remove(0);
+        }
+        compiled.push((pattern.to_string(), case_sensitive, regex.clone()));
+        regex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The highlighted text of every match `line_matcher` finds in `line`
+    fn highlights(line_matcher: &LineMatcher, line: &str) -> Vec<String> {
+        let mut matches = Matches::new(false);
+        line_matcher.find_in(line, 1, None, None, &mut matches);
+        matches.found.iter()
+            .map(|found| found.line_content[found.match_start..found.match_end].to_string())
+            .collect()
+    }
+
+    fn literal(pattern: &str, case_sensitive: bool) -> LineMatcher<'_> {
+        LineMatcher::new(pattern, &MatchMode::Exact, case_sensitive, None, false, false, None).unwrap()
+    }
+
+    #[test]
+    fn literal_matches_overlap() {
+        assert_eq!(highlights(&literal("aa", true), "aaa"), ["aa", "aa"]);
+        assert!(highlights(&literal("AA", true), "aaa").is_empty());
+    }
+
+    #[test]
+    fn case_insensitive_literal_handles_multibyte_text() {
+        assert_eq!(highlights(&literal("é", false), "éé"), ["é", "é"]);
+        assert_eq!(highlights(&literal("été", false), "un ÉTÉ, un été"), ["ÉTÉ", "été"]);
+        // Lowercasing changes the byte length of these, offsets must still fit the line
+        assert_eq!(highlights(&literal("stadt", false), "İİ Stadt"), ["Stadt"]);
+        assert_eq!(highlights(&literal("straße", false), "ẞ STRAẞE"), ["STRAẞE"]);
+    }
+
+    #[test]
+    fn repeated_patterns_handle_multibyte_text() {
+        let patterns = vec!["été".to_string(), "stadt".to_string(), "ß".to_string()];
+        let key = patterns.join("\n");
+        let options = SearchOptions { case_mode: CaseMode::Insensitive, ..Default::default() };
+        let line_matcher = LineMatcher::any_of(&patterns, &key, &options).unwrap();
+        assert_eq!(highlights(&line_matcher, "İİ Stadt, ÉTÉ et ẞ"), ["Stadt", "ÉTÉ", "ẞ"]);
+        assert_eq!(highlights(&line_matcher, "STADT été"), ["STADT", "été"]);
+
+        let mut matches = Matches::new(false);
+        line_matcher.find_in("İ été", 1, None, None, &mut matches);
+        assert_eq!(matches.found[0].pattern.as_deref(), Some("été"));
+    }
+
+    #[test]
+    fn folded_spans_map_back_to_whole_characters() {
+        let line = "aİb";
+        let (folded, origins) = fold_with_origins(line);
+        assert_eq!(folded, "ai\u{307}b");
+        let i = folded.find('i').unwrap();
+        // `i` alone is only part of what `İ` folds to, the span still covers all of it
+        assert_eq!(&line[original_span(line, &origins, i..i + 1)], "İ");
+        assert_eq!(&line[original_span(line, &origins, i..folded.len())], "İb");
+    }
+
+    #[test]
+    fn long_lines_keep_a_window_around_the_match() {
+        let line = format!("{}needle{}", "a".repeat(10_000), "b".repeat(10_000));
+        let (window, offset) = line_window(&line, 10_000, 10_006);
+        assert!(window.len() <= MAX_STORED_LINE_BYTES);
+        assert_eq!(&window[10_000 - offset..10_006 - offset], "needle");
+
+        let short = "short line";
+        assert_eq!(line_window(short, 0, 5), (short.to_string(), 0));
+    }
+
+    #[test]
+    fn truncated_multibyte_lines_keep_case_insensitive_highlights() {
+        // Three-byte characters, so the window edges fall inside them unless moved
+        let line = format!("{} STRAẞE İstanbul {}", "€".repeat(3_000), "ü".repeat(3_000));
+        let mut matches = Matches::new(false);
+        literal("straße", false).find_in(&line, 1, None, None, &mut matches);
+        let found = &matches.found[0];
+        assert!(found.line_content.len() < line.len());
+        assert_eq!(&found.line_content[found.match_start..found.match_end], "STRAẞE");
+
+        let patterns = vec!["i̇stanbul".to_string(), "ü".to_string()];
+        let key = patterns.join("\n");
+        let options = SearchOptions { case_mode: CaseMode::Insensitive, ..Default::default() };
+        let mut matches = Matches::new(false);
+        LineMatcher::any_of(&patterns, &key, &options).unwrap().find_in(&line, 1, None, None, &mut matches);
+        let found = &matches.found[0];
+        assert!(found.line_content.len() < line.len());
+        assert_eq!(&found.line_content[found.match_start..found.match_end], "İstanbul");
+    }
+
+    #[test]
+    fn fuzzy_content_marks_substrings_only() {
+        let line_matcher = LineMatcher::new("Ab", &MatchMode::Fuzzy, false, None, false, false, None).unwrap();
+        assert_eq!(highlights(&line_matcher, "xAB a_b ab"), ["AB", "ab"]);
+    }
+}
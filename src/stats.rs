@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, SystemTime};
+use colored::Colorize;
+use crate::config::Config;
+use crate::search::{self, SearchOptions};
+use crate::util;
+use crate::walker;
+use crate::warnings::Warnings;
+
+const DAY: u64 = 86400;
+
+/// Age buckets as (label, upper bound in seconds), shared with `ff timeline`
+pub const AGE_BUCKETS: &[(&str, u64)] = &[
+    ("Last 24 hours", DAY),
+    ("Last 7 days", 7 * DAY),
+    ("Last 30 days", 30 * DAY),
+    ("Last year", 365 * DAY),
+    ("Older", u64::MAX),
+];
+
+#[derive(Default)]
+struct GroupTotals {
+    files: usize,
+    bytes: u64,
+}
+
+#[derive(Default)]
+struct TreeStats {
+    files: usize,
+    dirs: usize,
+    bytes: u64,
+    by_extension: HashMap<String, GroupTotals>,
+    dir_sizes: HashMap<PathBuf, u64>,
+    ages: [usize; AGE_BUCKETS.len()],
+}
+
+/// `ff stats` - walk the tree with the usual ignore rules and print a space/age report
+pub fn run_stats(root: &Path, options: &SearchOptions, config: &Config, top: usize) {
+    eprintln!("{} Analyzing: {}", "📊".green(), root.display().to_string().cyan());
+
+    let filter = search::entry_filter(root, options, config, Arc::new(AtomicBool::new(true)));
+    let now = SystemTime::now();
+    let mut stats = TreeStats::default();
+
+    let mut warnings = Warnings::default();
+
+    for entry in walker::for_backend(options.backend).walk(filter.clone()) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warnings.walk_error(&e);
+                continue;
+            }
+        };
+        if entry.is_dir() {
+            if entry.depth() > 0 {
+                stats.dirs += 1;
+            }
+            continue;
+        }
+
+        let Ok(metadata) = entry.metadata() else { continue };
+        if !metadata.is_file() {
+            continue;
+        }
+        let size = metadata.len();
+
+        stats.files += 1;
+        stats.bytes += size;
+
+        let extension = entry.path()
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy().to_lowercase()))
+            .unwrap_or_else(|| "(no extension)".to_string());
+        let group = stats.by_extension.entry(extension).or_default();
+        group.files += 1;
+        group.bytes += size;
+
+        // Every directory between the file and the root accumulates its size
+        for ancestor in entry.path().ancestors().skip(1) {
+            if ancestor == root {
+                break;
+            }
+            *stats.dir_sizes.entry(ancestor.to_path_buf()).or_default() += size;
+        }
+
+        if let Some(age) = metadata.modified().ok().and_then(|m| now.duration_since(m).ok()) {
+            stats.ages[age_bucket(age)] += 1;
+        }
+    }
+
+    warnings.abandoned_dirs(&filter.abandoned_dirs());
+    warnings.reparse_points(&filter.skipped_reparse_points());
+    warnings.capped_dirs(&filter.capped_dirs(), options.dir_entry_cap);
+    warnings.cache_dirs(&filter.skipped_caches());
+    warnings.ignored_entries(&filter.skipped_ignored());
+    warnings.build_dirs(&filter.skipped_build_dirs());
+    warnings.symlink_loops(&filter.skipped_loops());
+
+    print_report(root, &stats, top);
+    warnings.print(options.verbose);
+}
+
+pub fn age_bucket(age: Duration) -> usize {
+    let secs = age.as_secs();
+    AGE_BUCKETS
+        .iter()
+        .position(|(_, limit)| secs < *limit)
+        .unwrap_or(AGE_BUCKETS.len() - 1)
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 { 0.0 } else { part as f64 * 100.0 / total as f64 }
+}
+
+fn print_report(root: &Path, stats: &TreeStats, top: usize) {
+    println!();
+    println!("{} {} files, {} directories, {} total",
+             "📁".yellow(),
+             stats.files.to_string().bright_green().bold(),
+             stats.dirs.to_string().bright_green().bold(),
+             util::format_size(stats.bytes).bright_green().bold());
+
+    if stats.files == 0 {
+        return;
+    }
+
+    let mut extensions: Vec<_> = stats.by_extension.iter().collect();
+    extensions.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then(b.1.files.cmp(&a.1.files)));
+
+    println!("\n{}", "BY EXTENSION:".yellow().bold());
+    for (extension, totals) in extensions.iter().take(top) {
+        println!("  {:<16} {:>8} files {:>10} {:>6.1}%",
+                 extension.blue(),
+                 totals.files,
+                 util::format_size(totals.bytes),
+                 percent(totals.bytes, stats.bytes));
+    }
+    if extensions.len() > top {
+        println!("  {}", format!("... {} more extensions", extensions.len() - top).dimmed());
+    }
+
+    let mut directories: Vec<_> = stats.dir_sizes.iter().collect();
+    directories.sort_by(|a, b| b.1.cmp(a.1));
+
+    if !directories.is_empty() {
+        println!("\n{}", "LARGEST DIRECTORIES:".yellow().bold());
+        for (dir, bytes) in directories.iter().take(top) {
+            let relative = dir.strip_prefix(root).unwrap_or(dir);
+            println!("  {:>10} {:>6.1}%  {}",
+                     util::format_size(**bytes),
+                     percent(**bytes, stats.bytes),
+                     util::display_path(relative).cyan());
+        }
+    }
+
+    println!("\n{}", "LAST MODIFIED:".yellow().bold());
+    for ((label, _), count) in AGE_BUCKETS.iter().zip(stats.ages.iter()) {
+        println!("  {:<16} {:>8} files {:>6.1}%",
+                 label,
+                 count,
+                 percent(*count as u64, stats.files as u64));
+    }
+}
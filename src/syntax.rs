@@ -0,0 +1,209 @@
+use std::path::Path;
+use clap::ValueEnum;
+
+/// Which part of source code --in keeps content matches from
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// Line and block comments, including doc comments
+    Comments,
+    /// String and character literals
+    Strings,
+    /// Everything outside comments and strings
+    Code,
+}
+
+impl Scope {
+    pub fn name(self) -> &'static str {
+        match self {
+            Scope::Comments => "comments",
+            Scope::Strings => "strings",
+            Scope::Code => "code",
+        }
+    }
+}
+
+/// Just enough of a language's lexical rules to tell comments and strings from code
+struct Syntax {
+    line_comments: &'static [&'static str],
+    block_comments: &'static [(&'static str, &'static str)],
+    strings: &'static [(&'static str, &'static str, bool)],  // Open, close, may span lines
+}
+
+const C_LIKE: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &[("\"", "\"", false), ("'", "'", false), ("`", "`", true)],
+};
+
+/// No '...' strings, they would swallow lifetimes like 'a. The char literal '"' is its own
+/// (immediately closed) string so its quote doesn't open one.
+const RUST: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &[("'\"'", "", false), ("r#\"", "\"#", true), ("\"", "\"", true)],
+};
+
+const PYTHON: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[],
+    strings: &[("\"\"\"", "\"\"\"", true), ("'''", "'''", true), ("\"", "\"", false), ("'", "'", false)],
+};
+
+const HASH: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[],
+    strings: &[("\"", "\"", false), ("'", "'", false)],
+};
+
+const DASH_DASH: Syntax = Syntax {
+    line_comments: &["--"],
+    block_comments: &[("/*", "*/")],
+    strings: &[("'", "'", false), ("\"", "\"", false)],
+};
+
+const MARKUP: Syntax = Syntax {
+    line_comments: &[],
+    block_comments: &[("<!--", "-->")],
+    strings: &[("\"", "\"", false)],
+};
+
+fn syntax_for(path: &Path) -> Option<&'static Syntax> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    let syntax = match extension.as_str() {
+        "rs" => &RUST,
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "java" | "js" | "mjs" | "cjs" | "jsx" | "ts" | "tsx"
+        | "go" | "cs" | "kt" | "kts" | "swift" | "scala" | "dart" | "php" => &C_LIKE,
+        "py" => &PYTHON,
+        "sh" | "bash" | "zsh" | "rb" | "pl" | "toml" | "yaml" | "yml" | "r" => &HASH,
+        "sql" | "lua" | "hs" => &DASH_DASH,
+        "html" | "htm" | "xml" | "svg" | "vue" => &MARKUP,
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Region {
+    Code,
+    Comment,
+    String,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Code,
+    Block(usize),   // Inside block_comments[i]
+    String(usize),  // Inside strings[i]
+}
+
+/// Follows one file line by line, remembering open block comments and strings between lines.
+/// Every line has to go through `advance`, including ones that aren't searched.
+pub struct ScopeTracker {
+    syntax: &'static Syntax,
+    scope: Scope,
+    state: State,
+    regions: Vec<(usize, Region)>,  // Where each region of the current line starts
+}
+
+impl ScopeTracker {
+    /// None for languages ff can't tokenize
+    pub fn for_file(path: &Path, scope: Scope) -> Option<Self> {
+        Some(Self { syntax: syntax_for(path)?, scope, state: State::Code, regions: Vec::new() })
+    }
+
+    pub fn advance(&mut self, line: &str) {
+        self.regions.clear();
+        let syntax = self.syntax;
+        let mut position = 0;
+        self.regions.push((0, self.region()));
+
+        while position < line.len() {
+            let rest = &line[position..];
+            match self.state {
+                State::Code => {
+                    if syntax.line_comments.iter().any(|marker| rest.starts_with(marker)) {
+                        self.regions.push((position, Region::Comment));
+                        return;
+                    }
+                    if let Some(index) = syntax.block_comments.iter().position(|(open, _)| rest.starts_with(open)) {
+                        self.enter(State::Block(index), position);
+                        position += syntax.block_comments[index].0.len();
+                        continue;
+                    }
+                    if let Some(index) = syntax.strings.iter().position(|(open, _, _)| rest.starts_with(open)) {
+                        self.enter(State::String(index), position);
+                        position += syntax.strings[index].0.len();
+                        continue;
+                    }
+                }
+                State::Block(index) => {
+                    let close = syntax.block_comments[index].1;
+                    if rest.starts_with(close) {
+                        position += close.len();
+                        self.enter(State::Code, position);
+                        continue;
+                    }
+                }
+                State::String(index) => {
+                    let close = syntax.strings[index].1;
+                    if let Some(escaped) = rest.strip_prefix('\\') {
+                        position += 1 + escaped.chars().next().map_or(0, char::len_utf8);
+                        continue;
+                    }
+                    if rest.starts_with(close) {
+                        position += close.len();
+                        self.enter(State::Code, position);
+                        continue;
+                    }
+                }
+            }
+            position += rest.chars().next().map_or(1, char::len_utf8);
+        }
+
+        // Single-line strings end with the line even when unterminated
+        if let State::String(index) = self.state
+            && !syntax.strings[index].2
+        {
+            self.state = State::Code;
+        }
+    }
+
+    /// Whether a match starting at this byte offset of the current line is in scope
+    pub fn allows(&self, offset: usize) -> bool {
+        let region = self.regions.iter().rev().find(|(start, _)| *start <= offset).map_or(Region::Code, |(_, region)| *region);
+        match self.scope {
+            Scope::Comments => region == Region::Comment,
+            Scope::Strings => region == Region::String,
+            Scope::Code => region == Region::Code,
+        }
+    }
+
+    /// Where comments and strings start and end on one line, read without the lines before it,
+    /// for highlighting excerpts. None for languages ff can't tokenize.
+    pub fn line_regions(path: &Path, line: &str) -> Option<Vec<(usize, Scope)>> {
+        let mut tracker = Self::for_file(path, Scope::Code)?;
+        tracker.advance(line);
+        let regions = tracker.regions.iter().map(|&(start, region)| {
+            let scope = match region {
+                Region::Code => Scope::Code,
+                Region::Comment => Scope::Comments,
+                Region::String => Scope::Strings,
+            };
+            (start, scope)
+        });
+        Some(regions.collect())
+    }
+
+    fn enter(&mut self, state: State, position: usize) {
+        self.state = state;
+        self.regions.push((position, self.region()));
+    }
+
+    fn region(&self) -> Region {
+        match self.state {
+            State::Code => Region::Code,
+            State::Block(_) => Region::Comment,
+            State::String(_) => Region::String,
+        }
+    }
+}
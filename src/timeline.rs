@@ -0,0 +1,115 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::SystemTime;
+use colored::Colorize;
+use crate::config::Config;
+use crate::search::{self, SearchOptions};
+use crate::stats::{age_bucket, AGE_BUCKETS};
+use crate::util;
+use crate::walker;
+use crate::warnings::Warnings;
+
+const BAR_WIDTH: usize = 40;
+
+/// `ff timeline` - bucket (matching) files by modification time and list the newest ones
+pub fn run_timeline(root: &Path, options: &SearchOptions, config: &Config, recent: usize) {
+    eprintln!("{} Timeline: {}", "🕒".green(), root.display().to_string().cyan());
+    if let Some(ref pattern) = options.filename_pattern {
+        eprintln!(" Filename pattern: {}", pattern.bright_white().bold());
+    }
+
+    let filter = search::entry_filter(root, options, config, Arc::new(AtomicBool::new(true)));
+    let name_case = options.filename_pattern.as_deref().is_some_and(|pattern| options.case_mode.sensitive_for(pattern));
+    let matcher = search::name_matcher(name_case);
+    let name_floor = options.filename_pattern.as_deref().and_then(|pattern| search::fuzzy_score_floor(pattern, options.min_score));
+    let name_pattern = options.name_pattern();
+    let now = SystemTime::now();
+    let mut buckets = [0usize; AGE_BUCKETS.len()];
+    // Min-heap of the newest files seen so far, capped at `recent` entries
+    let mut newest: BinaryHeap<Reverse<(SystemTime, PathBuf)>> = BinaryHeap::new();
+
+    let mut warnings = Warnings::default();
+
+    for entry in walker::for_backend(options.backend).walk(filter.clone()) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warnings.walk_error(&e);
+                continue;
+            }
+        };
+        if entry.is_dir() {
+            continue;
+        }
+
+        if let Some(ref pattern) = name_pattern {
+            let name = entry.path().file_name().unwrap_or_default().to_string_lossy();
+            let target = search::name_target(&name, entry.path(), root, pattern, options);
+            let found = search::get_best_match_score(&target, pattern, &matcher, &options.match_mode, name_case);
+            if search::keep_confident(found, name_floor).is_none() {
+                continue;
+            }
+        }
+
+        let Ok(metadata) = entry.metadata() else { continue };
+        let Ok(modified) = metadata.modified() else { continue };
+        if !metadata.is_file() {
+            continue;
+        }
+
+        buckets[age_bucket(now.duration_since(modified).unwrap_or_default())] += 1;
+
+        if recent > 0 {
+            newest.push(Reverse((modified, entry.into_path())));
+            if newest.len() > recent {
+                newest.pop();
+            }
+        }
+    }
+
+    warnings.abandoned_dirs(&filter.abandoned_dirs());
+    warnings.reparse_points(&filter.skipped_reparse_points());
+    warnings.capped_dirs(&filter.capped_dirs(), options.dir_entry_cap);
+    warnings.cache_dirs(&filter.skipped_caches());
+    warnings.ignored_entries(&filter.skipped_ignored());
+    warnings.build_dirs(&filter.skipped_build_dirs());
+    warnings.symlink_loops(&filter.skipped_loops());
+
+    print_report(root, &buckets, newest, now);
+    warnings.print(options.verbose);
+}
+
+fn print_report(root: &Path, buckets: &[usize], newest: BinaryHeap<Reverse<(SystemTime, PathBuf)>>, now: SystemTime) {
+    let total: usize = buckets.iter().sum();
+    println!();
+    if total == 0 {
+        eprintln!("{}", "No files found".bright_red());
+        return;
+    }
+
+    println!("{}", "LAST MODIFIED:".yellow().bold());
+    let largest = buckets.iter().copied().max().unwrap_or(0);
+    for ((label, _), &count) in AGE_BUCKETS.iter().zip(buckets) {
+        // Any non-empty bucket gets at least one block so it stays visible
+        let width = if count == 0 { 0 } else { (count * BAR_WIDTH / largest).max(1) };
+        println!("  {:<16} {:<bar$} {:>8}",
+                 label,
+                 "█".repeat(width).green(),
+                 count,
+                 bar = BAR_WIDTH);
+    }
+
+    if newest.is_empty() {
+        return;
+    }
+
+    println!("\n{}", "MOST RECENT:".yellow().bold());
+    for Reverse((modified, path)) in newest.into_sorted_vec() {
+        let age = now.duration_since(modified).unwrap_or_default();
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        println!("  {:>8}  {}", util::format_time_ago(age).dimmed(), util::display_path(relative).cyan());
+    }
+}
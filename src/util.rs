@@ -1,25 +1,38 @@
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::io::Write;
 use std::path::Path;
 use colored::Colorize;
 use crate::{SearchResult, SearchType};
 
-// Update display_results in util.rs
-pub fn display_results(results: &[SearchResult], show_details: bool) {
+pub fn display_results(results: &[SearchResult], show_details: bool, max_line_length: usize, use_pager: bool) {
+    // The count is a decoration (stderr), only the result lines themselves go to stdout
     if results.is_empty() {
-        println!();
-        println!("{}", "No files found matching the pattern".bright_red());
+        eprintln!();
+        eprintln!("{}", "No files found matching the pattern".bright_red());
         return;
     }
+    eprintln!();
+    eprintln!("{} Found {} match(es):", "✅".green(), results.len().to_string().bright_green().bold());
 
-    println!();
-    println!("{} Found {} match(es):", "✅".green(), results.len().to_string().bright_green().bold());
+    let output = render_results(results, show_details, max_line_length);
+    if use_pager {
+        page_output(&output);
+    } else {
+        print!("{}", output);
+    }
+}
+
+pub fn render_results(results: &[SearchResult], show_details: bool, max_line_length: usize) -> String {
+    let mut out = String::new();
 
     for (index, result) in results.iter().enumerate() {
-        println!();
+        if index > 0 {
+            out.push('\n');
+        }
         let index_str = format!("{:2}", index + 1);
         let type_icon = get_file_icon(result);
-        let path_str = result.path.display().to_string();
+        let path_str = result_path(result);
 
         let mut line = format!(
             "{} {} {}",
@@ -34,104 +47,382 @@ pub fn display_results(results: &[SearchResult], show_details: bool) {
             SearchType::Hybrid => line.push_str(&format!(" {}", "[HYBRID]".yellow())),
             _ => {}
         }
+        if result.content_truncated {
+            line.push_str(&format!(" {}", "[TRUNCATED SCAN]".yellow().dimmed()));
+        }
 
         if show_details {
             if let Some(size) = result.size {
                 line.push_str(&format!(" {}", format_size(size).dimmed()));
             }
-            if let Some(modified) = result.modified {
-                if let Ok(elapsed) = modified.elapsed() {
-                    line.push_str(&format!(" {}", format_time_ago(elapsed).dimmed()));
-                }
+            if let Some(disk_size) = result.disk_size {
+                line.push_str(&format!(" {}", format!("(sparse, {} on disk)", format_size(disk_size)).dimmed()));
             }
-            line.push_str(&format!(" {}", format!("({})", result.score).bright_black()));
+            if let Some(modified) = result.modified
+                && let Ok(elapsed) = modified.elapsed()
+            {
+                line.push_str(&format!(" {}", format_time_ago(elapsed).dimmed()));
+            }
+            line.push_str(&format!(" {}", format!("({}: {})", result.score, match_provenance(result)).bright_black()));
         }
 
-        println!("{}", line);
+        out.push_str(&format!("{}\n", line));
 
         // Show content matches
         if !result.content_matches.is_empty() {
             for (i, content_match) in result.content_matches.iter().enumerate() {
                 if i >= 3 { // Limit to first 3 matches per file
-                    println!("    {} {} more matches...", "...".dimmed(), (result.content_matches.len() - 3).to_string().dimmed());
+                    out.push_str(&format!("    {} {} more matches...\n", "...".dimmed(), (result.content_matches.len() - 3).to_string().dimmed()));
                     break;
                 }
 
-                let line_preview = if content_match.line_content.len() > 100 {
-                    format!("{}...", &content_match.line_content[..97])
-                } else {
-                    content_match.line_content.clone()
+                let line_preview = preview_line(
+                    &content_match.line_content,
+                    content_match.match_start,
+                    content_match.match_end,
+                    max_line_length,
+                );
+
+                let position = match content_match.location {
+                    Some(ref location) => location.clone(),
+                    None => format!("L{}", content_match.line_number),
+                };
+                // Tagged with the pattern that hit when several --content patterns were given
+                let position = match content_match.pattern {
+                    Some(ref pattern) => format!("{} {}", position.blue(), format!("[{}]", pattern).magenta()),
+                    None => position.blue().to_string(),
                 };
+                out.push_str(&format!("    {}: {}\n",
+                         position,
+                         line_preview
+                ));
+            }
+        }
 
-                println!("    {}: {}",
-                         format!("L{}", content_match.line_number).blue(),
-                         line_preview.dimmed()
-                );
+        // --peek: what's inside a matching directory
+        if let Some(ref peek) = result.peek {
+            if peek.total == 0 {
+                out.push_str(&format!("    {}\n", "(empty)".dimmed()));
+            }
+            for (name, is_dir) in &peek.entries {
+                let icon = if *is_dir { "📁" } else { crate::file_types::icon(Path::new(name).extension().and_then(|ext| ext.to_str())) };
+                out.push_str(&format!("    {} {}{}\n", icon, name.dimmed(), if *is_dir { "/" } else { "" }));
+            }
+            if peek.total > peek.entries.len() {
+                out.push_str(&format!("    {} {} more entries...\n", "...".dimmed(), (peek.total - peek.entries.len()).to_string().dimmed()));
             }
         }
     }
+
+    out
 }
 
+/// Cut a line down to about `width` characters around the match, marking cut sides with `…`
+/// and highlighting the match itself. Counts characters, so multi-byte text never splits.
+pub fn preview_line(line: &str, match_start: usize, match_end: usize, width: usize) -> String {
+    let line = line.trim_end();
+    let width = width.max(10);
 
-pub fn get_file_icon(result: &SearchResult) -> &'static str {
+    // Offsets that don't land on this line's char boundaries just mean no highlight
+    let (start, end) = if line.get(match_start..match_end).is_some() { (match_start, match_end) } else { (0, 0) };
+    let (before, matched, after) = (&line[..start], &line[start..end], &line[end..]);
+
+    let before_chars = before.chars().count();
+    let matched_chars = matched.chars().count();
+    let after_chars = after.chars().count();
+
+    if before_chars + matched_chars + after_chars <= width {
+        return format!("{}{}{}", before.dimmed(), matched.yellow().bold(), after.dimmed());
+    }
+
+    // A match wider than the preview is shown from its start
+    if matched_chars >= width {
+        let shown: String = matched.chars().take(width).collect();
+        let left = if before_chars > 0 { "…" } else { "" };
+        return format!("{}{}{}", left.dimmed(), shown.yellow().bold(), "…".dimmed());
+    }
+
+    // Split the remaining room evenly, handing one side's unused share to the other
+    let context = width - matched_chars;
+    let left = before_chars.min((context / 2).max(context.saturating_sub(after_chars)));
+    let right = after_chars.min(context - left);
+
+    let shown_before: String = before.chars().skip(before_chars - left).collect();
+    let shown_after: String = after.chars().take(right).collect();
+
+    format!("{}{}{}{}{}",
+            if left < before_chars { "…" } else { "" },
+            shown_before.dimmed(),
+            matched.yellow().bold(),
+            shown_after.dimmed(),
+            if right < after_chars { "…" } else { "" })
+}
+
+/// What a result satisfied, e.g. "name prefix + content, 3 lines"
+pub fn match_provenance(result: &SearchResult) -> String {
+    let mut parts = Vec::new();
+    if let Some(name_match) = result.name_match {
+        match result.name_floor {
+            Some(floor) if name_match == crate::search::NameMatch::Fuzzy => parts.push(format!("{}, floor {}", name_match.label(), floor)),
+            _ => parts.push(name_match.label().to_string()),
+        }
+    }
+    if !result.content_matches.is_empty() {
+        let mut lines: Vec<usize> = result.content_matches.iter().map(|m| m.line_number).collect();
+        lines.dedup();
+        parts.push(format!("content, {} line{}", lines.len(), if lines.len() == 1 { "" } else { "s" }));
+    } else if parts.is_empty() {
+        // Matched a content query through NOT terms only, nothing to point at
+        parts.push("content, no lines to show".to_string());
+    }
+    parts.join(" + ")
+}
+
+/// Group content matches by the exact text that matched, most frequent first
+pub fn display_match_groups(results: &[SearchResult], max_groups: usize, use_pager: bool) {
+    let output = render_match_groups(results, max_groups);
+    if use_pager {
+        page_output(&output);
+    } else {
+        print!("{}", output);
+    }
+}
+
+/// --count output: the number of content matches per file, most first. The header is a
+/// decoration (stderr), the count lines go to stdout.
+pub fn display_counts(results: &[SearchResult], use_pager: bool) {
+    let counted: Vec<_> = results.iter().filter(|result| result.content_count > 0).collect();
+    if counted.is_empty() {
+        eprintln!();
+        eprintln!("{}", "No files found matching the pattern".bright_red());
+        return;
+    }
+    let total: usize = counted.iter().map(|result| result.content_count).sum();
+    eprintln!();
+    eprintln!("{} {} match(es) in {} file(s):", "✅".green(),
+              total.to_string().bright_green().bold(), counted.len().to_string().bright_green().bold());
+
+    let width = counted[0].content_count.to_string().len() + 1;
+    let mut out = String::new();
+    for result in counted {
+        // A scan cut short at max_content_bytes_per_file only counted part of the file
+        let count = format!("{}{}", result.content_count, if result.content_truncated { "+" } else { "" });
+        out.push_str(&format!("{:>width$}  {}\n", count.bright_green(), result_path(result).cyan(), width = width));
+    }
+    if use_pager {
+        page_output(&out);
+    } else {
+        print!("{}", out);
+    }
+}
+
+struct MatchGroup<'a> {
+    occurrences: usize,
+    files: usize,
+    examples: Vec<(&'a Path, String)>,  // Path and ":line" or "!/cell 2, line 3"
+}
+
+pub fn render_match_groups(results: &[SearchResult], max_groups: usize) -> String {
+    const MAX_EXAMPLES: usize = 3;
+
+    let mut groups: HashMap<String, MatchGroup> = HashMap::new();
+    for result in results {
+        let mut seen_in_file = HashSet::new();
+        for content_match in &result.content_matches {
+            let Some(text) = content_match.line_content.get(content_match.match_start..content_match.match_end) else {
+                continue;
+            };
+
+            let group = groups.entry(text.to_string()).or_insert(MatchGroup {
+                occurrences: 0,
+                files: 0,
+                examples: Vec::new(),
+            });
+            group.occurrences += 1;
+            if seen_in_file.insert(text) {
+                group.files += 1;
+            }
+            if group.examples.len() < MAX_EXAMPLES {
+                let position = match content_match.location {
+                    Some(ref location) => format!("!/{}", location),
+                    None => format!(":{}", content_match.line_number),
+                };
+                let position = match result.archive_entry {
+                    Some(ref entry) => format!("!/{}{}", entry, position),
+                    None => position,
+                };
+                group.examples.push((&result.path, position));
+            }
+        }
+    }
+
+    let mut out = String::new();
+    if groups.is_empty() {
+        out.push('\n');
+        out.push_str(&format!("{}\n", "No content matches to group".bright_red()));
+        return out;
+    }
+
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort_by(|a, b| b.1.occurrences.cmp(&a.1.occurrences).then_with(|| a.0.cmp(&b.0)));
+
+    out.push('\n');
+    out.push_str(&format!("{} Found {} distinct match(es) in {} file(s):\n",
+                          "✅".green(),
+                          groups.len().to_string().bright_green().bold(),
+                          results.len().to_string().bright_green().bold()));
+
+    for (index, (text, group)) in groups.iter().take(max_groups).enumerate() {
+        out.push('\n');
+        out.push_str(&format!("{} {} {} in {} file(s)\n",
+                              format!("{:2}", index + 1).bright_blue().bold(),
+                              format!("{:?}", text).white().bold(),
+                              format!("×{}", group.occurrences).green(),
+                              group.files));
+        for (path, position) in &group.examples {
+            out.push_str(&format!("    {}{}\n", display_path(path).dimmed(), position.blue()));
+        }
+        if group.occurrences > group.examples.len() {
+            out.push_str(&format!("    {} {} more...\n", "...".dimmed(), (group.occurrences - group.examples.len()).to_string().dimmed()));
+        }
+    }
+
+    if groups.len() > max_groups {
+        out.push('\n');
+        out.push_str(&format!("{} {} more distinct matches, raise {} to see them\n",
+                              "...".dimmed(), groups.len() - max_groups, "--limit".blue()));
+    }
+
+    out
+}
+
+/// Type key for the breakdown: ".rs", "(no extension)" or "dirs"
+fn result_type(result: &SearchResult) -> String {
     if result.is_dir {
-        return "📁";
+        return "dirs".to_string();
+    }
+    result_extension(result)
+        .map(|ext| format!(".{}", ext.to_lowercase()))
+        .unwrap_or_else(|| "(no extension)".to_string())
+}
+
+/// Result counts per type, most common first
+pub fn type_breakdown(results: &[SearchResult]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for result in results {
+        *counts.entry(result_type(result)).or_default() += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// "12 .rs, 4 .toml, 3 dirs"
+pub fn print_type_breakdown(breakdown: &[(String, usize)]) {
+    if breakdown.is_empty() {
+        return;
+    }
+    let parts: Vec<String> = breakdown.iter().map(|(kind, count)| format!("{} {}", count, kind.cyan())).collect();
+    eprintln!("{} Types: {}", "🧾".yellow(), parts.join(", "));
+}
+
+/// Keep only results of the most common type (ties go to the alphabetically first one)
+pub fn keep_top_type(results: &mut Vec<SearchResult>, breakdown: &[(String, usize)]) {
+    let Some((top, _)) = breakdown.first() else { return };
+    results.retain(|result| result_type(result) == *top);
+}
+
+/// Show output that is taller than the terminal through $PAGER, or a simple built-in pager
+pub fn page_output(output: &str) {
+    use std::io::IsTerminal;
+
+    let lines: Vec<&str> = output.lines().collect();
+    let page_height = terminal_size::terminal_size()
+        .map(|(_, terminal_size::Height(h))| h as usize)
+        .unwrap_or(24)
+        .saturating_sub(2)
+        .max(5);
+
+    if lines.len() <= page_height || !io::stdout().is_terminal() || !io::stdin().is_terminal() {
+        print!("{}", output);
+        return;
     }
 
-    match result.path.extension().and_then(|s| s.to_str()) {
+    if let Ok(pager) = std::env::var("PAGER")
+        && run_external_pager(&pager, output).is_ok()
+    {
+        return;
+    }
 
-        // Programming / Scripting
-        Some("rs")               => "🦀",
-        Some("js") | Some("ts")  => "📜",
-        Some("py")               => "🐍",
-        Some("java")             => "☕",
-        Some("cpp") | Some("cxx")| Some("cc") => "💠",
-        Some("c")                => "🔵",
-        Some("h") | Some("hpp")  => "📘",
-        Some("go")               => "🐹",
-        Some("rb")               => "💎",
-        Some("php")              => "🐘",
-        Some("sh") | Some("bash")=> "🐚",
-        Some("swift")            => "🍎",
-        Some("kt") | Some("kts") => "🤖",
-        Some("cs")               => "🎯",
+    for (page_index, page) in lines.chunks(page_height).enumerate() {
+        for line in page {
+            println!("{}", line);
+        }
 
-        // Data / Config
-        Some("json")             => "📋",
-        Some("yaml") | Some("yml") => "⚙️",
-        Some("toml")             => "🛠️",
-        Some("ini")              => "📑",
-        Some("csv")              => "📊",
-        Some("xml")              => "🗂️",
+        let shown = (page_index * page_height + page.len()).min(lines.len());
+        if shown >= lines.len() {
+            break;
+        }
 
-        // Markup / Docs
-        Some("md")               => "📝",
-        Some("txt")              => "📄",
-        Some("html") | Some("htm") => "🌐",
-        Some("css")              => "🎨",
-        Some("pdf")              => "📕",
+        eprint!("{} {}/{} lines - {} for more, {} to stop ",
+                "--More--".reversed(), shown, lines.len(), "Enter".green(), "q".bright_red());
+        io::stderr().flush().unwrap();
 
-        // Images
-        Some("png") | Some("jpg") | Some("jpeg") | Some("gif") | Some("bmp") | Some("svg") => "🖼️",
-        Some("ico")              => "🔖",
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input).is_err() || input.trim().eq_ignore_ascii_case("q") {
+            eprintln!("{} {} more lines hidden", "...".dimmed(), (lines.len() - shown).to_string().dimmed());
+            break;
+        }
+    }
+}
+
+fn run_external_pager(pager: &str, output: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use std::process::{Command, Stdio};
 
-        // Video
-        Some("mp4") | Some("mkv") | Some("avi") | Some("mov") | Some("webm") => "🎬",
+    let mut parts = pager.split_whitespace();
+    let program = parts.next().ok_or("PAGER is empty")?;
 
-        // Audio
-        Some("mp3") | Some("wav") | Some("flac") | Some("ogg") | Some("m4a") => "🎵",
+    let mut command = Command::new(program);
+    command.args(parts).stdin(Stdio::piped());
+    // The output is colored, plain less shows the escape codes unless told to pass them through (R).
+    // Like git, also quit when it fits on one screen (F) and stay off the alternate screen (X), so
+    // the results are still visible when a --copy or --terminal selection prompt follows.
+    if Path::new(program).file_stem().is_some_and(|stem| stem == "less") {
+        let options = std::env::var("LESS").unwrap_or_default();
+        command.env("LESS", format!("{}FRX", options));
+    }
+    let mut child = command.spawn()?;
 
-        // Archives
-        Some("zip") | Some("tar") | Some("gz") | Some("bz2") | Some("xz") | Some("7z") => "📦",
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may exit early (user pressed q), so a broken pipe is fine here
+        let _ = stdin.write_all(output.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
 
-        // Misc
-        Some("exe") | Some("bin") | Some("dll") => "⚙️",
-        Some("lock")             => "🔒",
-        Some("log")              => "📜",
+pub fn get_file_icon(result: &SearchResult) -> &'static str {
+    if result.is_dir {
+        return "📁";
+    }
+    crate::file_types::icon(result_extension(result).as_deref())
+}
 
-        _ => "📄", // Default file
+/// How a result is shown: its path, followed by `!/entry` for a file inside an archive
+pub fn result_path(result: &SearchResult) -> String {
+    match result.archive_entry {
+        Some(ref entry) => format!("{}!/{}", display_path(&result.path), entry),
+        None => display_path(&result.path),
     }
+}
 
+/// Extension of the result's own name, the entry's for a file inside an archive
+pub fn result_extension(result: &SearchResult) -> Option<String> {
+    let name = match result.archive_entry {
+        Some(ref entry) => Path::new(entry),
+        None => result.path.as_path(),
+    };
+    name.extension().map(|ext| ext.to_string_lossy().into_owned())
 }
 
 pub fn format_size(bytes: u64) -> String {
@@ -151,6 +442,56 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
+/// A path made safe to print. Control characters (newlines, tabs, terminal escapes) and bytes
+/// that aren't UTF-8 are shown escaped, so a hostile file name can't corrupt the output.
+/// Actions and --print0 keep using the raw path.
+pub fn display_path(path: &Path) -> String {
+    let mut out = String::new();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        for chunk in path.as_os_str().as_bytes().utf8_chunks() {
+            push_escaped(&mut out, chunk.valid());
+            for byte in chunk.invalid() {
+                out.push_str(&format!("\\x{:02x}", byte));
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    push_escaped(&mut out, &path.to_string_lossy());
+
+    out
+}
+
+fn push_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Result paths separated by NUL bytes, exactly as stored on disk, for `xargs -0`
+pub fn print_null_separated(results: &[SearchResult]) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    for result in results {
+        #[cfg(unix)]
+        stdout.write_all(std::os::unix::ffi::OsStrExt::as_bytes(result.path.as_os_str()))?;
+        #[cfg(not(unix))]
+        stdout.write_all(result.path.to_string_lossy().as_bytes())?;
+        if let Some(ref entry) = result.archive_entry {
+            stdout.write_all(format!("!/{}", entry).as_bytes())?;
+        }
+        stdout.write_all(b"\0")?;
+    }
+    stdout.flush()
+}
+
 pub fn format_time_ago(elapsed: std::time::Duration) -> String {
     let secs = elapsed.as_secs();
 
@@ -165,7 +506,7 @@ pub fn format_time_ago(elapsed: std::time::Duration) -> String {
     }
 }
 
-pub fn get_file_metadata(entry: &walkdir::DirEntry) -> (Option<u64>, Option<std::time::SystemTime>) {
+pub fn get_file_metadata(entry: &crate::walker::WalkEntry) -> (Option<u64>, Option<u64>, Option<std::time::SystemTime>) {
     match entry.metadata() {
         Ok(meta) => (
             if meta.is_file() {
@@ -173,34 +514,231 @@ pub fn get_file_metadata(entry: &walkdir::DirEntry) -> (Option<u64>, Option<std:
             } else {
                 None
             },
+            sparse_disk_size(&meta),
             meta.modified().ok()
         ),
-        Err(_) => (None, None)
+        Err(_) => (None, None, None)
     }
 }
 
+/// Bytes actually allocated for a regular file, when that's less than its apparent size
+#[cfg(unix)]
+pub fn sparse_disk_size(meta: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
 
-pub fn interactive_select(results: &[SearchResult]) -> Option<&SearchResult> {
+    // st_blocks is always in 512-byte units, whatever the filesystem block size
+    let allocated = meta.blocks() * 512;
+    (meta.is_file() && allocated < meta.len()).then_some(allocated)
+}
+
+#[cfg(not(unix))]
+pub fn sparse_disk_size(_meta: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// FIFOs, sockets and device nodes. Reading them as files can block forever or never end.
+#[cfg(unix)]
+pub fn is_special_file(file_type: &std::fs::FileType) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    file_type.is_fifo() || file_type.is_socket() || file_type.is_char_device() || file_type.is_block_device()
+}
+
+#[cfg(not(unix))]
+pub fn is_special_file(_file_type: &std::fs::FileType) -> bool {
+    false
+}
+
+
+/// Replace `path` with `content` via a temp file in the same directory and a rename,
+/// so readers (and crashes) only ever see the old or the new file, never a partial one.
+/// The existing file's permissions carry over to the new one.
+pub fn write_atomically(path: &Path, content: &str) -> io::Result<()> {
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = std::path::PathBuf::from(temp_name);
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            std::fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        std::fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Per-user cache directory for ff (LOCALAPPDATA, ~/Library/Caches or XDG_CACHE_HOME)
+pub fn cache_dir() -> Option<std::path::PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("LOCALAPPDATA").map(std::path::PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| Path::new(&home).join("Library").join("Caches"))
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(std::path::PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+    };
+    base.map(|dir| dir.join("ff"))
+}
+
+/// Per-user config directory for ff (APPDATA, ~/Library/Application Support or XDG_CONFIG_HOME)
+pub fn config_dir() -> Option<std::path::PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(std::path::PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| Path::new(&home).join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(std::path::PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+    };
+    base.map(|dir| dir.join("ff"))
+}
+
+/// OFFLINE | RECALL_ON_OPEN | RECALL_ON_DATA_ACCESS, set on files whose data lives in the cloud
+#[cfg(target_os = "windows")]
+const CLOUD_PLACEHOLDER_ATTRIBUTES: u32 = 0x1000 | 0x40000 | 0x400000;
+
+/// Cloud-only placeholders (OneDrive/Dropbox/iCloud on-demand files) that reading would download
+#[cfg(target_os = "windows")]
+pub fn is_cloud_placeholder(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    std::fs::symlink_metadata(path)
+        .map(|meta| meta.file_attributes() & CLOUD_PLACEHOLDER_ATTRIBUTES != 0)
+        .unwrap_or(false)
+}
+
+/// Cloud-only placeholders (iCloud Drive / File Provider dataless files) that reading would download
+#[cfg(target_os = "macos")]
+pub fn is_cloud_placeholder(path: &Path) -> bool {
+    use std::os::macos::fs::MetadataExt;
+
+    const SF_DATALESS: u32 = 0x40000000;
+
+    std::fs::symlink_metadata(path)
+        .map(|meta| meta.st_flags() & SF_DATALESS != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn is_cloud_placeholder(_path: &Path) -> bool {
+    false
+}
+
+/// Entries --skip-reparse leaves out: Windows reparse points, or symlinks elsewhere
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub enum ReparseKind {
+    Symlink,
+    DirLink,           // Junction or directory symlink, both look the same without the reparse tag
+    CloudPlaceholder,  // OneDrive and other cloud-provider files and folders
+    Other,
+}
+
+impl ReparseKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ReparseKind::Symlink => "symlink",
+            ReparseKind::DirLink => "junction/dir symlink",
+            ReparseKind::CloudPlaceholder => "cloud placeholder",
+            ReparseKind::Other => "other reparse point",
+        }
+    }
+}
+
+/// Classify an entry from its own (not followed) metadata
+#[cfg(windows)]
+pub fn reparse_kind(meta: &std::fs::Metadata) -> Option<ReparseKind> {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+
+    let attributes = meta.file_attributes();
+    if attributes & FILE_ATTRIBUTE_REPARSE_POINT == 0 {
+        return None;
+    }
+    // std reports name-surrogate reparse points (symlinks and junctions) as symlinks
+    let kind = if meta.file_type().is_symlink() {
+        if attributes & FILE_ATTRIBUTE_DIRECTORY != 0 { ReparseKind::DirLink } else { ReparseKind::Symlink }
+    } else if attributes & CLOUD_PLACEHOLDER_ATTRIBUTES != 0 {
+        ReparseKind::CloudPlaceholder
+    } else {
+        ReparseKind::Other
+    };
+    Some(kind)
+}
+
+#[cfg(not(windows))]
+pub fn reparse_kind(meta: &std::fs::Metadata) -> Option<ReparseKind> {
+    meta.file_type().is_symlink().then_some(ReparseKind::Symlink)
+}
+
+/// Hidden means a dotfile everywhere, plus FILE_ATTRIBUTE_HIDDEN / SYSTEM on Windows.
+/// Metadata is only requested on Windows, where walkers already have it cached.
+pub fn is_hidden(name: &std::ffi::OsStr, depth: usize, metadata: impl FnOnce() -> Option<std::fs::Metadata>) -> bool {
+    let is_dotfile = name
+        .to_str()
+        .map(|name| name.starts_with('.') && name.len() > 1)
+        .unwrap_or(false);
+
+    // Drive roots like C:\ carry HIDDEN|SYSTEM, so never apply attributes to the root itself
+    is_dotfile || (depth > 0 && has_hidden_attribute(metadata))
+}
+
+#[cfg(target_os = "windows")]
+fn has_hidden_attribute(metadata: impl FnOnce() -> Option<std::fs::Metadata>) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+
+    metadata()
+        .map(|meta| meta.file_attributes() & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM) != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn has_hidden_attribute(_metadata: impl FnOnce() -> Option<std::fs::Metadata>) -> bool {
+    false
+}
+
+/// What the user picked from the numbered results
+pub enum Selection<'a> {
+    Result(&'a SearchResult),
+    NarrowTo(std::path::PathBuf),  // Re-run the search inside this directory
+}
+
+pub fn interactive_select(results: &[SearchResult]) -> Option<Selection<'_>> {
     if results.is_empty() {
         return None;
     }
 
     if results.len() == 1 {
-        println!();
-        println!("Auto-selecting the only match...");
-        return Some(&results[0]);
+        eprintln!();
+        eprintln!("Auto-selecting the only match...");
+        return Some(Selection::Result(&results[0]));
     }
 
-    println!();
+    eprintln!();
     loop {
-        print!(
-            "{} Enter number ({}-{}) or '{}' to quit: ",
+        eprint!(
+            "{} Enter number ({}-{}), '{}' to search only its directory, or '{}' to quit: ",
             "❓".cyan(),
             "1".bright_green(),
             results.len().to_string().bright_green(),
+            "d<number>".bright_yellow(),
             "q".bright_red()
         );
-        io::stdout().flush().unwrap();
+        io::stderr().flush().unwrap();
 
         let mut input = String::new();
         if io::stdin().read_line(&mut input).is_err() {
@@ -210,31 +748,94 @@ pub fn interactive_select(results: &[SearchResult]) -> Option<&SearchResult> {
         let input = input.trim().to_lowercase();
 
         if input == "q" || input == "quit" || input == "exit" {
-            println!("Selection cancelled");
+            eprintln!("Selection cancelled");
             return None;
         }
 
-        if let Ok(num) = input.parse::<usize>() {
-            if num >= 1 && num <= results.len() {
-                return Some(&results[num - 1]);
+        let (narrow, number) = match input.strip_prefix('d') {
+            Some(rest) => (true, rest.trim()),
+            None => (false, input.as_str()),
+        };
+        if let Ok(num) = number.parse::<usize>()
+            && num >= 1 && num <= results.len()
+        {
+            let result = &results[num - 1];
+            if !narrow {
+                return Some(Selection::Result(result));
+            }
+            // A directory result narrows to itself, a file (or anything inside an archive) to the directory holding it
+            let dir = if result.is_dir && result.archive_entry.is_none() { Some(result.path.as_path()) } else { result.path.parent() };
+            if let Some(dir) = dir.filter(|dir| !dir.as_os_str().is_empty()) {
+                return Some(Selection::NarrowTo(dir.to_path_buf()));
             }
         }
 
-        println!("{} Invalid selection. Please enter a number between 1-{} or 'q' to quit.",
+        eprintln!("{} Invalid selection. Please enter a number between 1-{}, d<number>, or 'q' to quit.",
                  "❌".red(), results.len());
     }
 }
 
-pub fn copy_to_clipboard(text: &str) -> Result<(), Box<dyn std::error::Error>> {
-    use arboard::Clipboard;
-    let mut clipboard = Clipboard::new()?;
-    clipboard.set_text(text)?;
-    println!();
-    println!("{} Path copied to clipboard:", "📋".green());
-    println!("   {}", text.cyan());
+/// Copy text with the configured backend: "system" (arboard), "osc52" (terminal escape
+/// sequence, works over SSH) or "auto", which tries the system clipboard first
+pub fn copy_to_clipboard(text: &str, backend: &str) -> Result<(), String> {
+    let destination = match backend.to_lowercase().as_str() {
+        "system" => copy_with_system_clipboard(text).map(|_| "clipboard")?,
+        "osc52" => copy_with_osc52(text).map(|_| "terminal clipboard (OSC 52)")?,
+        _ => match copy_with_system_clipboard(text) {
+            Ok(()) => "clipboard",
+            Err(system_error) => copy_with_osc52(text)
+                .map(|_| "terminal clipboard (OSC 52)")
+                .map_err(|osc_error| format!("{}, and OSC 52 fallback failed: {}", system_error, osc_error))?,
+        },
+    };
+    eprintln!();
+    eprintln!("{} Path copied to {}:", "📋".green(), destination);
+    eprintln!("   {}", text.cyan());
     Ok(())
 }
 
+fn copy_with_system_clipboard(text: &str) -> Result<(), String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| format!("system clipboard unavailable ({})", e))
+}
+
+/// Ask the terminal to set the clipboard. There's no acknowledgement, so this only
+/// fails when there's no terminal to ask; terminals may still ignore the request.
+fn copy_with_osc52(text: &str) -> Result<(), String> {
+    use std::io::IsTerminal;
+
+    if !io::stderr().is_terminal() {
+        return Err("stderr is not a terminal".to_string());
+    }
+    let sequence = format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()));
+    // tmux only forwards escape sequences wrapped in its passthrough
+    let sequence = if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    };
+    let mut stderr = io::stderr();
+    stderr.write_all(sequence.as_bytes()).and_then(|_| stderr.flush()).map_err(|e| e.to_string())
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
 pub fn change_directory(path: &Path) {
     let dir = if path.is_file() {
         path.parent().unwrap_or(path)
@@ -242,19 +843,82 @@ pub fn change_directory(path: &Path) {
         path
     };
 
-    println!();
-    println!("{} Opening new terminal in: {}", "🚀".green(), dir.display().to_string().cyan());
+    eprintln!();
+    eprintln!("{} Opening new terminal in: {}", "🚀".green(), display_path(dir).cyan());
 
     if let Err(e) = spawn_terminal(dir) {
         eprintln!("{} Failed to open terminal: {}", "❌".red(), e);
-        println!("{} Fallback - copy this command:", "💡".yellow());
+        eprintln!("{} Fallback - copy this command:", "💡".yellow());
 
         #[cfg(target_os = "windows")]
         println!("cd /d {}", dir.display());
 
         #[cfg(not(target_os = "windows"))]
-        println!("cd \"{}\"", dir.display());
+        println!("cd {}", crate::hooks::shell_quote(&dir.to_string_lossy()));
+    }
+}
+
+/// --link-to: put a link to the result in `dir`, numbering the name when it's taken
+pub fn link_into(path: &Path, dir: &Path) {
+    match create_link(path, dir) {
+        Ok(link) => eprintln!("{} Linked {} as {}", "🔗".green(), display_path(path).cyan(), display_path(&link).cyan()),
+        Err(e) => eprintln!("{} Couldn't link {} into {}: {}", "❌".red(), display_path(path).red(), display_path(dir), e),
+    }
+}
+
+fn create_link(path: &Path, dir: &Path) -> io::Result<std::path::PathBuf> {
+    let target = std::path::absolute(path)?;
+    let name = target.file_name().ok_or_else(|| io::Error::other("the result has no file name"))?;
+    std::fs::create_dir_all(dir)?;
+
+    // Results from different folders often share a name (mod.rs, index.js): mod (2).rs
+    let stem = Path::new(name).file_stem().unwrap_or(name).to_string_lossy();
+    let extension = Path::new(name).extension().map(|ext| format!(".{}", ext.to_string_lossy()));
+    let mut link = dir.join(name);
+    let mut number = 2;
+    while link.symlink_metadata().is_ok() {
+        if std::fs::read_link(&link).is_ok_and(|existing| existing == target) {
+            return Ok(link);  // Already in the set
+        }
+        link = dir.join(format!("{} ({}){}", stem, number, extension.as_deref().unwrap_or("")));
+        number += 1;
+    }
+
+    platform_link(&target, &link)
+}
+
+#[cfg(unix)]
+fn platform_link(target: &Path, link: &Path) -> io::Result<std::path::PathBuf> {
+    std::os::unix::fs::symlink(target, link)?;
+    Ok(link.to_path_buf())
+}
+
+/// Symlinks need Developer Mode or admin rights on Windows, fall back to a .lnk shortcut
+#[cfg(windows)]
+fn platform_link(target: &Path, link: &Path) -> io::Result<std::path::PathBuf> {
+    let symlink = if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    };
+    if symlink.is_ok() {
+        return Ok(link.to_path_buf());
+    }
+
+    let mut shortcut = link.as_os_str().to_owned();
+    shortcut.push(".lnk");
+    let quote = |path: &std::ffi::OsStr| format!("'{}'", path.to_string_lossy().replace('\'', "''"));
+    let script = format!(
+        "$s = (New-Object -ComObject WScript.Shell).CreateShortcut({}); $s.TargetPath = {}; $s.Save()",
+        quote(&shortcut), quote(target.as_os_str()),
+    );
+    let status = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other("creating the shortcut failed"));
     }
+    Ok(std::path::PathBuf::from(shortcut))
 }
 
 pub fn spawn_terminal(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
@@ -277,20 +941,21 @@ pub fn spawn_terminal(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
 
     #[cfg(target_os = "linux")]
     {
-        let path_str = path.display().to_string(); // Create string once
+        // The raw path, so names that aren't UTF-8 still open
+        let path_arg = path.as_os_str();
 
         let terminals = [
-            ("gnome-terminal", vec!["--working-directory", &path_str]),
-            ("konsole", vec!["--workdir", &path_str]),
-            ("xfce4-terminal", vec!["--working-directory", &path_str]),
-            ("alacritty", vec!["--working-directory", &path_str]),
-            ("kitty", vec!["--directory", &path_str]),
-            ("wezterm", vec!["start", "--cwd", &path_str]),
+            ("gnome-terminal", vec!["--working-directory"]),
+            ("konsole", vec!["--workdir"]),
+            ("xfce4-terminal", vec!["--working-directory"]),
+            ("alacritty", vec!["--working-directory"]),
+            ("kitty", vec!["--directory"]),
+            ("wezterm", vec!["start", "--cwd"]),
         ];
 
         let mut success = false;
         for (terminal, args) in &terminals {
-            if Command::new(terminal).args(args).spawn().is_ok() {
+            if Command::new(terminal).args(args).arg(path_arg).spawn().is_ok() {
                 success = true;
                 break;
             }
@@ -0,0 +1,408 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{FileType, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use clap::ValueEnum;
+use crate::ignore_files::IgnoreFiles;
+use crate::ignore_rules::IgnoreRules;
+use crate::util;
+use crate::watchdog::StallGuard;
+
+/// Directory traversal backends selectable with --backend / config `walker_backend`
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Single-threaded walkdir traversal (default)
+    #[default]
+    Walkdir,
+    /// Multi-threaded traversal using the ignore crate's parallel walker
+    Ignore,
+}
+
+impl Backend {
+    /// Parse the config spelling, unknown names fall back to the default backend
+    pub fn from_config(name: &str) -> Self {
+        Backend::from_str(name, true).unwrap_or_default()
+    }
+}
+
+/// One enumerated entry, independent of the backend that produced it
+pub struct WalkEntry {
+    path: PathBuf,
+    depth: usize,
+    file_type: FileType,
+    follow_links: bool,
+}
+
+impl WalkEntry {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.file_type.is_dir()
+    }
+
+    /// FIFO, socket or device node (judging a symlink by its target, since reads follow it)
+    pub fn is_special(&self) -> bool {
+        if self.file_type.is_symlink() {
+            return std::fs::metadata(&self.path).is_ok_and(|m| util::is_special_file(&m.file_type()));
+        }
+        util::is_special_file(&self.file_type)
+    }
+
+    /// Metadata of the entry, following symlinks only when the walk does
+    pub fn metadata(&self) -> io::Result<Metadata> {
+        if self.follow_links {
+            std::fs::metadata(&self.path)
+        } else {
+            std::fs::symlink_metadata(&self.path)
+        }
+    }
+}
+
+/// What makes two paths the same directory when symlinks lead to it more than once: device
+/// and inode on Unix, the fully resolved path elsewhere
+#[cfg(unix)]
+pub type DirKey = (u64, u64);
+#[cfg(not(unix))]
+pub type DirKey = PathBuf;
+
+#[cfg(unix)]
+fn dir_key(path: &Path) -> Option<DirKey> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_key(path: &Path) -> Option<DirKey> {
+    std::fs::canonicalize(path).ok()
+}
+
+/// Traversal rules every backend applies before yielding or descending into an entry
+pub struct EntryFilter {
+    pub root: PathBuf,
+    pub include_hidden: bool,
+    pub follow_symlinks: bool,
+    pub ignore_rules: IgnoreRules,
+    pub ignore_files: Option<IgnoreFiles>,  // .gitignore and friends, None with --no-ignore
+    pub only_dirs: Vec<String>,
+    pub min_depth: usize,          // Entries shallower than this are descended into but not yielded
+    pub max_depth: Option<usize>,  // Entries deeper than this are never read
+    pub running: Arc<AtomicBool>,
+    pub stall_guard: Option<StallGuard>,
+    pub dir_entry_cap: Option<usize>,
+    pub dir_entries: Mutex<HashMap<PathBuf, usize>>,  // Entries seen so far per parent directory
+    pub capped_dirs: Mutex<Vec<PathBuf>>,
+    pub skip_reparse: bool,
+    pub skipped_reparse: Mutex<Vec<(PathBuf, util::ReparseKind)>>,
+    pub skipped_build_dirs: Mutex<Vec<(PathBuf, &'static str)>>,
+    pub skipped_caches: Mutex<Vec<PathBuf>>,
+    pub skipped_ignored: Mutex<Vec<PathBuf>>,
+    pub git_files: Option<Arc<crate::git::GitFiles>>,
+    pub visited_dirs: Mutex<HashSet<DirKey>>,  // With follow_symlinks, every directory entered so far
+    pub skipped_loops: Mutex<Vec<PathBuf>>,
+}
+
+impl EntryFilter {
+    /// Shared decision for cancellation, hidden entries, ignore rules and stall detection
+    pub fn allows(
+        &self,
+        path: &Path,
+        depth: usize,
+        is_dir: bool,
+        metadata: impl FnOnce() -> Option<Metadata>,
+    ) -> bool {
+        if !self.running.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let name = path.file_name().unwrap_or(path.as_os_str());
+
+        // --only-dirs: everything directly under the root must be one of the named directories
+        if depth == 1 && !self.only_dirs.is_empty() && !(is_dir && self.only_dirs.iter().any(|dir| name == dir.as_str())) {
+            return false;
+        }
+
+        // Hidden only applies below what was asked for by name: the root itself (`--path ~/.config`),
+        // an --only-dirs directory or an --include-dir directory
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        if !self.include_hidden
+            && util::is_hidden(name, depth, metadata)
+            && !(depth == 0
+                || (depth == 1 && !self.only_dirs.is_empty())
+                || (is_dir && self.ignore_rules.explicitly_included(relative)))
+        {
+            return false;
+        }
+
+        // --git-tracked and friends: files git didn't list, and directories without any, never show up
+        if let Some(git_files) = &self.git_files
+            && !git_files.contains(path, is_dir)
+        {
+            return false;
+        }
+
+        // --exclude runs before any scoring or content scan, and prunes excluded directories
+        if self.ignore_rules.is_excluded(relative) {
+            return false;
+        }
+
+        // Use config ignore rules (gitignore-style, relative to the search root)
+        if self.ignore_rules.is_ignored(relative, is_dir) {
+            return false;
+        }
+
+        // Ignore files on disk, unless --include-dir asked for the directory by name
+        if let Some(ignore_files) = &self.ignore_files
+            && !(is_dir && self.ignore_rules.explicitly_included(relative))
+            && ignore_files.is_ignored(path, is_dir)
+        {
+            self.skipped_ignored.lock().unwrap().push(path.to_path_buf());
+            return false;
+        }
+
+        if is_dir && self.ignore_rules.is_tagged_cache(path, relative) {
+            self.skipped_caches.lock().unwrap().push(path.to_path_buf());
+            return false;
+        }
+        if is_dir && let Some(reason) = self.ignore_rules.build_output_reason(path, relative) {
+            self.skipped_build_dirs.lock().unwrap().push((path.to_path_buf(), reason));
+            return false;
+        }
+
+        // --skip-reparse: look at the entry itself, even when the walk follows links
+        if self.skip_reparse
+            && depth > 0
+            && let Some(kind) = std::fs::symlink_metadata(path).ok().as_ref().and_then(util::reparse_kind)
+        {
+            self.skipped_reparse.lock().unwrap().push((path.to_path_buf(), kind));
+            return false;
+        }
+
+        // Following symlinks, a link to a directory that was already entered (back up the tree, or
+        // a second link to one place) is skipped, so cycles end
+        if is_dir && self.follow_symlinks && !self.first_visit(path) {
+            self.skipped_loops.lock().unwrap().push(path.to_path_buf());
+            return false;
+        }
+
+        // Skip directories that stall on enumeration (dead mounts, cloud folders)
+        match &self.stall_guard {
+            Some(guard) if is_dir => guard.is_responsive(path),
+            _ => true,
+        }
+    }
+
+    /// Records the directory as entered. Only a symlink can be a repeat visit, the real
+    /// directory is always walked even if a link led there first.
+    fn first_visit(&self, path: &Path) -> bool {
+        let Some(key) = dir_key(path) else { return true };
+        let new = self.visited_dirs.lock().unwrap().insert(key);
+        new || !std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+    }
+
+    /// Whether the entry's parent already yielded `dir_entry_cap` entries. Checked before `allows`
+    /// and counting every entry, since reading a directory costs the same whether entries pass or not.
+    pub fn over_dir_cap(&self, path: &Path, depth: usize) -> bool {
+        let (Some(cap), Some(parent)) = (self.dir_entry_cap, path.parent().filter(|_| depth > 0)) else {
+            return false;
+        };
+        let mut dir_entries = self.dir_entries.lock().unwrap();
+        let seen = dir_entries.entry(parent.to_path_buf()).or_default();
+        *seen += 1;
+        if *seen == cap + 1 {
+            self.capped_dirs.lock().unwrap().push(parent.to_path_buf());
+        }
+        *seen > cap
+    }
+
+    /// Directories cut short by the entry cap
+    pub fn capped_dirs(&self) -> Vec<PathBuf> {
+        self.capped_dirs.lock().unwrap().clone()
+    }
+
+    /// Directories the stall guard gave up on during the walk
+    pub fn abandoned_dirs(&self) -> Vec<PathBuf> {
+        self.stall_guard
+            .as_ref()
+            .map(StallGuard::abandoned)
+            .unwrap_or_default()
+    }
+
+    /// Directories the build-output heuristics skipped, with the marker that gave them away
+    pub fn skipped_build_dirs(&self) -> Vec<(PathBuf, &'static str)> {
+        self.skipped_build_dirs.lock().unwrap().clone()
+    }
+
+    /// Directories skipped for holding a CACHEDIR.TAG
+    pub fn skipped_caches(&self) -> Vec<PathBuf> {
+        self.skipped_caches.lock().unwrap().clone()
+    }
+
+    /// Entries left out because an ignore file listed them
+    pub fn skipped_ignored(&self) -> Vec<PathBuf> {
+        self.skipped_ignored.lock().unwrap().clone()
+    }
+
+    /// Directories left out for having been walked already through another symlink
+    pub fn skipped_loops(&self) -> Vec<PathBuf> {
+        self.skipped_loops.lock().unwrap().clone()
+    }
+
+    /// Entries left out by --skip-reparse, with what they were
+    pub fn skipped_reparse_points(&self) -> Vec<(PathBuf, util::ReparseKind)> {
+        self.skipped_reparse.lock().unwrap().clone()
+    }
+}
+
+/// A traversal backend. Search code only sees the entry stream, so adding a
+/// backend never requires touching the matching logic.
+pub trait Walker {
+    fn name(&self) -> &'static str;
+
+    /// Enumerate entries under `filter.root` that pass the filter
+    fn walk(&self, filter: Arc<EntryFilter>) -> Box<dyn Iterator<Item = io::Result<WalkEntry>>>;
+}
+
+pub fn for_backend(backend: Backend) -> Box<dyn Walker> {
+    match backend {
+        Backend::Walkdir => Box::new(WalkDirWalker),
+        Backend::Ignore => Box::new(IgnoreWalker),
+    }
+}
+
+pub struct WalkDirWalker;
+
+impl Walker for WalkDirWalker {
+    fn name(&self) -> &'static str {
+        "walkdir"
+    }
+
+    fn walk(&self, filter: Arc<EntryFilter>) -> Box<dyn Iterator<Item = io::Result<WalkEntry>>> {
+        let follow_links = filter.follow_symlinks;
+        let mut walk = walkdir::WalkDir::new(&filter.root)
+            .follow_links(follow_links)
+            .max_depth(filter.max_depth.unwrap_or(usize::MAX))
+            .into_iter();
+
+        // Filtered by hand rather than with filter_entry, which can only skip the rejected entry
+        // itself. Past the entry cap the rest of the parent directory is skipped unread.
+        let entries = std::iter::from_fn(move || loop {
+            let e = match walk.next()? {
+                Ok(e) => e,
+                Err(e) => {
+                    // Keep the io error kind but also the path walkdir reports
+                    let kind = e.io_error().map(|io| io.kind()).unwrap_or(io::ErrorKind::Other);
+                    return Some(Err(io::Error::new(kind, e)));
+                }
+            };
+            let is_dir = e.file_type().is_dir();
+
+            if filter.over_dir_cap(e.path(), e.depth()) {
+                // A yielded directory is already the current one, its parent is next in line
+                if is_dir {
+                    walk.skip_current_dir();
+                }
+                walk.skip_current_dir();
+                continue;
+            }
+            if !filter.allows(e.path(), e.depth(), is_dir, || e.metadata().ok()) {
+                if is_dir {
+                    walk.skip_current_dir();
+                }
+                continue;
+            }
+            // Not walkdir's own min_depth, which would hide shallow ignored directories from the filter
+            if e.depth() < filter.min_depth {
+                continue;
+            }
+
+            return Some(Ok(WalkEntry {
+                depth: e.depth(),
+                file_type: e.file_type(),
+                path: e.into_path(),
+                follow_links,
+            }));
+        });
+
+        Box::new(entries)
+    }
+}
+
+pub struct IgnoreWalker;
+
+impl Walker for IgnoreWalker {
+    fn name(&self) -> &'static str {
+        "ignore (parallel)"
+    }
+
+    fn walk(&self, filter: Arc<EntryFilter>) -> Box<dyn Iterator<Item = io::Result<WalkEntry>>> {
+        let follow_links = filter.follow_symlinks;
+        let (tx, rx) = mpsc::channel();
+
+        let mut builder = ignore::WalkBuilder::new(&filter.root);
+        builder
+            .follow_links(follow_links)
+            .max_depth(filter.max_depth)
+            // ff applies its own hidden/ignore rules through EntryFilter
+            .standard_filters(false)
+            .filter_entry({
+                let filter = filter.clone();
+                move |e| {
+                    let is_dir = e.file_type().is_some_and(|t| t.is_dir());
+                    // The parallel walker can't stop reading a directory early, the cap only saves the per-entry work
+                    !filter.over_dir_cap(e.path(), e.depth())
+                        && filter.allows(e.path(), e.depth(), is_dir, || e.metadata().ok())
+                }
+            });
+
+        let walker = builder.build_parallel();
+        thread::Builder::new()
+            .name("ff-walk".to_string())
+            .spawn(move || {
+                walker.run(|| {
+                    let tx = tx.clone();
+                    let running = filter.running.clone();
+                    let min_depth = filter.min_depth;
+                    Box::new(move |result| {
+                        let item = match result {
+                            Ok(entry) if entry.depth() < min_depth => return ignore::WalkState::Continue,
+                            Ok(entry) => match entry.file_type() {
+                                Some(file_type) => Ok(WalkEntry {
+                                    depth: entry.depth(),
+                                    file_type,
+                                    path: entry.into_path(),
+                                    follow_links,
+                                }),
+                                None => return ignore::WalkState::Continue, // stdin entry
+                            },
+                            // Keep the io error kind and the ignore error, it knows the path
+                            Err(e) => Err(io::Error::new(e.io_error().map_or(io::ErrorKind::Other, |io| io.kind()), e)),
+                        };
+
+                        // Stop early when the consumer is gone or the search was cancelled
+                        if tx.send(item).is_err() || !running.load(Ordering::SeqCst) {
+                            ignore::WalkState::Quit
+                        } else {
+                            ignore::WalkState::Continue
+                        }
+                    })
+                });
+            })
+            .expect("Error spawning walker thread");
+
+        Box::new(rx.into_iter())
+    }
+}
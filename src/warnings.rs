@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use colored::Colorize;
+use crate::util::{display_path, format_size, ReparseKind};
+
+/// How much an issue affects the results
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Notice,   // Skipped on purpose, results are still what was asked for
+    Warning,  // Results may be incomplete
+    Error,    // Something that should have been searched couldn't be
+}
+
+impl Severity {
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Notice => "notice",
+            Severity::Warning => "warn",
+            Severity::Error => "error",
+        }
+    }
+
+    fn tag(self) -> colored::ColoredString {
+        match self {
+            Severity::Notice => self.label().cyan(),
+            Severity::Warning => self.label().yellow(),
+            Severity::Error => self.label().red(),
+        }
+    }
+}
+
+struct Issue {
+    severity: Severity,
+    category: &'static str,
+    summary: String,
+    details: Vec<String>,  // Listed in verbose mode
+}
+
+/// Non-fatal issues collected while walking and searching, printed as one
+/// summary after the results instead of in between progress lines
+#[derive(Default)]
+pub struct Warnings {
+    issues: Vec<Issue>,
+    walk_errors: HashMap<&'static str, Vec<String>>,
+    denied: Vec<PathBuf>,  // Entries the walk or a content scan wasn't allowed to read
+}
+
+impl Warnings {
+    fn push(&mut self, severity: Severity, category: &'static str, summary: String, details: Vec<String>) {
+        self.issues.push(Issue { severity, category, summary, details });
+    }
+
+    /// An error the walker hit for one entry, grouped by kind
+    pub fn walk_error(&mut self, error: &io::Error) {
+        let message = error.to_string();
+        let kind = if error.kind() == io::ErrorKind::PermissionDenied || message.contains("Permission denied") {
+            self.denied.extend(error_path(error));
+            "permission denied"
+        } else if message.contains("File system loop") || message.contains("Too many levels of symbolic links") {
+            "symlink cycle"
+        } else {
+            "other"
+        };
+        self.walk_errors.entry(kind).or_default().push(message);
+    }
+
+    /// Directories given up on after the stall timeout
+    pub fn abandoned_dirs(&mut self, paths: &[PathBuf]) {
+        if paths.is_empty() {
+            return;
+        }
+        self.push(Severity::Warning, "stalled",
+                  format!("Skipped {} unresponsive director{} (stall timeout)",
+                          paths.len(), if paths.len() == 1 { "y" } else { "ies" }),
+                  paths.iter().map(|path| display_path(path)).collect());
+    }
+
+    /// Entries left out by --skip-reparse, grouped by kind
+    pub fn reparse_points(&mut self, skipped: &[(PathBuf, ReparseKind)]) {
+        if skipped.is_empty() {
+            return;
+        }
+        let kinds = skipped.iter().map(|(_, kind)| kind.label());
+        self.push(Severity::Notice, "reparse",
+                  format!("Skipped {} link(s)/reparse point(s) ({}), drop {} to include them",
+                          skipped.len(), breakdown(kinds), "--skip-reparse".blue()),
+                  skipped.iter().map(|(path, kind)| format!("{} ({})", display_path(path), kind.label())).collect());
+    }
+
+    /// Directories whose entries past max_entries_per_dir were never read
+    pub fn capped_dirs(&mut self, capped: &[PathBuf], cap: Option<usize>) {
+        let (Some(cap), false) = (cap, capped.is_empty()) else {
+            return;
+        };
+        self.push(Severity::Warning, "dir cap",
+                  format!("Stopped reading {} director{} after {} entries (max_entries_per_dir), use {} to read them fully",
+                          capped.len(), if capped.len() == 1 { "y" } else { "ies" }, cap, "--no-dir-cap".blue()),
+                  capped.iter().map(|path| display_path(path)).collect());
+    }
+
+    /// Directories skipped for holding a CACHEDIR.TAG
+    pub fn cache_dirs(&mut self, skipped: &[PathBuf]) {
+        if skipped.is_empty() {
+            return;
+        }
+        self.push(Severity::Notice, "caches",
+                  format!("Skipped {} cache director{} (CACHEDIR.TAG), use {} to search them",
+                          skipped.len(), if skipped.len() == 1 { "y" } else { "ies" }, "--include-caches".blue()),
+                  skipped.iter().map(|path| display_path(path)).collect());
+    }
+
+    /// Entries skipped for being listed in .gitignore, .ignore, .ffignore or git's excludes
+    pub fn ignored_entries(&mut self, skipped: &[PathBuf]) {
+        if skipped.is_empty() {
+            return;
+        }
+        self.push(Severity::Notice, "ignore files",
+                  format!("Skipped {} entr{} listed in ignore files (.gitignore, .ignore, .ffignore), use {} to search them",
+                          skipped.len(), if skipped.len() == 1 { "y" } else { "ies" }, "--no-ignore".blue()),
+                  skipped.iter().map(|path| display_path(path)).collect());
+    }
+
+    /// Directories skipped because they looked like build or dependency output
+    pub fn build_dirs(&mut self, skipped: &[(PathBuf, &'static str)]) {
+        if skipped.is_empty() {
+            return;
+        }
+        self.push(Severity::Notice, "build dirs",
+                  format!("Skipped {} director{} with build output markers, search one with {} or set heuristic_ignores to false",
+                          skipped.len(), if skipped.len() == 1 { "y" } else { "ies" }, "--include-dir NAME".blue()),
+                  skipped.iter().map(|(path, reason)| format!("{} ({})", display_path(path), reason)).collect());
+    }
+
+    /// Directories reached again through a symlink while following them
+    pub fn symlink_loops(&mut self, skipped: &[PathBuf]) {
+        if skipped.is_empty() {
+            return;
+        }
+        self.push(Severity::Notice, "symlinks",
+                  format!("Skipped {} symlinked director{} already searched through another path",
+                          skipped.len(), if skipped.len() == 1 { "y" } else { "ies" }),
+                  skipped.iter().map(|path| display_path(path)).collect());
+    }
+
+    pub fn file_cap(&mut self, max_files: usize) {
+        self.push(Severity::Warning, "limit",
+                  format!("Stopped after {} entries (max_files_per_search), raise it with {}", max_files, "--max-files".blue()),
+                  Vec::new());
+    }
+
+    /// The parallel walk stopped because its path list reached max_memory_mb
+    pub fn memory_budget(&mut self, entries: usize, budget: u64) {
+        self.push(Severity::Warning, "memory",
+                  format!("Stopped walking after {} entries, the path list reached the {} budget (max_memory_mb)",
+                          entries, format_size(budget)),
+                  Vec::new());
+    }
+
+    pub fn skipped_placeholders(&mut self, count: usize) {
+        if count > 0 {
+            self.push(Severity::Notice, "cloud",
+                      format!("Skipped content search in {} cloud-only file(s), use {} to search them", count, "--hydrate".blue()),
+                      Vec::new());
+        }
+    }
+
+    /// Files cut short by max_content_bytes_per_file, and files skipped once max_total_content_bytes ran out
+    pub fn content_budget(&mut self, truncated: usize, per_file: u64, skipped: usize, total: u64) {
+        if truncated > 0 {
+            self.push(Severity::Notice, "budget",
+                      format!("{} large file(s) were only content-searched up to {} (max_content_bytes_per_file)",
+                              truncated, format_size(per_file)),
+                      Vec::new());
+        }
+        if skipped > 0 {
+            self.push(Severity::Warning, "budget",
+                      format!("Content budget of {} used up, {} file(s) were not content-searched (max_total_content_bytes)",
+                              format_size(total), skipped),
+                      Vec::new());
+        }
+    }
+
+    /// Files whose content couldn't be read, grouped by reason
+    pub fn unreadable_files(&mut self, files: &mut [(PathBuf, String)]) {
+        if files.is_empty() {
+            return;
+        }
+        files.sort();
+        self.denied.extend(files.iter().filter(|(_, reason)| reason == "permission denied").map(|(path, _)| path.clone()));
+        let details = files.iter().map(|(path, reason)| format!("{} ({})", display_path(path), reason)).collect();
+        let reasons = files.iter().map(|(_, reason)| reason.as_str());
+        self.push(Severity::Error, "unreadable",
+                  format!("{} file(s) unreadable ({})", files.len(), breakdown(reasons)),
+                  details);
+    }
+
+    /// Paths that couldn't be read for lack of permission, for the elevation hint
+    pub fn denied_paths(&self) -> Vec<PathBuf> {
+        self.denied.clone()
+    }
+
+    /// Fold the walk errors into issues and put the most severe first
+    fn finish(&mut self) {
+        let mut walk_errors: Vec<_> = std::mem::take(&mut self.walk_errors).into_iter().collect();
+        walk_errors.sort();
+        for (kind, messages) in walk_errors {
+            let severity = if kind == "symlink cycle" { Severity::Notice } else { Severity::Warning };
+            let summary = format!("{} entr{} not walked ({})",
+                                  messages.len(), if messages.len() == 1 { "y" } else { "ies" }, kind);
+            self.push(severity, "walk", summary, messages);
+        }
+        self.issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+    }
+
+    /// One plain line per issue, "error unreadable: 2 file(s) unreadable (...)", for --report
+    pub fn summary_lines(&mut self) -> Vec<String> {
+        self.finish();
+        self.issues.iter().map(|issue| format!("{} {}: {}", issue.severity.label(), issue.category, issue.summary)).collect()
+    }
+
+    /// Print the summary, most severe first. Verbose mode also lists the affected paths.
+    pub fn print(mut self, verbose: bool) {
+        self.finish();
+        if self.issues.is_empty() {
+            return;
+        }
+
+        eprintln!();
+        eprintln!("{}  {} issue(s) during the search:", "⚠️".yellow(), self.issues.len());
+        let mut hidden_details = false;
+        for issue in &self.issues {
+            eprintln!("   [{:<6}] {:<10} {}", issue.severity.tag(), issue.category.dimmed(), issue.summary);
+            if verbose {
+                for detail in &issue.details {
+                    eprintln!("      {}", detail.dimmed());
+                }
+            } else {
+                hidden_details |= !issue.details.is_empty();
+            }
+        }
+        if hidden_details {
+            eprintln!("   Run with {} to list the affected paths", "--verbose".blue());
+        }
+    }
+}
+
+/// The path a walker error is about, when the backend reported one
+fn error_path(error: &io::Error) -> Option<PathBuf> {
+    let inner = error.get_ref()?;
+    if let Some(e) = inner.downcast_ref::<walkdir::Error>() {
+        return e.path().map(Path::to_path_buf);
+    }
+    inner.downcast_ref::<ignore::Error>().and_then(ignore_error_path)
+}
+
+fn ignore_error_path(error: &ignore::Error) -> Option<PathBuf> {
+    match error {
+        ignore::Error::WithPath { path, .. } => Some(path.clone()),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => ignore_error_path(err),
+        _ => None,
+    }
+}
+
+/// "3 permission denied, 1 not valid UTF-8", most common first
+fn breakdown<'a>(reasons: impl Iterator<Item = &'a str>) -> String {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for reason in reasons {
+        *counts.entry(reason).or_default() += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    counts
+        .iter()
+        .map(|(reason, count)| format!("{} {}", count, reason))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// A directory to probe, and where to report that it answered
+type Probe = (PathBuf, Sender<()>);
+
+/// Probes directories on helper threads before the walker descends into them,
+/// so a dead network mount or a cloud placeholder folder can't hang the whole search.
+/// Only the ignore backend can use it: walkdir opens a directory before anyone sees it.
+/// Each check waits on its own reply, so parallel walker threads never queue behind one another.
+pub struct StallGuard {
+    timeout: Duration,
+    requests: Sender<Probe>,
+    queue: Arc<Mutex<Receiver<Probe>>>,
+    idle: Arc<AtomicUsize>,  // Probe threads waiting for work, one more is started when none are
+    abandoned: Mutex<Vec<PathBuf>>,
+}
+
+impl StallGuard {
+    pub fn new(timeout: Duration) -> Self {
+        let (requests, queue) = mpsc::channel();
+        Self {
+            timeout,
+            requests,
+            queue: Arc::new(Mutex::new(queue)),
+            idle: Arc::new(AtomicUsize::new(0)),
+            abandoned: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns false (and remembers the path) if the directory didn't respond in time
+    pub fn is_responsive(&self, dir: &Path) -> bool {
+        if self.idle.load(Ordering::Acquire) == 0 {
+            self.spawn_prober();
+        }
+        let (reply_tx, reply_rx) = mpsc::channel();
+        if self.requests.send((dir.to_path_buf(), reply_tx)).is_err() {
+            return true;
+        }
+
+        match reply_rx.recv_timeout(self.timeout) {
+            Ok(()) => true,
+            Err(RecvTimeoutError::Timeout) => {
+                self.abandoned.lock().unwrap().push(dir.to_path_buf());
+                false
+            }
+            Err(RecvTimeoutError::Disconnected) => true,
+        }
+    }
+
+    fn spawn_prober(&self) {
+        let queue = Arc::clone(&self.queue);
+        let idle = Arc::clone(&self.idle);
+        idle.fetch_add(1, Ordering::AcqRel);
+
+        thread::Builder::new()
+            .name("ff-dir-probe".to_string())
+            .spawn(move || {
+                loop {
+                    // The lock is only held while waiting for a request, never during a probe
+                    let request = queue.lock().unwrap().recv();
+                    let Ok((path, reply)) = request else { break };
+                    idle.fetch_sub(1, Ordering::AcqRel);
+                    // Opening the directory and pulling one entry is where dead mounts block
+                    let _ = std::fs::read_dir(&path).map(|mut entries| entries.next());
+                    // Nobody waits for a probe that timed out. Its thread was counted as busy
+                    // all along, so the pool already grew past it, and it retires.
+                    if reply.send(()).is_err() {
+                        break;
+                    }
+                    idle.fetch_add(1, Ordering::AcqRel);
+                }
+            })
+            .expect("Error spawning directory probe thread");
+    }
+
+    pub fn abandoned(&self) -> Vec<PathBuf> {
+        self.abandoned.lock().unwrap().clone()
+    }
+}